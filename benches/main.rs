@@ -235,5 +235,42 @@ fn remove_edge(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, add_node, remove_node, add_edge, remove_edge);
+fn add_bulk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Bulk+");
+
+    for side_length in (0..=100).step_by(25) {
+        group.throughput(Throughput::Elements((side_length * side_length) as u64));
+        group.bench_with_input::<_, _, usize>(
+            BenchmarkId::from_parameter(side_length * side_length),
+            &side_length,
+            |b, side_length| {
+                b.iter(|| {
+                    // Same complete graph as CompleteGraph but added using the bulk methods
+                    let node_count = side_length * side_length;
+                    let mut g =
+                        TestGraph::with_capacity(Default::default(), node_count, node_count);
+                    g.add_nodes((0..node_count).map(|i| (i, i)), false)?;
+
+                    let edges = (0..*side_length)
+                        .flat_map(|x| (0..*side_length).map(move |y| (x, y)))
+                        .filter(|(x, y)| x != y)
+                        .map(|(x, y)| (x, y, (x + y * side_length, 0)));
+                    g.add_edges(edges, false)?;
+
+                    TestResult::Ok(g)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    add_node,
+    remove_node,
+    add_edge,
+    remove_edge,
+    add_bulk
+);
 criterion_main!(benches);
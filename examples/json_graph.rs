@@ -171,7 +171,7 @@ impl SchemaExt<WeightId, WeightId> for JsonSchema {
     /// Only let edges in the whitelist through
     ///
     /// This could be modified to also check for quantity by returning
-    /// DisAllowedEdge::ToMany if the count exceeds a specified amount
+    /// DisAllowedEdge::ToManyOutgoing or DisAllowedEdge::ToManyIncoming if the count exceeds a specified amount
     fn allow_edge(
         &self,
         _outgoing_edge_count: usize, 
@@ -350,7 +350,8 @@ fn main() -> SchemaResult<(), WeightId, WeightId, JsonSchema> {
             visited.insert(front_id, distance);
 
             // here we can focus on writing the implementation instead of having to bother with directions
-            for edge in g.get_incoming_and_outgoing(front_id).unwrap() {
+            // get_all_edges only returns self loops once
+            for edge in g.get_all_edges(front_id).unwrap() {
                 front.push((edge.get_outer(), distance + 1));
             }
         }
@@ -3,9 +3,10 @@
 ///! Everything is handled at compile time meaning the schema does not store any data
 ///! This makes it very safe to use as we are guaranteed to know which types of edges and nodes will be in the graph
 ///!
-///! This example creates make_node_structand make_edge_struct which autogenerating NodeType, EdgeType, Node, Edge and a type for every kind of node and edge
+///! This example uses the schema macro to create the schema along with NodeType, EdgeType, Node and Edge
+///! The GraphNode and GraphEdge derives then implement the traits needed by a type for every kind of node and edge
 ///!
-///! The Node and Edge are then used by the schema to define a set of rules for the graph
+///! The Node and Edge are used by the schema to define a set of rules for the graph
 ///!
 ///! The schema creates the graph
 ///! ```
@@ -15,403 +16,77 @@
 ///!  A ---> B ---> C
 ///!     AB    BC
 ///! ```
-use std::fmt::Display;
-
 use typed_graph::{
-    DisAllowedEdge, Downcast, DowncastMut, EdgeExt, Id, Key, NodeExt, SchemaExt, SchemaResult,
-    ToGraphWalker, Typed, TypedError, TypedGraph,
+    schema, Downcast, GraphEdge, GraphNode, Id, Key, SchemaExt, SchemaResult, ToGraphWalker, Typed,
+    TypedGraph,
 };
 
-/// Create NodeType, Node and a type for each NodeType
-macro_rules! make_node_struct {
-    ($($name:ident $({$($field_name:ident : $field_type:ty),*})?),*) => {
-        // Create a type for referencing nodes
-        #[derive(PartialEq, Clone, Copy, Debug)]
-        pub enum NodeType {
-            $(
-                $name
-            ),*
-        }
-
-        impl Display for NodeType {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, "{:?}", self)
-            }
-        }
-
-        // Create the individual nodes
-        $(
-            #[derive(Debug, Clone)]
-            pub struct $name<K: Key> {
-                id: K,
-                $(
-                    $(
-                        pub $field_name: $field_type
-                    ),*
-                )?
-            }
-
-            impl<K: Key> $name<K> {
-                fn new(
-                    id: K,
-                    $(
-                        $(
-                            $field_name: $field_type
-                        ),*
-                    )?
-                ) -> Self {
-                    Self {
-                        id,
-                        $(
-                            $(
-                                $field_name
-                            ),*
-                        )?
-                    }
-                }
-            }
-
-            impl<K: Key> Id<K> for $name<K> {
-                fn get_id(&self) -> K {
-                    self.id
-                }
-
-                fn set_id(&mut self, new_id: K) {
-                    self.id = new_id
-                }
-            }
-
-            impl<K: Key> Typed for $name<K> {
-                type Type = NodeType;
-
-                fn get_type(&self) -> Self::Type {
-                    NodeType::$name
-                }
-            }
-
-            impl<K: Key> PartialEq<NodeType> for $name<K> {
-                fn eq(&self, other: &NodeType) -> bool {
-                    other == &NodeType::$name
-                }
-            }
-
-            impl<K: Key> From<$name<K>> for Node<K> {
-                fn from(a: $name<K>) -> Self {
-                    Node::$name(a)
-                }
-            }
-
-            impl<'b, NK, EK, S> DowncastMut<'b, NK, EK, &'b mut $name<EK>, S> for Node<EK>
-            where
-                NK: Key,
-                EK: Key,
-                S: SchemaExt<NK, EK, N = Node<EK>>
-            {
-                fn downcast_mut<'a: 'b>(&'a mut self) -> SchemaResult<&'b mut $name<EK>, NK, EK, S> {
-                    match self {
-                        Node::$name(e) => Ok(e),
-                        e => Err(TypedError::DownCastFailed(
-                            stringify!($name).to_string(),
-                            e.get_type().to_string())
-                        )
-                    }
-                }
-            }
-
-            impl<'b, NK, EK, S> Downcast<'b, NK, EK, &'b $name<EK>, S> for Node<EK>
-            where
-                NK: Key,
-                EK: Key,
-                S: SchemaExt<NK, EK, N = Node<EK>>
-            {
-                fn downcast<'a: 'b>(&'a self) -> SchemaResult<&'b $name<EK>, NK, EK, S> {
-                    match self {
-                        Node::$name(e) => Ok(e),
-                        e => Err(TypedError::DownCastFailed(
-                            stringify!($name).to_string(),
-                            e.get_type().to_string())
-                        )
-                    }
-                }
-            }
-
-            impl<K: Key> NodeExt<K> for $name<K> {}
-        )*
-
-        // Create a contianer for all the nodes
-        #[derive(Debug, Clone)]
-        pub enum Node<K: Key> {
-            $(
-                $name($name<K>)
-            ),*
-        }
-
-        impl<K: Key> Id<K> for Node<K> {
-            fn get_id(&self) -> K {
-                match self {
-                    $(
-                        Node::$name(v) => v.get_id()
-                    ),*
-                }
-            }
-
-            fn set_id(&mut self, new_id: K) {
-                match self {
-                    $(
-                        Node::$name(v) => v.set_id(new_id)
-                    ),*
-                }
-            }
-        }
-
-        impl<K: Key> Typed for Node<K> {
-            type Type = NodeType;
-
-            fn get_type(&self) -> Self::Type {
-                match self {
-                    $(
-                        Node::$name(v) => v.get_type()
-                    ),*
-                }
-            }
-        }
-
-        impl<K: Key> PartialEq<NodeType> for Node<K> {
-            fn eq(&self, other: &NodeType) -> bool {
-                match self {
-                    $(
-                        Node::$name(v) => v.eq(other)
-                    ),*
-                }
-            }
-        }
-
-        impl<K: Key> NodeExt<K> for Node<K> {}
-    };
+// Create the schema along with a type for referencing nodes and edges
+// and a container for all the nodes and edges
+// Only the edges listed in the rules are allowed
+schema! {
+    pub struct Schema {
+        nodes: Node<K> {
+            A(A<K>),
+            B(B<K>),
+            C(C<K>),
+        },
+        edges: Edge<K> {
+            AB(AB<K>),
+            BC(BC<K>),
+            CA(CA<K>),
+        },
+        rules: {
+            A -> AB -> B,
+            B -> BC -> C,
+            C -> CA -> A,
+        },
+    }
 }
 
-/// Create EdgeType, Edge and a type for each EdgeType
-macro_rules! make_edge_struct {
-    ($($name:ident $({$($field_name:ident : $field_type:ty),*})?),*) => {
-        // Create a type for referencing edges
-        #[derive(PartialEq, Clone, Copy, Debug)]
-        pub enum EdgeType {
-            $(
-                $name
-            ),*
-        }
-
-        impl Display for EdgeType {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, "{:?}", self)
-            }
-        }
-
-        // Create the individual edges
-        $(
-            #[derive(Debug, Clone)]
-            pub struct $name<K: Key> {
-                id: K,
-                $(
-                    $(
-                        pub $field_name: $field_type
-                    ),*
-                )?
-            }
-
-            impl<K: Key> $name<K> {
-                fn new(
-                    id: K,
-                    $(
-                        $(
-                            $field_name: $field_type
-                        ),*
-                    )?
-                ) -> Self {
-                    Self {
-                        id,
-                        $(
-                            $(
-                                $field_name
-                            ),*
-                        )?
-                    }
-                }
-            }
-
-            impl<K: Key> Id<K> for $name<K> {
-                fn get_id(&self) -> K {
-                    self.id
-                }
-
-                fn set_id(&mut self, new_id: K) {
-                    self.id = new_id;
-                }
-            }
-
-            impl<K: Key> Typed for $name<K> {
-                type Type = EdgeType;
-
-                fn get_type(&self) -> Self::Type {
-                    EdgeType::$name
-                }
-            }
-
-            impl<K: Key> PartialEq<EdgeType> for $name<K> {
-                fn eq(&self, other: &EdgeType) -> bool {
-                    other == &EdgeType::$name
-                }
-            }
-
-            impl<K: Key> From<$name<K>> for Edge<K> {
-                fn from(a: $name<K>) -> Self {
-                    Edge::$name(a)
-                }
-            }
-
-            impl<'b, NK, EK, S> DowncastMut<'b, NK, EK, &'b mut $name<EK>, S> for Edge<EK>
-            where
-                NK: Key,
-                EK: Key,
-                S: SchemaExt<NK, EK, E = Edge<EK>>
-            {
-                fn downcast_mut<'a: 'b>(&'a mut self) -> SchemaResult<&'b mut $name<EK>, NK, EK, S> {
-                    match self {
-                        Edge::$name(e) => Ok(e),
-                        e => Err(TypedError::DownCastFailed(
-                            stringify!($name).to_string(),
-                            e.get_type().to_string())
-                        )
-                    }
-                }
-            }
-
-            impl<'b, NK, EK, S> Downcast<'b, NK, EK, &'b $name<EK>, S> for Edge<EK>
-            where
-                NK: Key,
-                EK: Key,
-                S: SchemaExt<NK, EK, E = Edge<EK>>
-            {
-                fn downcast<'a: 'b>(&'a self) -> SchemaResult<&'b $name<EK>, NK, EK, S> {
-                    match self {
-                        Edge::$name(e) => Ok(e),
-                        e => Err(TypedError::DownCastFailed(
-                            stringify!($name).to_string(),
-                            e.get_type().to_string())
-                        )
-                    }
-                }
-            }
-
-            impl<K: Key> EdgeExt<K> for $name<K> {}
-        )*
-
-        // Create a container for all the edges
-        #[derive(Debug, Clone)]
-        pub enum Edge<K: Key> {
-            $(
-                $name($name<K>)
-            ),*
-        }
-
-        impl<K: Key> Id<K> for Edge<K> {
-            fn get_id(&self) -> K {
-                match self {
-                    $(
-                        Edge::$name(v) => v.get_id()
-                    ),*
-                }
-            }
-
-            fn set_id(&mut self, new_id: K) {
-                match self {
-                    $(
-                        Edge::$name(v) => v.set_id(new_id)
-                    ),*
-                }
-            }
-        }
-
-        impl<K: Key> Typed for Edge<K> {
-            type Type = EdgeType;
-
-            fn get_type(&self) -> Self::Type {
-                match self {
-                    $(
-                        Edge::$name(v) => v.get_type()
-                    ),*
-                }
-            }
-        }
-
-        impl<K: Key> PartialEq<EdgeType> for Edge<K> {
-            fn eq(&self, other: &EdgeType) -> bool {
-                match self {
-                    $(
-                        Edge::$name(v) => v.eq(other)
-                    ),*
-                }
-            }
-        }
-
-        impl<K: Key> EdgeExt<K> for Edge<K> {}
-    };
+// Create the individual nodes
+// The derive implements Id using the id field and Typed using the given variant
+#[derive(Debug, Clone, GraphNode)]
+#[graph(id = id, type = NodeType::A)]
+pub struct A<K: Key> {
+    id: K,
+    pub name: String,
 }
 
-make_node_struct! {
-    A {
-        name: String
-    },
-    B {
-        name: String
-    },
-    C {
-        name: String
-    }
+#[derive(Debug, Clone, GraphNode)]
+#[graph(type = NodeType::B)]
+pub struct B<K: Key> {
+    id: K,
+    pub name: String,
 }
 
-make_edge_struct! {
-    AB {
-        distance: usize
-    },
-    BC {
-        distance: usize
-    },
-    CA {
-        distance: usize
-    }
+#[derive(Debug, Clone, GraphNode)]
+#[graph(type = NodeType::C)]
+pub struct C<K: Key> {
+    id: K,
+    pub name: String,
 }
 
-#[derive(Default)]
-pub struct Schema {}
-
-impl<NK: Key, EK: Key> SchemaExt<NK, EK> for Schema {
-    type N = Node<NK>;
-    type E = Edge<EK>;
-
-    fn name(&self) -> String {
-        "Schema".to_string()
-    }
+// Create the individual edges
+#[derive(Debug, Clone, GraphEdge)]
+#[graph(type = EdgeType::AB)]
+pub struct AB<K: Key> {
+    id: K,
+    pub distance: usize,
+}
 
-    fn allow_node(&self, _node_ty: NodeType) -> Result<(), typed_graph::DisAllowedNode> {
-        Ok(())
-    }
+#[derive(Debug, Clone, GraphEdge)]
+#[graph(type = EdgeType::BC)]
+pub struct BC<K: Key> {
+    id: K,
+    pub distance: usize,
+}
 
-    fn allow_edge(
-        &self,
-        _outgoing_edge_count: usize, 
-        _incoming_edge_count: usize, 
-        edge_ty: EdgeType,
-        source: NodeType,
-        target: NodeType,
-    ) -> Result<(), typed_graph::DisAllowedEdge> {
-        match (source, target, edge_ty) {
-            (NodeType::A, NodeType::B, EdgeType::AB)
-            | (NodeType::B, NodeType::C, EdgeType::BC)
-            | (NodeType::C, NodeType::A, EdgeType::CA) => Ok(()),
-            _ => Err(DisAllowedEdge::InvalidType),
-        }
-    }
+#[derive(Debug, Clone, GraphEdge)]
+#[graph(type = EdgeType::CA)]
+pub struct CA<K: Key> {
+    id: K,
+    pub distance: usize,
 }
 
 type Graph<NK, EK> = TypedGraph<NK, EK, Schema>;
@@ -426,16 +101,32 @@ fn main() -> SchemaResult<(), usize, usize, Schema> {
     // For this example we will be working with double usize
     let mut g: Graph<usize, usize> = Graph::default();
 
-    let a_id = g.add_node(A::new(0, "Stop A".to_string()))?;
-    let b_id = g.add_node(B::new(1, "Stop B".to_string()))?;
-    let c_id = g.add_node(C::new(2, "Stop C".to_string()))?;
-
-    let ab_id = g.add_edge(a_id, b_id, AB::new(0, 10))?;
-    let bc_id = g.add_edge(b_id, c_id, BC::new(1, 5))?;
-    let ca_id = g.add_edge(c_id, a_id, CA::new(2, 1))?;
+    let a_id = g.add_node(A {
+        id: 0,
+        name: "Stop A".to_string(),
+    })?;
+    let b_id = g.add_node(B {
+        id: 1,
+        name: "Stop B".to_string(),
+    })?;
+    let c_id = g.add_node(C {
+        id: 2,
+        name: "Stop C".to_string(),
+    })?;
+
+    let ab_id = g.add_edge(
+        a_id,
+        b_id,
+        AB {
+            id: 0,
+            distance: 10,
+        },
+    )?;
+    let bc_id = g.add_edge(b_id, c_id, BC { id: 1, distance: 5 })?;
+    let ca_id = g.add_edge(c_id, a_id, CA { id: 2, distance: 1 })?;
 
     // We cannot create an instance of AB between C -> A since the schema only allows for AB edges to be between A -> B
-    let e = g.add_edge(c_id, a_id, AB::new(0, 3));
+    let e = g.add_edge(c_id, a_id, AB { id: 0, distance: 3 });
     assert!(e.is_err());
 
     // If we want to retrieve data from the graph
@@ -468,6 +159,14 @@ fn main() -> SchemaResult<(), usize, usize, Schema> {
     println!("BC distance = {}", bc.distance);
     println!("CA distance = {}", ca.distance);
 
+    // Since AB edges always go from A to B, the edge and both of its endpoints can be retrieved at once
+    let (ab, a, b): (&AB<_>, &A<_>, &B<_>) = g.get_edge_typed(ab_id)?;
+    println!("{} -> {} distance = {}", a.name, b.name, ab.distance);
+
+    // This will fail if any of the three has the wrong type
+    let e = g.get_edge_typed::<&AB<_>, &B<_>, &A<_>>(ab_id);
+    assert!(e.is_err());
+
     // We can now traverse the graph to calculate the total distance between all the nodes
 
     /// Function to retrieve the next node in the chain
@@ -502,11 +201,11 @@ fn main() -> SchemaResult<(), usize, usize, Schema> {
                 acc
             },
         )
-        .progress_with_state(get_connected_node::<AB<_>>, |mut acc, nc| {
+        .progress_with_state(get_connected_node::<BC<_>>, |mut acc, nc| {
             acc += nc.distance;
             acc
         })
-        .progress_with_state(get_connected_node::<AB<_>>, |mut acc, nc| {
+        .progress_with_state(get_connected_node::<CA<_>>, |mut acc, nc| {
             acc += nc.distance;
             acc
         })
@@ -1,6 +1,6 @@
 use std::any::type_name;
 
-use crate::{Downcast, Key, SchemaError, SchemaExt, SchemaResult, Typed};
+use crate::{Downcast, DowncastMut, Key, SchemaError, SchemaExt, SchemaResult, Typed};
 
 macro_rules! any_of_impl {
     ($($name:ident = $($v:ident($g:ident)),*;)*) => {$(
@@ -43,6 +43,41 @@ macro_rules! any_of_impl {
                 )
             }
         }
+
+        impl<'a, NK, EK, S, T, $($g),*> DowncastMut<'a, NK, EK, $name<$(&'a mut $g),*>, S> for T
+        where
+            T: Typed $( + for<'c> DowncastMut<'c, NK, EK, &'c mut $g, S>)*,
+            NK: Key,
+            EK: Key,
+            S: SchemaExt<NK, EK>,
+            $(
+                $g: Typed
+            ),*
+        {
+            fn downcast_mut<'b: 'a>(&'b mut self) -> SchemaResult<$name<$(&'a mut $g),*>, NK, EK, S> {
+                // A failed downcast keeps self borrowed for the rest of the function
+                // So each type is first tried using a short lived borrow
+                $(
+                    let is_type = DowncastMut::<'_, NK, EK, &mut $g, S>::downcast_mut(&mut *self).is_ok();
+
+                    if is_type {
+                        return DowncastMut::<'a, NK, EK, &'a mut $g, S>::downcast_mut(self).map($name::$v);
+                    }
+
+                )*
+
+                let type_names = &[
+                    $(
+                        stringify!($g)
+                    ),*
+                ];
+
+                Err(SchemaError::<NK, EK, S>::DownCastFailed(
+                    format!("Either<{}>", type_names.join(", ")),
+                    self.get_type().to_string())
+                )
+            }
+        }
     )*};
 }
 
@@ -56,4 +91,46 @@ any_of_impl!{
     Either8 = One(T1), Two(T2), Three(T3), Four(T4), Five(T5), Six(T6), Seven(T7), Eight(T8);
     Either9 = One(T1), Two(T2), Three(T3), Four(T4), Five(T5), Six(T6), Seven(T7), Eight(T8), Nine(T9);
     Either10 = One(T1), Two(T2), Three(T3), Four(T4), Five(T5), Six(T6), Seven(T7), Eight(T8), Nine(T9), Ten(T10);
-}
\ No newline at end of file
+}
+#[test]
+fn either_downcast_mut_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = VariantTestGraph::default();
+    g.add_node(VariantNode::Number(NumberNode { id: 0, value: 1 }))?;
+    g.add_node(VariantNode::Name(NameNode {
+        id: 1,
+        name: "a".to_string(),
+    }))?;
+
+    for node_id in [0, 1] {
+        match g.get_node_downcast_mut::<Either2<&mut NumberNode, &mut NameNode>>(node_id)? {
+            Either2::One(n) => n.value += 1,
+            Either2::Two(n) => n.name.push('b'),
+        }
+    }
+
+    let number: &NumberNode = g.get_node_downcast(0)?;
+    assert_eq!(number.value, 2);
+    let name: &NameNode = g.get_node_downcast(1)?;
+    assert_eq!(name.name, "ab");
+
+    // The order of the types does not matter
+    let n = g.get_node_downcast_mut::<Either2<&mut NameNode, &mut NumberNode>>(0)?;
+    assert!(matches!(n, Either2::Two(NumberNode { value: 2, .. })));
+
+    // A node matching none of the types fails
+    let n = g.get_node_downcast_mut::<Either2<&mut NumberNode, &mut NumberNode>>(1);
+    assert!(n.is_err());
+
+    // The same works for the Either from the either crate
+    if let either::Either::Right(n) =
+        g.get_node_downcast_mut::<either::Either<&mut NumberNode, &mut NameNode>>(1)?
+    {
+        n.name.push('c');
+    }
+    let name: &NameNode = g.get_node_downcast(1)?;
+    assert_eq!(name.name, "abc");
+
+    Ok(())
+}
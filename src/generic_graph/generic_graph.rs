@@ -3,9 +3,13 @@ use crate::{
     DisAllowedEdge, DisAllowedNode, EdgeExt, Id, Key, NodeExt, SchemaExt, SchemaResult,
     TypeIdentifier, Typed, TypedGraph,
 };
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
+use thiserror::Error;
 
 // Define a node and edge type
 pub type GenericNode<K, T> = GenericWeight<K, T>;
@@ -25,19 +29,133 @@ pub trait GenericTypeIdentifier: TypeIdentifier + Eq + Hash {}
 
 impl<T> GenericTypeIdentifier for T where T: TypeIdentifier + Eq + Hash {}
 
+/// An edge type together with the types of the source and target it goes between
+///
+/// Endpoint rules in the GenericSchema are stored as (source, target, edge)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint<NT, ET> {
+    pub source: NT,
+    pub target: NT,
+    pub edge: ET,
+}
+
+impl<NT, ET> Endpoint<NT, ET> {
+    pub fn new(source: NT, target: NT, edge: ET) -> Self {
+        Endpoint {
+            source,
+            target,
+            edge,
+        }
+    }
+}
+
+impl<NT, ET> From<(NT, NT, ET)> for Endpoint<NT, ET> {
+    fn from((source, target, edge): (NT, NT, ET)) -> Self {
+        Endpoint::new(source, target, edge)
+    }
+}
+
+impl<NT, ET> From<Endpoint<NT, ET>> for (NT, NT, ET) {
+    fn from(endpoint: Endpoint<NT, ET>) -> Self {
+        (endpoint.source, endpoint.target, endpoint.edge)
+    }
+}
+
 /// Schema capable of controlling all aspects of the graph
 ///
 /// The schema is build
-#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GenericSchema<NT: GenericTypeIdentifier, ET: GenericTypeIdentifier> {
     node_whitelist: Option<Vec<NT>>,
     node_blacklist: Option<Vec<NT>>,
     edge_whitelist: Option<Vec<ET>>,
     edge_blacklist: Option<Vec<ET>>,
+    /// (source NodeType, target NodeType, EdgeType)
     endpoint_whitelist: Option<Vec<(NT, NT, ET)>>,
+    /// (source NodeType, target NodeType, EdgeType)
     endpoint_blacklist: Option<Vec<(NT, NT, ET)>>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_quantities"))]
     endpoint_outgoing_max_quantity: Option<HashMap<(NT, ET), usize>>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_quantities"))]
     endpoint_incoming_max_quantity: Option<HashMap<(NT, ET), usize>>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_quantities"))]
+    endpoint_outgoing_min_quantity: Option<HashMap<(NT, ET), usize>>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_quantities"))]
+    endpoint_incoming_min_quantity: Option<HashMap<(NT, ET), usize>>,
+    #[cfg_attr(feature = "serde", serde(default = "default_allow_self_loops"))]
+    allow_self_loops: bool,
+}
+
+#[cfg(feature = "serde")]
+fn default_allow_self_loops() -> bool {
+    true
+}
+
+/// Serialize the quantities sorted by their key
+///
+/// This keeps the output the same between runs, as the order of a HashMap is random.
+/// The types are not required to be Ord, so the keys are sorted by their debug representation
+#[cfg(feature = "serde")]
+fn serialize_quantities<NT, ET, Ser>(
+    quantities: &Option<HashMap<(NT, ET), usize>>,
+    serializer: Ser,
+) -> Result<Ser::Ok, Ser::Error>
+where
+    NT: GenericTypeIdentifier + Serialize,
+    ET: GenericTypeIdentifier + Serialize,
+    Ser: Serializer,
+{
+    let sorted: Option<IndexMap<_, _>> = quantities.as_ref().map(|q| {
+        let mut entries: Vec<_> = q.iter().collect();
+        entries.sort_by_cached_key(|(key, _)| format!("{:?}", key));
+        entries.into_iter().collect()
+    });
+    sorted.serialize(serializer)
+}
+
+/// Rules of a GenericSchema which contradict each other
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SchemaRuleError<NT, ET> {
+    #[error("Node type {0} is both whitelisted and blacklisted")]
+    ConflictingNodeType(NT),
+
+    #[error("Edge type {0} is both whitelisted and blacklisted")]
+    ConflictingEdgeType(ET),
+
+    #[error("Endpoint {2} from {0} to {1} is both whitelisted and blacklisted")]
+    ConflictingEndpoint(NT, NT, ET),
+
+    #[error("Endpoint {2} from {0} to {1} is whitelisted but the edge type is not allowed")]
+    EndpointEdgeNotAllowed(NT, NT, ET),
+
+    #[error("Endpoint {2} from {0} to {1} is whitelisted but its node types are not allowed")]
+    EndpointNodeNotAllowed(NT, NT, ET),
+
+    #[error("The max quantity of {1} edges on {0} is 0 so the edge can never be added")]
+    ZeroMaxQuantity(NT, ET),
+
+    #[error("The min quantity of {1} edges on {0} is larger than the max quantity")]
+    MinAboveMax(NT, ET),
+}
+
+impl<NT: GenericTypeIdentifier, ET: GenericTypeIdentifier> Default for GenericSchema<NT, ET> {
+    fn default() -> Self {
+        GenericSchema {
+            node_whitelist: None,
+            node_blacklist: None,
+            edge_whitelist: None,
+            edge_blacklist: None,
+            endpoint_whitelist: None,
+            endpoint_blacklist: None,
+            endpoint_outgoing_max_quantity: None,
+            endpoint_incoming_max_quantity: None,
+            endpoint_outgoing_min_quantity: None,
+            endpoint_incoming_min_quantity: None,
+            // Self loops are allowed unless stated otherwise
+            allow_self_loops: true,
+        }
+    }
 }
 
 impl<NT: GenericTypeIdentifier, ET: GenericTypeIdentifier> GenericSchema<NT, ET> {
@@ -73,7 +191,7 @@ impl<NT: GenericTypeIdentifier, ET: GenericTypeIdentifier> GenericSchema<NT, ET>
         self
     }
 
-    /// Edge filter: (EdgeType, NodeType, NodeType)
+    /// Edge filter: (source NodeType, target NodeType, EdgeType)
     pub fn endpoint_whitelist(
         mut self,
         edge_endpoint_whitelist: Option<Vec<(NT, NT, ET)>>,
@@ -82,12 +200,31 @@ impl<NT: GenericTypeIdentifier, ET: GenericTypeIdentifier> GenericSchema<NT, ET>
         self
     }
 
-    /// Edge filter: (EdgeType, NodeType, NodeType)
+    /// Edge filter: (source NodeType, target NodeType, EdgeType)
     pub fn endpoint_blacklist(mut self, endpoint_blacklist: Option<Vec<(NT, NT, ET)>>) -> Self {
         self.endpoint_blacklist = endpoint_blacklist;
         self
     }
 
+    /// Add a single endpoint to the whitelist
+    pub fn whitelist_endpoint(mut self, endpoint: Endpoint<NT, ET>) -> Self {
+        self.endpoint_whitelist
+            .get_or_insert_with(Vec::new)
+            .push(endpoint.into());
+        self
+    }
+
+    /// Add a single endpoint to the blacklist
+    pub fn blacklist_endpoint(mut self, endpoint: Endpoint<NT, ET>) -> Self {
+        self.endpoint_blacklist
+            .get_or_insert_with(Vec::new)
+            .push(endpoint.into());
+        self
+    }
+
+    /// Limit the number of outgoing edges: (source NodeType, EdgeType) -> max quantity
+    ///
+    /// Exceeding the limit results in DisAllowedEdge::ToManyOutgoing
     pub fn endpoint_outgoing_max_quantity(
         mut self,
         endpoint_max_quantity: Option<HashMap<(NT, ET), usize>>,
@@ -96,6 +233,9 @@ impl<NT: GenericTypeIdentifier, ET: GenericTypeIdentifier> GenericSchema<NT, ET>
         self
     }
 
+    /// Limit the number of incoming edges: (target NodeType, EdgeType) -> max quantity
+    ///
+    /// Exceeding the limit results in DisAllowedEdge::ToManyIncoming
     pub fn endpoint_incoming_max_quantity(
         mut self,
         endpoint_max_quantity: Option<HashMap<(NT, ET), usize>>,
@@ -103,6 +243,180 @@ impl<NT: GenericTypeIdentifier, ET: GenericTypeIdentifier> GenericSchema<NT, ET>
         self.endpoint_incoming_max_quantity = endpoint_max_quantity;
         self
     }
+
+    pub fn endpoint_outgoing_min_quantity(
+        mut self,
+        endpoint_min_quantity: Option<HashMap<(NT, ET), usize>>,
+    ) -> Self {
+        self.endpoint_outgoing_min_quantity = endpoint_min_quantity;
+        self
+    }
+
+    pub fn endpoint_incoming_min_quantity(
+        mut self,
+        endpoint_min_quantity: Option<HashMap<(NT, ET), usize>>,
+    ) -> Self {
+        self.endpoint_incoming_min_quantity = endpoint_min_quantity;
+        self
+    }
+
+    /// Whether edges are allowed to go from a node to itself
+    pub fn allow_self_loops(mut self, allow_self_loops: bool) -> Self {
+        self.allow_self_loops = allow_self_loops;
+        self
+    }
+
+    /// Build a schema which whitelists exactly the node types, edge types and endpoints used in the graph
+    ///
+    /// Nothing is blacklisted and no quantities are set, so the result can be used as a starting point for a stricter schema.
+    /// The graph can use any schema with the same node and edge types, such as a permissive GenericSchema used while loading
+    pub fn infer_from<NK, EK, S, H>(
+        graph: &TypedGraph<NK, EK, S, H>,
+    ) -> SchemaResult<Self, NK, EK, S>
+    where
+        NK: Key,
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+        S::N: Typed<Type = NT>,
+        S::E: Typed<Type = ET>,
+    {
+        let mut node_types = Vec::new();
+        for node in graph.nodes() {
+            push_unique(&mut node_types, node.get_type());
+        }
+
+        let mut edge_types = Vec::new();
+        let mut endpoints = Vec::new();
+        for edge in graph.try_edges_full() {
+            let edge = edge?;
+            let source = graph.get_node(edge.get_source())?.get_type();
+            let target = graph.get_node(edge.get_target())?.get_type();
+            push_unique(&mut edge_types, edge.get_type());
+            push_unique(&mut endpoints, (source, target, edge.get_type()));
+        }
+
+        Ok(GenericSchema {
+            node_whitelist: Some(node_types),
+            edge_whitelist: Some(edge_types),
+            endpoint_whitelist: Some(endpoints),
+            ..Default::default()
+        })
+    }
+
+    /// Finish building the schema and check that its rules do not contradict each other
+    pub fn validated(self) -> Result<Self, SchemaRuleError<NT, ET>> {
+        self.validate_rules()?;
+        Ok(self)
+    }
+
+    /// Check that the rules of the schema do not contradict each other
+    ///
+    /// A contradiction does not make the schema unusable, but it means some types can never be added
+    pub fn validate_rules(&self) -> Result<(), SchemaRuleError<NT, ET>> {
+        if let Some(ty) = in_both(&self.node_whitelist, &self.node_blacklist) {
+            return Err(SchemaRuleError::ConflictingNodeType(ty.clone()));
+        }
+
+        if let Some(ty) = in_both(&self.edge_whitelist, &self.edge_blacklist) {
+            return Err(SchemaRuleError::ConflictingEdgeType(ty.clone()));
+        }
+
+        if let Some((source, target, ty)) =
+            in_both(&self.endpoint_whitelist, &self.endpoint_blacklist)
+        {
+            return Err(SchemaRuleError::ConflictingEndpoint(
+                source.clone(),
+                target.clone(),
+                ty.clone(),
+            ));
+        }
+
+        let allow_node = |ty| is_allowed(&self.node_whitelist, &self.node_blacklist, ty);
+        let allow_edge = |ty| is_allowed(&self.edge_whitelist, &self.edge_blacklist, ty);
+
+        for (source, target, ty) in self.endpoint_whitelist.iter().flatten() {
+            if !allow_edge(ty) {
+                return Err(SchemaRuleError::EndpointEdgeNotAllowed(
+                    source.clone(),
+                    target.clone(),
+                    ty.clone(),
+                ));
+            }
+
+            if !allow_node(source) || !allow_node(target) {
+                return Err(SchemaRuleError::EndpointNodeNotAllowed(
+                    source.clone(),
+                    target.clone(),
+                    ty.clone(),
+                ));
+            }
+        }
+
+        let quantities = [
+            (
+                &self.endpoint_outgoing_min_quantity,
+                &self.endpoint_outgoing_max_quantity,
+            ),
+            (
+                &self.endpoint_incoming_min_quantity,
+                &self.endpoint_incoming_max_quantity,
+            ),
+        ];
+
+        for (min_quantity, max_quantity) in quantities {
+            for ((node_ty, edge_ty), max) in max_quantity.iter().flatten() {
+                if *max == 0 {
+                    return Err(SchemaRuleError::ZeroMaxQuantity(
+                        node_ty.clone(),
+                        edge_ty.clone(),
+                    ));
+                }
+
+                let min = min_quantity
+                    .as_ref()
+                    .and_then(|l| l.get(&(node_ty.clone(), edge_ty.clone())));
+                if min.is_some_and(|min| min > max) {
+                    return Err(SchemaRuleError::MinAboveMax(
+                        node_ty.clone(),
+                        edge_ty.clone(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check if the type passes both the whitelist and blacklist
+fn is_allowed<T: PartialEq>(
+    whitelist: &Option<Vec<T>>,
+    blacklist: &Option<Vec<T>>,
+    ty: &T,
+) -> bool {
+    let is_whitelisted = match whitelist {
+        Some(l) => l.contains(ty),
+        None => true,
+    };
+    let is_blacklisted = blacklist.as_ref().is_some_and(|l| l.contains(ty));
+    is_whitelisted && !is_blacklisted
+}
+
+/// Add the value to the list unless it is already there
+fn push_unique<T: PartialEq>(list: &mut Vec<T>, value: T) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+/// Find the first entry of the whitelist which is also in the blacklist
+fn in_both<'a, T: PartialEq>(
+    whitelist: &'a Option<Vec<T>>,
+    blacklist: &Option<Vec<T>>,
+) -> Option<&'a T> {
+    let blacklist = blacklist.as_ref()?;
+    whitelist.iter().flatten().find(|ty| blacklist.contains(ty))
 }
 
 impl<NK, EK, NT, ET> SchemaExt<NK, EK> for GenericSchema<NT, ET>
@@ -136,7 +450,8 @@ where
             .as_ref()
             .map_or(true, |l| !l.contains(&edge_ty));
 
-        let endpoint = (source.clone(), target.clone(), edge_ty.clone());
+        let endpoint: (NT, NT, ET) =
+            Endpoint::new(source.clone(), target.clone(), edge_ty.clone()).into();
 
         let is_endpoint_whitelist = self
             .endpoint_whitelist
@@ -193,6 +508,45 @@ where
 
         Ok(())
     }
+
+    fn allow_self_loop(
+        &self,
+        _edge_ty: <Self::E as Typed>::Type,
+        _node_ty: <Self::N as Typed>::Type,
+    ) -> bool {
+        self.allow_self_loops
+    }
+
+    fn validate_node(
+        &self,
+        node_ty: <Self::N as Typed>::Type,
+        outgoing_edges: &[<Self::E as Typed>::Type],
+        incoming_edges: &[<Self::E as Typed>::Type],
+    ) -> Result<(), <Self::E as Typed>::Type> {
+        let endpoints = [
+            (&self.endpoint_outgoing_min_quantity, outgoing_edges),
+            (&self.endpoint_incoming_min_quantity, incoming_edges),
+        ];
+
+        for (min_quantity, edges) in endpoints {
+            let Some(min_quantity) = min_quantity else {
+                continue;
+            };
+
+            for ((ty, edge_ty), quantity) in min_quantity {
+                if ty != &node_ty {
+                    continue;
+                }
+
+                let edge_count = edges.iter().filter(|ty| *ty == edge_ty).count();
+                if edge_count < *quantity {
+                    return Err(edge_ty.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<NK: Key, EK: Key, NT: GenericTypeIdentifier, ET: GenericTypeIdentifier>
@@ -239,7 +593,7 @@ impl<NK: Key, EK: Key, NT: GenericTypeIdentifier, ET: GenericTypeIdentifier>
         }
 
         for node in self.node_ids() {
-            let edges = self.get_outgoing(node)?;
+            let edges = self.get_outgoing(node.clone())?;
             let other_edges = other.get_outgoing(node)?;
 
             let zipped = edges.zip(other_edges);
@@ -252,3 +606,188 @@ impl<NK: Key, EK: Key, NT: GenericTypeIdentifier, ET: GenericTypeIdentifier>
         Ok(())
     }
 }
+
+#[test]
+fn generic_schema_max_quantity_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use crate::{DisAllowedEdge, TypedError};
+
+    let schema = TestSchema::new()
+        .endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 0), 1)])))
+        .endpoint_incoming_max_quantity(Some(HashMap::from([((0, 1), 1)])));
+    let mut g = TestGraph::new(schema);
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+
+    // Each limit is reported using the direction which was exceeded
+    g.add_edge(0, 1, (0, 0))?;
+    let result = g.add_edge(0, 2, (1, 0));
+    assert!(matches!(
+        result,
+        Err(TypedError::InvalidEdgeType(
+            _,
+            _,
+            _,
+            DisAllowedEdge::ToManyOutgoing,
+            _
+        ))
+    ));
+
+    g.add_edge(1, 0, (2, 1))?;
+    let result = g.add_edge(2, 0, (3, 1));
+    assert!(matches!(
+        result,
+        Err(TypedError::InvalidEdgeType(
+            _,
+            _,
+            _,
+            DisAllowedEdge::ToManyIncoming,
+            _
+        ))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn generic_schema_validate_rules_test() {
+    use crate::test::*;
+
+    assert_eq!(TestSchema::new().validate_rules(), Ok(()));
+
+    let schema = TestSchema::new()
+        .node_whitelist(Some(vec![0, 1]))
+        .node_blacklist(Some(vec![2, 1]));
+    assert_eq!(
+        schema.validate_rules(),
+        Err(SchemaRuleError::ConflictingNodeType(1))
+    );
+
+    let schema = TestSchema::new()
+        .edge_whitelist(Some(vec![0]))
+        .edge_blacklist(Some(vec![0]));
+    assert_eq!(
+        schema.validate_rules(),
+        Err(SchemaRuleError::ConflictingEdgeType(0))
+    );
+
+    let schema = TestSchema::new()
+        .endpoint_whitelist(Some(vec![(0, 1, 0)]))
+        .endpoint_blacklist(Some(vec![(0, 1, 0)]));
+    assert_eq!(
+        schema.validate_rules(),
+        Err(SchemaRuleError::ConflictingEndpoint(0, 1, 0))
+    );
+
+    let schema = TestSchema::new()
+        .edge_blacklist(Some(vec![1]))
+        .endpoint_whitelist(Some(vec![(0, 1, 0), (0, 1, 1)]));
+    assert_eq!(
+        schema.validate_rules(),
+        Err(SchemaRuleError::EndpointEdgeNotAllowed(0, 1, 1))
+    );
+
+    let schema = TestSchema::new()
+        .node_whitelist(Some(vec![0]))
+        .endpoint_whitelist(Some(vec![(0, 1, 0)]));
+    assert_eq!(
+        schema.validate_rules(),
+        Err(SchemaRuleError::EndpointNodeNotAllowed(0, 1, 0))
+    );
+
+    let schema =
+        TestSchema::new().endpoint_incoming_max_quantity(Some(HashMap::from([((0, 1), 0)])));
+    assert_eq!(
+        schema.validated().err(),
+        Some(SchemaRuleError::ZeroMaxQuantity(0, 1))
+    );
+
+    let schema = TestSchema::new()
+        .endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 1), 1)])))
+        .endpoint_outgoing_min_quantity(Some(HashMap::from([((0, 1), 2)])));
+    assert_eq!(
+        schema.validated().err(),
+        Some(SchemaRuleError::MinAboveMax(0, 1))
+    );
+
+    // A schema without contradictions passes through unchanged
+    let schema = TestSchema::new()
+        .node_whitelist(Some(vec![0, 1]))
+        .endpoint_whitelist(Some(vec![(0, 1, 0)]))
+        .endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 0), 1)])))
+        .validated();
+    assert!(schema.is_ok());
+}
+
+#[test]
+fn generic_schema_endpoint_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    // Only edges of type 0 from type 0 to type 1 are allowed, but not of type 1 from 1 to 0
+    let schema = TestSchema::new()
+        .whitelist_endpoint(Endpoint::new(0, 1, 0))
+        .whitelist_endpoint(Endpoint::new(1, 0, 1))
+        .blacklist_endpoint(Endpoint::new(1, 0, 1));
+    let mut g = TestGraph::new(schema);
+    g.add_nodes([(0, 0), (1, 1)], true)?;
+
+    g.add_edge(0, 1, (0, 0))?;
+    assert!(g.add_edge(1, 0, (1, 0)).is_err());
+    assert!(g.add_edge(1, 0, (2, 1)).is_err());
+    assert!(g.add_edge(0, 1, (3, 1)).is_err());
+
+    // The typed endpoint uses the same order as the tuples
+    let endpoint: (usize, usize, usize) = Endpoint::new(0, 1, 2).into();
+    assert_eq!(endpoint, (0, 1, 2));
+    assert_eq!(Endpoint::from((0, 1, 2)).edge, 2);
+
+    Ok(())
+}
+
+#[test]
+fn generic_schema_infer_from_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 1), (2, 1), (3, 2)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0)), (1, 3, (2, 1))], true)?;
+
+    let schema = TestSchema::infer_from(&g)?;
+    assert_eq!(schema.node_whitelist, Some(vec![0, 1, 2]));
+    assert_eq!(schema.edge_whitelist, Some(vec![0, 1]));
+    assert_eq!(schema.endpoint_whitelist, Some(vec![(0, 1, 0), (1, 2, 1)]));
+    assert!(schema.validate_rules().is_ok());
+
+    // The inferred schema accepts the graph it was inferred from but nothing else
+    let mut g = g.reinterpret_schema(schema)?;
+    assert!(g.add_node((4, 3)).is_err());
+    assert!(g.add_edge(0, 3, (3, 0)).is_err());
+    g.add_edge(0, 1, (3, 0))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn generic_schema_serialize_unordered_types_test() {
+    use std::fmt::{self, Display};
+
+    /// Type which can be used as a key but has no ordering
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+    struct Kind(usize);
+
+    impl Display for Kind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Kind{}", self.0)
+        }
+    }
+
+    let schema = || {
+        let quantities = (0..20).map(|i| ((Kind(i), Kind(i % 3)), i)).collect();
+        GenericSchema::<Kind, Kind>::default().endpoint_outgoing_max_quantity(Some(quantities))
+    };
+
+    // Every schema gets its own HashMap order, but the quantities are still serialized the same way
+    let s1 = bincode::serialize(&schema()).unwrap();
+    let s2 = bincode::serialize(&schema()).unwrap();
+    assert_eq!(s1, s2);
+}
@@ -1,11 +1,11 @@
 use super::GenericTypeIdentifier;
 use crate::{Id, Key, Typed};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
-#[derive(
-    Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize,
-)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GenericWeight<K: Key, T: GenericTypeIdentifier>((K, T));
 
 impl<K: Key, T: GenericTypeIdentifier> Deref for GenericWeight<K, T> {
@@ -29,7 +29,7 @@ impl<K: Key, T: GenericTypeIdentifier> From<(K, T)> for GenericWeight<K, T> {
 
 impl<K: Key, T: GenericTypeIdentifier> Id<K> for GenericWeight<K, T> {
     fn get_id(&self) -> K {
-        self.0 .0
+        self.0 .0.clone()
     }
 
     fn set_id(&mut self, new_id: K) {
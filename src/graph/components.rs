@@ -0,0 +1,290 @@
+use crate::{Id, Key, SchemaExt, SchemaResult, TypedError, TypedGraph};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// Keeps track of the connected components of a graph while it is being changed
+///
+/// The graph is changed through the index so the components can be kept up to date, and the direction of edges is ignored.
+/// Adding nodes and edges joins components in near-constant time.
+///
+/// Removing a node or an edge may split a component, which can not be undone in a union-find.
+/// Instead the index is marked as stale and the components are rebuilt from the graph by the next query in O(V + E) time.
+/// Any number of removals between two queries only cause a single rebuild,
+/// so the amortized cost of a removal is O(V + E) when every removal is followed by a query and less when they are batched
+#[derive(Debug, Clone)]
+pub struct ComponentIndex<NK> {
+    components: UnionFind<NK>,
+    stale: bool,
+}
+
+impl<NK: Key> ComponentIndex<NK> {
+    /// Create an index of the current components of the graph
+    pub fn new<EK, S, H>(graph: &TypedGraph<NK, EK, S, H>) -> SchemaResult<Self, NK, EK, S>
+    where
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+    {
+        let mut index = ComponentIndex {
+            components: UnionFind::new(std::iter::empty()),
+            stale: true,
+        };
+        index.rebuild(graph)?;
+        Ok(index)
+    }
+
+    /// Add a node to the graph and the index
+    pub fn add_node<EK, S, N, H>(
+        &mut self,
+        graph: &mut TypedGraph<NK, EK, S, H>,
+        node: N,
+    ) -> SchemaResult<NK, NK, EK, S>
+    where
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+        N: Into<S::N>,
+    {
+        let node_id = graph.add_node(node)?;
+        self.components.insert(node_id.clone());
+        Ok(node_id)
+    }
+
+    /// Add an edge to the graph and join the components of its endpoints
+    ///
+    /// Moving an existing edge to new endpoints is treated as a removal
+    pub fn add_edge<EK, S, E, H>(
+        &mut self,
+        graph: &mut TypedGraph<NK, EK, S, H>,
+        source: NK,
+        target: NK,
+        edge: E,
+    ) -> SchemaResult<EK, NK, EK, S>
+    where
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+        E: Into<S::E>,
+    {
+        let edge: S::E = edge.into();
+        let old_endpoints = graph.edge_endpoints(edge.get_id()).ok();
+
+        let edge_id = graph.add_edge(source.clone(), target.clone(), edge)?;
+        if old_endpoints.is_some_and(|endpoints| endpoints != (source.clone(), target.clone())) {
+            self.stale = true;
+        }
+
+        // A stale index will pick up the edge when it is rebuilt
+        if !self.stale {
+            self.components.union(&source, &target);
+        }
+
+        Ok(edge_id)
+    }
+
+    /// Remove a node and its edges from the graph
+    pub fn remove_node<EK, S, H>(
+        &mut self,
+        graph: &mut TypedGraph<NK, EK, S, H>,
+        node_id: NK,
+    ) -> SchemaResult<S::N, NK, EK, S>
+    where
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+    {
+        let node = graph.remove_node(node_id)?;
+        self.stale = true;
+        Ok(node)
+    }
+
+    /// Remove an edge from the graph
+    pub fn remove_edge<EK, S, H>(
+        &mut self,
+        graph: &mut TypedGraph<NK, EK, S, H>,
+        edge_id: EK,
+    ) -> SchemaResult<S::E, NK, EK, S>
+    where
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+    {
+        let edge = graph.remove_edge(edge_id)?;
+        self.stale = true;
+        Ok(edge)
+    }
+
+    /// Check if there is a path between the two nodes when ignoring the direction of edges
+    ///
+    /// The graph must be the one the index has been updated with
+    pub fn same_component<EK, S, H>(
+        &mut self,
+        graph: &TypedGraph<NK, EK, S, H>,
+        a: NK,
+        b: NK,
+    ) -> SchemaResult<bool, NK, EK, S>
+    where
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+    {
+        self.rebuild(graph)?;
+
+        let a_idx = self
+            .components
+            .index(&a)
+            .ok_or(TypedError::MissingNode(a))?;
+        let b_idx = self
+            .components
+            .index(&b)
+            .ok_or(TypedError::MissingNode(b))?;
+        Ok(self.components.find(a_idx) == self.components.find(b_idx))
+    }
+
+    /// Check if the components will be rebuilt by the next query
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Recompute the components from scratch if they may have been split
+    fn rebuild<EK, S, H>(&mut self, graph: &TypedGraph<NK, EK, S, H>) -> SchemaResult<(), NK, EK, S>
+    where
+        EK: Key,
+        S: SchemaExt<NK, EK>,
+        H: BuildHasher,
+    {
+        if !self.stale {
+            return Ok(());
+        }
+
+        self.components = UnionFind::new(graph.node_ids());
+        for edge in graph.try_edges_full() {
+            let edge = edge?;
+            self.components
+                .union(&edge.get_source(), &edge.get_target());
+        }
+        self.stale = false;
+
+        Ok(())
+    }
+}
+
+/// The size of a single connected component
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentStat<NK> {
+    /// One of the nodes in the component
+    pub representative: NK,
+    pub node_count: usize,
+    pub edge_count: usize,
+}
+
+/// Disjoint sets of node ids
+#[derive(Debug, Clone)]
+pub(crate) struct UnionFind<NK> {
+    indices: HashMap<NK, usize>,
+    parents: Vec<usize>,
+    ranks: Vec<usize>,
+}
+
+impl<NK: Key> UnionFind<NK> {
+    pub(crate) fn new(ids: impl Iterator<Item = NK>) -> Self {
+        let indices: HashMap<_, _> = ids.enumerate().map(|(i, id)| (id, i)).collect();
+        UnionFind {
+            parents: (0..indices.len()).collect(),
+            ranks: vec![0; indices.len()],
+            indices,
+        }
+    }
+
+    /// Add the id as its own set if it is not already known
+    pub(crate) fn insert(&mut self, id: NK) {
+        if self.indices.contains_key(&id) {
+            return;
+        }
+
+        self.indices.insert(id, self.parents.len());
+        self.parents.push(self.parents.len());
+        self.ranks.push(0);
+    }
+
+    fn index(&self, id: &NK) -> Option<usize> {
+        self.indices.get(id).copied()
+    }
+
+    /// Find the representative of the set containing the id
+    pub(crate) fn root(&mut self, id: &NK) -> Option<usize> {
+        let idx = self.index(id)?;
+        Some(self.find(idx))
+    }
+
+    fn find(&mut self, mut idx: usize) -> usize {
+        while self.parents[idx] != idx {
+            // Path halving keeps the trees shallow
+            self.parents[idx] = self.parents[self.parents[idx]];
+            idx = self.parents[idx];
+        }
+        idx
+    }
+
+    /// Join the sets of the two ids
+    ///
+    /// Returns false if they were already in the same set
+    pub(crate) fn union(&mut self, a: &NK, b: &NK) -> bool {
+        let a = self.find(self.indices[a]);
+        let b = self.find(self.indices[b]);
+        if a == b {
+            return false;
+        }
+
+        match self.ranks[a].cmp(&self.ranks[b]) {
+            Ordering::Less => self.parents[a] = b,
+            Ordering::Greater => self.parents[b] = a,
+            Ordering::Equal => {
+                self.parents[b] = a;
+                self.ranks[a] += 1;
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn component_index_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.add_edge(0, 1, (0, 0))?;
+
+    let mut index = ComponentIndex::new(&g)?;
+    assert!(index.same_component(&g, 0, 1)?);
+
+    index.add_node(&mut g, (2, 0))?;
+    index.add_node(&mut g, (3, 0))?;
+    assert!(!index.same_component(&g, 1, 2)?);
+
+    // The direction of the edges does not matter
+    index.add_edge(&mut g, 2, 1, (1, 0))?;
+    index.add_edge(&mut g, 3, 2, (2, 0))?;
+    assert!(index.same_component(&g, 0, 3)?);
+    assert!(!index.is_stale());
+
+    // Removing an edge splits the component on the next query
+    index.remove_edge(&mut g, 1)?;
+    assert!(index.is_stale());
+    assert!(!index.same_component(&g, 0, 3)?);
+    assert!(index.same_component(&g, 2, 3)?);
+    assert!(!index.is_stale());
+
+    // Moving an edge may also split a component
+    index.add_edge(&mut g, 1, 3, (0, 0))?;
+    assert!(!index.same_component(&g, 0, 1)?);
+    assert!(index.same_component(&g, 1, 2)?);
+
+    index.remove_node(&mut g, 3)?;
+    assert!(!index.same_component(&g, 1, 2)?);
+    assert!(index.same_component(&g, 3, 1).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use crate::{Id, Key, SchemaExt, SchemaResult, Typed, TypedGraph};
+use std::hash::BuildHasher;
+
+/// The differences between two graphs
+///
+/// Added elements are only found in the new graph, removed elements are only found in the old graph
+/// and changed elements are found in both but with different content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphDiff<NK, EK> {
+    pub added_nodes: Vec<NK>,
+    pub removed_nodes: Vec<NK>,
+    /// Nodes with a different type or weight
+    pub changed_nodes: Vec<NK>,
+    pub added_edges: Vec<EK>,
+    pub removed_edges: Vec<EK>,
+    /// Edges with different endpoints, type or weight
+    pub changed_edges: Vec<EK>,
+}
+
+impl<NK, EK> GraphDiff<NK, EK> {
+    /// Check if the two graphs contains the same nodes and edges
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.changed_edges.is_empty()
+    }
+}
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    S::N: PartialEq,
+    S::E: PartialEq,
+    H: BuildHasher,
+{
+    /// Find the nodes and edges which has been added, removed or changed going from self to other
+    ///
+    /// The order of the outgoing edges is not compared
+    pub fn diff(&self, other: &Self) -> SchemaResult<GraphDiff<NK, EK>, NK, EK, S> {
+        let mut diff = GraphDiff {
+            added_nodes: Vec::new(),
+            removed_nodes: Vec::new(),
+            changed_nodes: Vec::new(),
+            added_edges: Vec::new(),
+            removed_edges: Vec::new(),
+            changed_edges: Vec::new(),
+        };
+
+        for node in self.nodes() {
+            let node_id = node.get_id();
+            match other.get_node_safe(node_id.clone()) {
+                Some(other_node) => {
+                    if node.get_type() != other_node.get_type() || node != other_node {
+                        diff.changed_nodes.push(node_id);
+                    }
+                }
+                None => diff.removed_nodes.push(node_id),
+            }
+        }
+
+        diff.added_nodes = other
+            .node_ids()
+            .filter(|node_id| self.get_node_safe(node_id.clone()).is_none())
+            .collect();
+
+        for edge in self.try_edges_full() {
+            let edge = edge?;
+            let edge_id = edge.get_id();
+            if other.get_edge_safe(edge_id.clone()).is_none() {
+                diff.removed_edges.push(edge_id);
+                continue;
+            }
+
+            let other_edge = other.get_edge_full(edge_id.clone())?;
+            if edge.get_source() != other_edge.get_source()
+                || edge.get_target() != other_edge.get_target()
+                || edge.get_type() != other_edge.get_type()
+                || edge.weight != other_edge.weight
+            {
+                diff.changed_edges.push(edge_id);
+            }
+        }
+
+        diff.added_edges = other
+            .edge_ids()
+            .filter(|edge_id| self.get_edge_safe(edge_id.clone()).is_none())
+            .collect();
+
+        Ok(diff)
+    }
+
+    /// Check if the two graphs contains the same nodes and edges with the outgoing edges in the same order
+    ///
+    /// Unlike comparing the graphs field by field this does not depend on the order the elements were added and removed in.
+    /// The schemas are not compared
+    pub fn semantic_eq(&self, other: &Self) -> bool {
+        let is_same_content = self.diff(other).is_ok_and(|diff| diff.is_empty());
+        if !is_same_content {
+            return false;
+        }
+
+        self.node_ids().all(|node_id| {
+            match (
+                self.get_outgoing(node_id.clone()),
+                other.get_outgoing(node_id),
+            ) {
+                (Ok(outgoing), Ok(other_outgoing)) => outgoing
+                    .map(|edge| edge.get_id())
+                    .eq(other_outgoing.map(|edge| edge.get_id())),
+                _ => false,
+            }
+        })
+    }
+}
+
+#[test]
+fn diff_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (2, 3, (2, 0))], true)?;
+
+    assert!(g.diff(&g)?.is_empty());
+
+    let mut other = g.clone();
+    other.add_node((0, 1))?;
+    other.remove_node(3)?;
+    other.add_node((4, 0))?;
+    other.add_edge(1, 0, (1, 0))?;
+    other.add_edge(0, 4, (3, 0))?;
+
+    let diff = g.diff(&other)?;
+    assert_eq!(
+        diff,
+        GraphDiff {
+            added_nodes: vec![4],
+            removed_nodes: vec![3],
+            changed_nodes: vec![0],
+            added_edges: vec![3],
+            removed_edges: vec![2],
+            changed_edges: vec![1],
+        }
+    );
+
+    // Going the other way swaps what is added and removed
+    let diff = other.diff(&g)?;
+    assert_eq!(diff.added_nodes, vec![3]);
+    assert_eq!(diff.removed_nodes, vec![4]);
+    assert_eq!(diff.changed_nodes, vec![0]);
+    assert_eq!(diff.added_edges, vec![2]);
+    assert_eq!(diff.removed_edges, vec![3]);
+    assert_eq!(diff.changed_edges, vec![1]);
+
+    Ok(())
+}
+
+#[test]
+fn semantic_eq_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use crate::InsertPosition;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0)), (0, 3, (2, 0))], true)?;
+
+    // Build the same graph in a different way
+    let mut other = TestGraph::default();
+    other.add_nodes([(5, 0), (3, 0), (2, 0), (1, 0), (0, 0)], true)?;
+    other.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0)), (0, 3, (2, 0))], true)?;
+    other.remove_node(5)?;
+    assert!(g.semantic_eq(&other));
+    assert!(other.semantic_eq(&g));
+
+    // The outgoing order matters
+    other.move_edge_order(2, 0, InsertPosition::Before)?;
+    assert!(!g.semantic_eq(&other));
+    other.move_edge_order(2, 1, InsertPosition::After)?;
+    assert!(g.semantic_eq(&other));
+
+    other.add_node((3, 1))?;
+    assert!(!g.semantic_eq(&other));
+
+    Ok(())
+}
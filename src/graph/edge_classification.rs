@@ -0,0 +1,144 @@
+use crate::{Key, SchemaExt, SchemaResult, TypedGraph};
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+
+/// The role of an edge in a depth first search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeClass {
+    /// The edge was used to discover its target
+    Tree,
+    /// The edge leads back to a node which is still being visited, so it closes a cycle
+    Back,
+    /// The edge leads to a descendant which has already been visited
+    Forward,
+    /// The edge leads to a node in a branch of the search which has already been finished
+    Cross,
+}
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Perform a depth first search from the start node and classify every edge it encounters
+    ///
+    /// The edges are returned in the order the search reaches them.
+    /// The outgoing edges of each node are followed in their outgoing order, so the result is deterministic.
+    /// Edges which can not be reached from the start are not included.
+    ///
+    /// In an undirected graph every edge is only classified the first time it is reached, so all edges are either tree or back edges
+    pub fn classify_edges(&self, start: NK) -> SchemaResult<Vec<(EK, EdgeClass)>, NK, EK, S> {
+        let outgoing = |node_id: NK| -> SchemaResult<_, NK, EK, S> {
+            let edges: Vec<_> = self
+                .get_outgoing(node_id)?
+                .map(|edge| (edge.get_id(), edge.get_outer()))
+                .collect();
+            Ok(edges.into_iter())
+        };
+
+        // The order in which the nodes were discovered
+        let mut discovered = HashMap::from([(start.clone(), 0)]);
+        let mut finished = HashSet::new();
+        let mut classified = HashSet::new();
+        let mut classes = Vec::new();
+        let mut stack = vec![(start.clone(), outgoing(start)?)];
+
+        while let Some((node, edges)) = stack.last_mut() {
+            let node = node.clone();
+            let Some((edge_id, next)) = edges.next() else {
+                finished.insert(node);
+                stack.pop();
+                continue;
+            };
+
+            if !classified.insert(edge_id.clone()) {
+                continue;
+            }
+
+            let class = match discovered.get(&next) {
+                None => EdgeClass::Tree,
+                Some(_) if !finished.contains(&next) => EdgeClass::Back,
+                Some(time) if discovered[&node] < *time => EdgeClass::Forward,
+                Some(_) => EdgeClass::Cross,
+            };
+
+            if class == EdgeClass::Tree {
+                discovered.insert(next.clone(), discovered.len());
+                stack.push((next.clone(), outgoing(next)?));
+            }
+            classes.push((edge_id, class));
+        }
+
+        Ok(classes)
+    }
+}
+
+#[test]
+fn classify_edges_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..5 {
+        g.add_node((i, 0))?;
+    }
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 3, (1, 0)),
+            (1, 2, (2, 0)),
+            (2, 0, (3, 0)),
+            (0, 2, (4, 0)),
+            (3, 2, (5, 0)),
+            (3, 3, (6, 0)),
+            (4, 0, (7, 0)),
+        ],
+        true,
+    )?;
+
+    // Node 4 can not be reached so edge 7 is left out
+    assert_eq!(
+        g.classify_edges(0)?,
+        vec![
+            (0, EdgeClass::Tree),
+            (2, EdgeClass::Tree),
+            (3, EdgeClass::Back),
+            (1, EdgeClass::Tree),
+            (5, EdgeClass::Cross),
+            (6, EdgeClass::Back),
+            (4, EdgeClass::Forward),
+        ]
+    );
+    assert!(g.classify_edges(9).is_err());
+
+    // The outgoing order decides which edges become tree edges
+    g.set_outgoing_order(0, &[4, 1, 0])?;
+    assert_eq!(
+        g.classify_edges(0)?,
+        vec![
+            (4, EdgeClass::Tree),
+            (3, EdgeClass::Back),
+            (1, EdgeClass::Tree),
+            (5, EdgeClass::Cross),
+            (6, EdgeClass::Back),
+            (0, EdgeClass::Tree),
+            (2, EdgeClass::Cross),
+        ]
+    );
+
+    // Undirected edges are not classified again when reached from the other side
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (2, 0, (2, 0))], true)?;
+    assert_eq!(
+        g.classify_edges(0)?,
+        vec![
+            (0, EdgeClass::Tree),
+            (1, EdgeClass::Tree),
+            (2, EdgeClass::Back)
+        ]
+    );
+
+    Ok(())
+}
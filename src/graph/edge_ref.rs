@@ -1,6 +1,12 @@
-use crate::{Direction, Downcast, Key, SchemaExt, SchemaResult};
+use crate::{Direction, Downcast, Id, Key, SchemaExt, SchemaResult, Typed};
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 
+/// An edge seen from one of its endpoints while traversing the graph
+///
+/// The direction tells which of the endpoints is the inner node the edge was reached from.
+/// Both endpoints are kept as borrows of the node weights, which costs two references
+/// but lets the ids and types of the endpoints be read without hashing their ids again
 #[derive(Debug, Clone)]
 pub struct EdgeRef<'a, NK, EK, S>
 where
@@ -9,8 +15,8 @@ where
     S: SchemaExt<NK, EK>,
 {
     pub(crate) weight: &'a S::E,
-    pub(crate) source: NK,
-    pub(crate) target: NK,
+    pub(crate) source: &'a S::N,
+    pub(crate) target: &'a S::N,
     pub(crate) direction: Direction,
 }
 
@@ -24,6 +30,10 @@ where
         self.weight
     }
 
+    /// Downcast the weight into a more specific type
+    ///
+    /// The edge ref borrows the graph immutably so the weight can not be changed through it.
+    /// Use TypedGraph::for_each_outgoing_mut to change the outgoing edges of a node instead
     pub fn get_weight_downcast<E>(&self) -> SchemaResult<&'a E, NK, EK, S>
     where
         S::E: Downcast<'a, NK, EK, &'a E, S>,
@@ -31,14 +41,46 @@ where
         self.weight.downcast()
     }
 
+    /// Get the id of the edge without having Id in scope
+    pub fn get_id(&self) -> EK {
+        self.weight.get_id()
+    }
+
+    /// Get the type of the edge without having Typed in scope
+    pub fn get_type(&self) -> <S::E as Typed>::Type {
+        self.weight.get_type()
+    }
+
     pub fn get_source(&self) -> NK {
-        self.source
+        self.source.get_id()
     }
 
     pub fn get_target(&self) -> NK {
+        self.target.get_id()
+    }
+
+    /// Get the weight of the source node
+    ///
+    /// The endpoints are borrowed when the edge is resolved, so this does not look up the node again
+    pub fn get_source_node(&self) -> &'a S::N {
+        self.source
+    }
+
+    /// Get the weight of the target node
+    pub fn get_target_node(&self) -> &'a S::N {
         self.target
     }
 
+    /// Get the type of the source node without looking it up in the graph
+    pub fn source_type(&self) -> <S::N as Typed>::Type {
+        self.source.get_type()
+    }
+
+    /// Get the type of the target node without looking it up in the graph
+    pub fn target_type(&self) -> <S::N as Typed>::Type {
+        self.target.get_type()
+    }
+
     pub fn get_outer(&self) -> NK {
         match self.direction {
             Direction::Incoming => self.get_source(),
@@ -69,3 +111,214 @@ where
         &self.weight
     }
 }
+
+/// Edge refs are equal if they refer to the same edge
+///
+/// Only the id of the edges are compared, not their weights.
+/// This means that the same edge reached from either of its endpoints is also considered equal
+impl<'a, NK, EK, S> PartialEq for EdgeRef<'a, NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.get_id() == other.get_id()
+    }
+}
+
+impl<'a, NK, EK, S> Eq for EdgeRef<'a, NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+}
+
+/// Edge refs are hashed using only the id of the edge, so it agrees with PartialEq
+impl<'a, NK, EK, S> Hash for EdgeRef<'a, NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_id().hash(state);
+    }
+}
+
+/// An edge together with its source and target nodes
+///
+/// Unlike EdgeRef the edge is not seen from one of its endpoints, so there is no inner or outer node
+#[derive(Debug, Clone)]
+pub struct EdgeView<'a, NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    pub(crate) weight: &'a S::E,
+    pub(crate) source: &'a S::N,
+    pub(crate) target: &'a S::N,
+}
+
+impl<'a, NK, EK, S> EdgeView<'a, NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    pub fn get_weight(&self) -> &'a S::E {
+        self.weight
+    }
+
+    pub fn get_weight_downcast<E>(&self) -> SchemaResult<&'a E, NK, EK, S>
+    where
+        S::E: Downcast<'a, NK, EK, &'a E, S>,
+    {
+        self.weight.downcast()
+    }
+
+    /// Get the id of the edge without having Id in scope
+    pub fn get_id(&self) -> EK {
+        self.weight.get_id()
+    }
+
+    /// Get the type of the edge without having Typed in scope
+    pub fn get_type(&self) -> <S::E as Typed>::Type {
+        self.weight.get_type()
+    }
+
+    pub fn get_source(&self) -> NK {
+        self.source.get_id()
+    }
+
+    pub fn get_target(&self) -> NK {
+        self.target.get_id()
+    }
+
+    /// Get the weight of the source node
+    ///
+    /// The endpoints are borrowed when the edge is resolved, so this does not look up the node again
+    pub fn get_source_node(&self) -> &'a S::N {
+        self.source
+    }
+
+    /// Get the weight of the target node
+    pub fn get_target_node(&self) -> &'a S::N {
+        self.target
+    }
+
+    /// Get the type of the source node without looking it up in the graph
+    pub fn source_type(&self) -> <S::N as Typed>::Type {
+        self.source.get_type()
+    }
+
+    /// Get the type of the target node without looking it up in the graph
+    pub fn target_type(&self) -> <S::N as Typed>::Type {
+        self.target.get_type()
+    }
+}
+
+impl<'a, NK, EK, S> Deref for EdgeView<'a, NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    type Target = S::E;
+    fn deref(&self) -> &Self::Target {
+        self.weight
+    }
+}
+
+impl<'a, NK, EK, S> From<EdgeRef<'a, NK, EK, S>> for EdgeView<'a, NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    fn from(edge: EdgeRef<'a, NK, EK, S>) -> Self {
+        EdgeView {
+            weight: edge.weight,
+            source: edge.source,
+            target: edge.target,
+        }
+    }
+}
+
+#[test]
+fn edge_ref_accessors_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.add_edge(0, 1, (3, 2))?;
+
+    // The id and type are known without annotating the key type
+    let edge = g.get_outgoing(0)?.next().unwrap();
+    assert_eq!(edge.get_id(), 3);
+    assert_eq!(edge.get_type(), 2);
+
+    let edge = g.get_edge_full(3)?;
+    assert_eq!((edge.get_id(), edge.get_type()), (3, 2));
+
+    Ok(())
+}
+
+#[test]
+fn edge_ref_eq_by_id_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::HashSet;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (1, 1, (2, 0))], true)?;
+
+    // The same edge seen from both of its endpoints is only counted once
+    let mut edges = HashSet::new();
+    for node_id in 0..3 {
+        edges.extend(g.get_outgoing(node_id)?);
+        edges.extend(g.get_incoming(node_id)?);
+    }
+    assert_eq!(edges.len(), 3);
+
+    let outgoing = g.get_outgoing(0)?.next().unwrap();
+    let incoming = g.get_incoming(1)?.find(|e| e.get_id() == 0).unwrap();
+    assert_eq!(outgoing, incoming);
+    assert_ne!(outgoing.get_direction(), incoming.get_direction());
+    assert_ne!(outgoing, g.get_outgoing(1)?.next().unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn edge_ref_endpoint_types_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 1)], true)?;
+    g.add_edge(0, 1, (0, 2))?;
+
+    let outgoing = g.get_outgoing(0)?.next().unwrap();
+    assert_eq!((outgoing.source_type(), outgoing.target_type()), (0, 1));
+    assert_eq!(outgoing.get_target_node(), g.get_node(1)?);
+
+    // The endpoints keep their roles when the edge is reached from the target
+    let incoming = g.get_incoming(1)?.next().unwrap();
+    assert_eq!((incoming.source_type(), incoming.target_type()), (0, 1));
+
+    let view = g.get_edge_full(0)?;
+    assert_eq!((view.source_type(), view.target_type()), (0, 1));
+    assert_eq!(view.get_source_node(), g.get_node(0)?);
+
+    // In an undirected graph the inner node is always the source
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes([(0, 0), (1, 1)], true)?;
+    g.add_edge(0, 1, (0, 2))?;
+
+    let incoming = g.get_outgoing(1)?.next().unwrap();
+    assert_eq!((incoming.source_type(), incoming.target_type()), (1, 0));
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use crate::{Id, Key, SchemaExt, SchemaResult, TypedError, TypedGraph};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// A view into a single node in a graph which may either be occupied or vacant
+///
+/// Created using TypedGraph::node_entry
+pub enum NodeEntry<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    Occupied(OccupiedNodeEntry<'a, S::N>),
+    Vacant(VacantNodeEntry<'a, NK, EK, S, H>),
+}
+
+/// A node which already exists in the graph
+pub struct OccupiedNodeEntry<'a, N> {
+    pub(crate) weight: &'a mut N,
+}
+
+/// A node id which is not yet used in the graph
+pub struct VacantNodeEntry<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    pub(crate) graph: &'a mut TypedGraph<NK, EK, S, H>,
+    pub(crate) node_id: NK,
+}
+
+impl<'a, NK, EK, S, H> NodeEntry<'a, NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Get the existing node or insert the node created by the function
+    pub fn or_insert_with<N, F>(self, f: F) -> SchemaResult<&'a mut S::N, NK, EK, S>
+    where
+        N: Into<S::N>,
+        F: FnOnce() -> N,
+    {
+        match self {
+            NodeEntry::Occupied(entry) => Ok(entry.into_mut()),
+            NodeEntry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Modify the node if it exists
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut S::N),
+    {
+        if let NodeEntry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, N> OccupiedNodeEntry<'a, N> {
+    pub fn get(&self) -> &N {
+        self.weight
+    }
+
+    /// Changing the type of the node through the reference is handled the same way as through get_node_mut
+    pub fn get_mut(&mut self) -> &mut N {
+        self.weight
+    }
+
+    /// Changing the type of the node through the reference is handled the same way as through get_node_mut
+    pub fn into_mut(self) -> &'a mut N {
+        self.weight
+    }
+}
+
+impl<'a, NK, EK, S, H> VacantNodeEntry<'a, NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    pub fn id(&self) -> NK {
+        self.node_id.clone()
+    }
+
+    /// Add the node to the graph
+    ///
+    /// The node has to use the id of the entry
+    pub fn insert<N>(self, node: N) -> SchemaResult<&'a mut S::N, NK, EK, S>
+    where
+        N: Into<S::N>,
+    {
+        let weight: S::N = node.into();
+        if weight.get_id() != self.node_id {
+            return Err(TypedError::InconsistentNodeIds(
+                self.node_id,
+                weight.get_id(),
+            ));
+        }
+
+        let node_key = self.graph.insert_node_weight(weight)?;
+        Ok(self.graph.node_weight_mut(node_key))
+    }
+}
+
+/// A view into a single edge in a graph which may either be occupied or vacant
+///
+/// Created using TypedGraph::edge_entry
+pub enum EdgeEntry<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    Occupied(OccupiedEdgeEntry<'a, S::E>),
+    Vacant(VacantEdgeEntry<'a, NK, EK, S, H>),
+}
+
+/// An edge which already exists in the graph
+pub struct OccupiedEdgeEntry<'a, E> {
+    pub(crate) weight: &'a mut E,
+}
+
+/// An edge id which is not yet used in the graph
+pub struct VacantEdgeEntry<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    pub(crate) graph: &'a mut TypedGraph<NK, EK, S, H>,
+    pub(crate) edge_id: EK,
+}
+
+impl<'a, NK, EK, S, H> EdgeEntry<'a, NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Get the existing edge or insert the edge created by the function between source and target
+    pub fn or_insert_with<E, F>(
+        self,
+        source: NK,
+        target: NK,
+        f: F,
+    ) -> SchemaResult<&'a mut S::E, NK, EK, S>
+    where
+        E: Into<S::E>,
+        F: FnOnce() -> E,
+    {
+        match self {
+            EdgeEntry::Occupied(entry) => Ok(entry.into_mut()),
+            EdgeEntry::Vacant(entry) => entry.insert(source, target, f()),
+        }
+    }
+
+    /// Modify the edge if it exists
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut S::E),
+    {
+        if let EdgeEntry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, E> OccupiedEdgeEntry<'a, E> {
+    pub fn get(&self) -> &E {
+        self.weight
+    }
+
+    /// Changing the type of the edge through the reference is handled the same way as through get_edge_mut
+    pub fn get_mut(&mut self) -> &mut E {
+        self.weight
+    }
+
+    /// Changing the type of the edge through the reference is handled the same way as through get_edge_mut
+    pub fn into_mut(self) -> &'a mut E {
+        self.weight
+    }
+}
+
+impl<'a, NK, EK, S, H> VacantEdgeEntry<'a, NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    pub fn id(&self) -> EK {
+        self.edge_id.clone()
+    }
+
+    /// Add the edge to the graph between source and target
+    ///
+    /// The edge has to use the id of the entry
+    pub fn insert<E>(self, source: NK, target: NK, edge: E) -> SchemaResult<&'a mut S::E, NK, EK, S>
+    where
+        E: Into<S::E>,
+    {
+        let weight: S::E = edge.into();
+        if weight.get_id() != self.edge_id {
+            return Err(TypedError::InconsistentEdgeIds(
+                self.edge_id,
+                weight.get_id(),
+            ));
+        }
+
+        let edge_key = self.graph.insert_edge_weight(source, target, weight)?;
+        Ok(self.graph.edge_weight_mut(edge_key))
+    }
+}
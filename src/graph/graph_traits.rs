@@ -3,7 +3,7 @@ use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use either::Either;
 
-use crate::{SchemaError, SchemaResult};
+use crate::{Direction, SchemaError, SchemaResult};
 
 /// Trait shared by all nodes in a graph
 pub trait NodeExt<NK: Key>: Typed + Id<NK> + Clone + Debug {}
@@ -20,6 +20,20 @@ where
     /// Type of edge weights used by the schema
     type E: EdgeExt<EK>;
 
+    /// Whether the edges of the graph has a direction
+    ///
+    /// In an undirected graph every edge is both an outgoing and incoming edge of both its endpoints.
+    /// The edge is still only stored once and keeps the source and target it was added with
+    const DIRECTED: bool = true;
+
+    /// Whether the incoming edges of a node keeps their order
+    ///
+    /// By default the incoming edges are only a cache and removing an edge may reorder the remaining ones.
+    /// When enabled the incoming edges stay in the order they were added in and can be reordered using `move_incoming_edge_order`.
+    /// This uses no extra memory, but removing an edge becomes O(n) in the number of incoming edges of its target instead of O(1).
+    /// The incoming order is not serialized, so it is rebuilt from the order of the edges when deserializing
+    const ORDERED_INCOMING: bool = false;
+
     /// Get the name of the schema in order to provide better error messages
     fn name(&self) -> String;
 
@@ -40,6 +54,77 @@ where
         source: <Self::N as Typed>::Type,
         target: <Self::N as Typed>::Type,
     ) -> Result<(), DisAllowedEdge>;
+
+    /// Before adding a new edge check if the new edge is allowed given the weights of the edge and its endpoints
+    ///
+    /// This allows the schema to put constraints on the data stored in the weights.
+    /// By default only the types are checked using allow_edge
+    fn allow_edge_full(
+        &self,
+        outgoing_edge_count: usize,
+        incoming_edge_count: usize,
+        weight: &Self::E,
+        source: &Self::N,
+        target: &Self::N,
+    ) -> Result<(), DisAllowedEdge> {
+        self.allow_edge(
+            outgoing_edge_count,
+            incoming_edge_count,
+            weight.get_type(),
+            source.get_type(),
+            target.get_type(),
+        )
+    }
+
+    /// Before adding an edge going from a node to itself check if the schema allows self loops
+    ///
+    /// This is checked in addition to allow_edge and is rejected with DisAllowedEdge::SelfLoop
+    fn allow_self_loop(
+        &self,
+        _edge_ty: <Self::E as Typed>::Type,
+        _node_ty: <Self::N as Typed>::Type,
+    ) -> bool {
+        true
+    }
+
+    /// After the edges of a node has been added check if the node is valid
+    ///
+    /// The node type is given along with the types of all its outgoing and incoming edges.
+    /// If the node is missing an edge return Err(EdgeType) with the type of the missing edge
+    fn validate_node(
+        &self,
+        _node_ty: <Self::N as Typed>::Type,
+        _outgoing_edges: &[<Self::E as Typed>::Type],
+        _incoming_edges: &[<Self::E as Typed>::Type],
+    ) -> Result<(), <Self::E as Typed>::Type> {
+        Ok(())
+    }
+
+    /// When adding an edge check if the edge should be mirrored by an edge going in the opposite direction
+    ///
+    /// Return None if the edge is not symmetric
+    fn symmetric_edge(&self, _edge_ty: <Self::E as Typed>::Type) -> Option<ReverseSpec<Self::E>> {
+        None
+    }
+}
+
+/// Describes how to create the mirrored edge of a symmetric edge
+pub struct ReverseSpec<E> {
+    /// Create the weight of the mirrored edge from the weight of the original edge
+    ///
+    /// The mirrored edge has to be given a new id which does not collide with any other edge.
+    /// Creating the mirrored edge of the mirrored edge must give back the id of the original edge
+    pub reverse_edge: Box<dyn Fn(&E) -> E>,
+}
+
+/// How to handle the nodes and edges which are not allowed when moving a graph to a new schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisAllowedPolicy {
+    /// Fail with the error from the new schema
+    #[default]
+    Reject,
+    /// Remove them from the graph
+    Drop,
 }
 
 #[derive(Debug)]
@@ -49,18 +134,53 @@ pub enum DisAllowedNode {
 
 #[derive(Debug)]
 pub enum DisAllowedEdge {
+    /// The source has too many outgoing edges of the type
     ToManyOutgoing,
+    /// The target has too many incoming edges of the type
     ToManyIncoming,
     InvalidType,
+    SelfLoop,
+}
+
+impl DisAllowedEdge {
+    /// The direction in which the quantity limit was exceeded
+    ///
+    /// Returns None if the edge was not disallowed due to its quantity
+    pub fn quantity_direction(&self) -> Option<Direction> {
+        match self {
+            DisAllowedEdge::ToManyOutgoing => Some(Direction::Outgoing),
+            DisAllowedEdge::ToManyIncoming => Some(Direction::Incoming),
+            DisAllowedEdge::InvalidType | DisAllowedEdge::SelfLoop => None,
+        }
+    }
+}
+
+impl Display for DisAllowedEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisAllowedEdge::ToManyOutgoing => {
+                write!(f, "too many outgoing edges of the type from the source")
+            }
+            DisAllowedEdge::ToManyIncoming => {
+                write!(f, "too many incoming edges of the type into the target")
+            }
+            DisAllowedEdge::InvalidType => write!(f, "the type not being allowed"),
+            DisAllowedEdge::SelfLoop => write!(f, "self loops not being allowed"),
+        }
+    }
 }
 
 /// Trait indicating a type can be used as a key in the graph
 ///
 /// Mostly common key types is integers and uuid's.
 /// By implementing this trait more exotic types can be used aswell
-pub trait Key: Hash + Debug + PartialEq + Eq + Copy {}
+///
+/// Keys are cloned whenever the graph needs to keep or return a copy of them.
+/// For Copy keys such as integers this is just a copy, while keys such as String allocate on every clone,
+/// so interning them to integers is still faster for large graphs
+pub trait Key: Hash + Debug + PartialEq + Eq + Clone {}
 
-impl<K> Key for K where K: Hash + Debug + Eq + Copy {}
+impl<K> Key for K where K: Hash + Debug + Eq + Clone {}
 
 /// Provide a getter and setter for the id of a node or edge
 pub trait Id<K: Key> {
@@ -70,7 +190,7 @@ pub trait Id<K: Key> {
 
 impl<T: Key> Id<T> for T {
     fn get_id(&self) -> T {
-        *self
+        self.clone()
     }
 
     fn set_id(&mut self, new_id: T) {
@@ -150,32 +270,30 @@ impl<'a, O1, O2, NK, EK, S, T> Downcast<'a, NK, EK, Either<&'a O1, &'a O2>, S> f
     }
 }
 
-/*impl<'a, O1, O2, NK, EK, S, T> DowncastMut<'a, NK, EK, Either<&'a mut O1, &'a mut O2>, S> for T
+impl<'a, O1, O2, NK, EK, S, T> DowncastMut<'a, NK, EK, Either<&'a mut O1, &'a mut O2>, S> for T
     where
-        T: DowncastMut<'a, NK, EK, &'a mut O1, S> + DowncastMut<'a, NK, EK, &'a mut O2, S> + Typed,
+        T: for<'c> DowncastMut<'c, NK, EK, &'c mut O1, S> + for<'c> DowncastMut<'c, NK, EK, &'c mut O2, S> + Typed,
         O1: Typed,
         O2: Typed,
         NK: Key,
         EK: Key,
         S: SchemaExt<NK, EK>
 {
-    fn downcast_mut<'b: 'a>(&'a mut self) -> SchemaResult<Either<&'a mut O1, &'a mut O2>, NK, EK, S> {
-        let n1 = DowncastMut::<'a, NK, EK, &'a mut O1, S>::downcast_mut(self);
+    fn downcast_mut<'b: 'a>(&'b mut self) -> SchemaResult<Either<&'a mut O1, &'a mut O2>, NK, EK, S> {
+        // A failed downcast keeps self borrowed for the rest of the function
+        // So each type is first tried using a short lived borrow
+        let is_left = DowncastMut::<'_, NK, EK, &mut O1, S>::downcast_mut(&mut *self).is_ok();
 
-        if let Ok(n1) = n1 {
-            return Ok(Either::Left(n1));
+        if is_left {
+            return DowncastMut::<'a, NK, EK, &'a mut O1, S>::downcast_mut(self).map(Either::Left);
         }
 
-        drop(n1);
-
-        let n2 = DowncastMut::<'a, NK, EK, &'a mut O2, S>::downcast_mut(self);
+        let is_right = DowncastMut::<'_, NK, EK, &mut O2, S>::downcast_mut(&mut *self).is_ok();
 
-        if let Ok(n2) = n2 {
-            return Ok(Either::Right(n2));
+        if is_right {
+            return DowncastMut::<'a, NK, EK, &'a mut O2, S>::downcast_mut(self).map(Either::Right);
         }
 
-        drop(n2);
-
         Err(SchemaError::<NK, EK, S>::DownCastFailed(format!("{:?} or {:?}", type_name::<O1>(), type_name::<O2>()), self.get_type().to_string()))
     }
-}*/
\ No newline at end of file
+}
\ No newline at end of file
@@ -1,8 +1,14 @@
-use crate::{Id, Key, SchemaExt, SchemaResult, Typed, TypedGraph};
-use std::iter::{once, Once};
+use crate::{EdgeRef, Id, Key, SchemaExt, SchemaResult, Typed, TypedError, TypedGraph};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::BuildHasher;
+use std::iter::{from_fn, once, Once};
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
 
 #[derive(Clone)]
-pub struct GraphWalker<'a, T, State, NK, EK, S, Front>
+pub struct GraphWalker<'a, T, State, NK, EK, S, Front, H = RandomState>
 where
     NK: Key,
     EK: Key,
@@ -10,36 +16,62 @@ where
     State: Clone,
     Front: Iterator<Item = (State, SchemaResult<T, NK, EK, S>)>,
 {
-    g: &'a TypedGraph<NK, EK, S>,
+    g: &'a TypedGraph<NK, EK, S, H>,
     front: Front,
+    /// Nodes visited by the walker
+    ///
+    /// This is created by the first dedup point and shared with all later ones
+    visited: Option<Arc<Mutex<HashSet<NK>>>>,
 }
 
+/// Front of the walker where the concrete iterator type has been erased
+type BoxedFront<'a, T, State, NK, EK, S> =
+    Box<dyn Iterator<Item = (State, SchemaResult<T, NK, EK, S>)> + 'a>;
+
 /// Type storing the value and state of the walker at a given point
 pub struct WalkerTarget<T, State> {
     pub val: T,
     pub state: State,
 }
 
-impl<'a, T, State, NK, EK, S, Progress> GraphWalker<'a, T, State, NK, EK, S, Progress>
+/// State of a walker which keeps track of the nodes visited by each branch
+#[derive(Clone, Debug)]
+pub struct WalkerPath<NK, State> {
+    /// Ids of the nodes visited by the branch in the order they were visited
+    pub path: Vec<NK>,
+    pub state: State,
+}
+
+impl<'a, T, State, NK, EK, S, Progress, H> GraphWalker<'a, T, State, NK, EK, S, Progress, H>
 where
     NK: Key,
     EK: Key,
     S: SchemaExt<NK, EK>,
+    H: BuildHasher,
     State: Clone,
     Progress: Iterator<Item = (State, SchemaResult<T, NK, EK, S>)> + 'a,
 {
     pub fn new(
-        g: &'a TypedGraph<NK, EK, S>,
-    ) -> GraphWalker<'a, (), (), NK, EK, S, impl Iterator<Item = ((), SchemaResult<(), NK, EK, S>)>>
-    {
+        g: &'a TypedGraph<NK, EK, S, H>,
+    ) -> GraphWalker<
+        'a,
+        (),
+        (),
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = ((), SchemaResult<(), NK, EK, S>)>,
+        H,
+    > {
         GraphWalker {
             g,
             front: once(((), Ok(()))),
+            visited: Default::default(),
         }
     }
 
     pub fn new_from(
-        g: &'a TypedGraph<NK, EK, S>,
+        g: &'a TypedGraph<NK, EK, S, H>,
         start: NK,
     ) -> GraphWalker<
         'a,
@@ -49,10 +81,12 @@ where
         EK,
         S,
         impl Iterator<Item = ((), SchemaResult<&'a S::N, NK, EK, S>)>,
+        H,
     > {
         GraphWalker {
             g,
             front: g.get_node_safe(start).map(|n| ((), Ok(n))).into_iter(),
+            visited: Default::default(),
         }
     }
 
@@ -67,6 +101,7 @@ where
         EK,
         S,
         impl Iterator<Item = (NewState, SchemaResult<T, NK, EK, S>)>,
+        H,
     >
     where
         NewState: Clone,
@@ -74,6 +109,7 @@ where
         GraphWalker {
             g: self.g,
             front: self.front.map(move |(_, res)| (new_state.clone(), res)),
+            visited: self.visited,
         }
     }
 
@@ -89,6 +125,7 @@ where
         EK,
         S,
         impl Iterator<Item = (State, SchemaResult<NewT, NK, EK, S>)> + 'b,
+        H,
     >
     where
         'a: 'b,
@@ -97,7 +134,7 @@ where
         StateAddition: Clone + 'b,
         <NextStep as IntoIterator>::IntoIter: 'b,
         NextStep: IntoIterator<Item = (StateAddition, NewT)>,
-        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S>) -> SchemaResult<NextStep, NK, EK, S> + 'b,
+        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S, H>) -> SchemaResult<NextStep, NK, EK, S> + 'b,
     {
         GraphWalker {
             g: self.g,
@@ -122,7 +159,8 @@ where
                             )
                         )
                 )
-                .flatten()
+                .flatten(),
+            visited: self.visited,
         }
     }
 
@@ -139,6 +177,7 @@ where
         EK,
         S,
         impl Iterator<Item = (State, SchemaResult<NewT, NK, EK, S>)> + 'b,
+        H,
     >
     where
         'a: 'b,
@@ -147,7 +186,7 @@ where
         StateAddition: Clone + 'b,
         <NextStep as IntoIterator>::IntoIter: 'b,
         NextStep: IntoIterator<Item = (StateAddition, NewT)>,
-        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S>) -> SchemaResult<NextStep, NK, EK, S> + 'b,
+        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S, H>) -> SchemaResult<NextStep, NK, EK, S> + 'b,
         UpdateState: Fn(State, StateAddition) -> State + 'b + Copy,
     {
         GraphWalker {
@@ -173,7 +212,377 @@ where
                             )
                         )
                 )
-                .flatten()
+                .flatten(),
+            visited: self.visited,
+        }
+    }
+
+    /// Moves the walker forward using a step which can see the current state of the branch
+    ///
+    /// This allows the traversal to depend on what the branch has accumulated so far,
+    /// such as not moving to a node which is already part of the path.
+    /// The state is then updated the same way as in progress_with_state
+    #[allow(clippy::type_complexity)]
+    pub fn progress_with_state_aware<'b, NewT, NextStep, StateAddition, WalkerStep, UpdateState>(
+        self,
+        walker_step: WalkerStep,
+        update_state: UpdateState,
+    ) -> GraphWalker<
+        'b,
+        NewT,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<NewT, NK, EK, S>)> + 'b,
+        H,
+    >
+    where
+        'a: 'b,
+        'b: 'a,
+        NewT: 'b,
+        State: 'b,
+        StateAddition: 'b,
+        <NextStep as IntoIterator>::IntoIter: 'b,
+        NextStep: IntoIterator<Item = (StateAddition, NewT)>,
+        WalkerStep:
+            Fn(&State, T, &'a TypedGraph<NK, EK, S, H>) -> SchemaResult<NextStep, NK, EK, S> + 'b,
+        UpdateState: Fn(State, StateAddition) -> State + 'b + Copy,
+    {
+        let g = self.g;
+        GraphWalker {
+            g,
+            front: self.front.flat_map(
+                move |(state, res)| -> BoxedFront<'b, NewT, State, NK, EK, S> {
+                    match res.and_then(|t| walker_step(&state, t, g)) {
+                        Ok(inner) => Box::new(inner.into_iter().map(move |(addition, t)| {
+                            (update_state(state.clone(), addition), Ok(t))
+                        })),
+                        Err(e) => Box::new(once((state, Err(e)))),
+                    }
+                },
+            ),
+            visited: self.visited,
+        }
+    }
+
+    /// Moves the walker forward along the returned edges and updates the state of the branch using the traversed edge
+    #[allow(clippy::type_complexity)]
+    pub fn progress_edges<'b, NewT, NextStep, WalkerStep, UpdateState>(
+        self,
+        walker_step: WalkerStep,
+        update_state: UpdateState,
+    ) -> GraphWalker<
+        'b,
+        NewT,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<NewT, NK, EK, S>)> + 'b,
+        H,
+    >
+    where
+        'a: 'b,
+        'b: 'a,
+        NewT: 'b,
+        State: 'b,
+        <NextStep as IntoIterator>::IntoIter: 'b,
+        NextStep: IntoIterator<Item = (EdgeRef<'a, NK, EK, S>, NewT)>,
+        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S, H>) -> SchemaResult<NextStep, NK, EK, S> + 'b,
+        UpdateState: Fn(State, &EdgeRef<'a, NK, EK, S>) -> State + 'b + Copy,
+    {
+        let g = self.g;
+        GraphWalker {
+            g,
+            front: self.front.flat_map(
+                move |(state, res)| -> BoxedFront<'b, NewT, State, NK, EK, S> {
+                    match res.and_then(|t| walker_step(t, g)) {
+                        Ok(inner) => Box::new(
+                            inner
+                                .into_iter()
+                                .map(move |(edge, t)| (update_state(state.clone(), &edge), Ok(t))),
+                        ),
+                        Err(e) => Box::new(once((state, Err(e)))),
+                    }
+                },
+            ),
+            visited: self.visited,
+        }
+    }
+
+    /// Moves the walker forward n times without changing the state of the branch
+    ///
+    /// This is the same as calling progress n times with the same step
+    #[allow(clippy::type_complexity)]
+    pub fn progress_n<NextStep, StateAddition, WalkerStep>(
+        self,
+        n: usize,
+        walker_step: WalkerStep,
+    ) -> GraphWalker<
+        'a,
+        T,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<T, NK, EK, S>)> + 'a,
+        H,
+    >
+    where
+        T: 'a,
+        State: 'a,
+        <NextStep as IntoIterator>::IntoIter: 'a,
+        NextStep: IntoIterator<Item = (StateAddition, T)>,
+        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S, H>) -> SchemaResult<NextStep, NK, EK, S> + 'a,
+    {
+        let g = self.g;
+        let walker_step = Rc::new(walker_step);
+
+        let mut front: BoxedFront<'a, T, State, NK, EK, S> = Box::new(self.front);
+        for _ in 0..n {
+            let walker_step = walker_step.clone();
+            front = Box::new(front.flat_map(
+                move |(state, res)| -> BoxedFront<'a, T, State, NK, EK, S> {
+                    match res.and_then(|t| walker_step(t, g)) {
+                        Ok(inner) => {
+                            Box::new(inner.into_iter().map(move |(_, t)| (state.clone(), Ok(t))))
+                        }
+                        Err(e) => Box::new(once((state, Err(e)))),
+                    }
+                },
+            ));
+        }
+
+        GraphWalker {
+            g,
+            front,
+            visited: self.visited,
+        }
+    }
+
+    /// Moves the walker forward until the stop condition holds without changing the state of the branch
+    ///
+    /// A branch is no longer expanded once the condition holds for its current value, instead the value is yielded.
+    /// Branches are expanded breadth first, so the values closest to the start are yielded first.
+    /// Branches which dies out before the condition holds are removed.
+    /// Note that a branch which keeps going around a cycle without the condition holding will never stop
+    #[allow(clippy::type_complexity)]
+    pub fn progress_until<NextStep, StateAddition, WalkerStep, Stop>(
+        self,
+        walker_step: WalkerStep,
+        stop: Stop,
+    ) -> GraphWalker<
+        'a,
+        T,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<T, NK, EK, S>)> + 'a,
+        H,
+    >
+    where
+        T: 'a,
+        State: 'a,
+        NextStep: IntoIterator<Item = (StateAddition, T)>,
+        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S, H>) -> SchemaResult<NextStep, NK, EK, S> + 'a,
+        Stop: Fn(&T) -> bool + 'a,
+    {
+        let g = self.g;
+        let mut front = self.front;
+        let mut queue = VecDeque::new();
+
+        GraphWalker {
+            g,
+            front: from_fn(move || loop {
+                // Only take a new branch from the front once all the current branches have been expanded
+                let (state, res) = queue.pop_front().or_else(|| front.next())?;
+
+                let t = match res {
+                    Ok(t) if !stop(&t) => t,
+                    res => return Some((state, res)),
+                };
+
+                match walker_step(t, g) {
+                    Ok(inner) => {
+                        queue.extend(inner.into_iter().map(|(_, t)| (state.clone(), Ok(t))))
+                    }
+                    Err(e) => return Some((state, Err(e))),
+                }
+            }),
+            visited: self.visited,
+        }
+    }
+
+    /// Remove all branches whose current value does not satisfy the predicate
+    ///
+    /// Branches containing an error are always kept
+    #[allow(clippy::type_complexity)]
+    pub fn filter<F>(
+        self,
+        f: F,
+    ) -> GraphWalker<
+        'a,
+        T,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<T, NK, EK, S>)>,
+        H,
+    >
+    where
+        F: Fn(&T) -> bool,
+    {
+        GraphWalker {
+            g: self.g,
+            front: self.front.filter(move |(_, res)| match res {
+                Ok(t) => f(t),
+                Err(_) => true,
+            }),
+            visited: self.visited,
+        }
+    }
+
+    /// Transform the current value of all branches and remove the branches where None is returned
+    ///
+    /// Branches containing an error are always kept
+    #[allow(clippy::type_complexity)]
+    pub fn filter_map<NewT, F>(
+        self,
+        f: F,
+    ) -> GraphWalker<
+        'a,
+        NewT,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<NewT, NK, EK, S>)>,
+        H,
+    >
+    where
+        F: Fn(T) -> Option<NewT>,
+    {
+        GraphWalker {
+            g: self.g,
+            front: self.front.filter_map(move |(state, res)| match res {
+                Ok(t) => f(t).map(|t| (state, Ok(t))),
+                Err(e) => Some((state, Err(e))),
+            }),
+            visited: self.visited,
+        }
+    }
+
+    /// Limit the number of branches which can pass through this point of the walker
+    ///
+    /// Once the limit is reached the next branch is replaced by a BranchLimitExceeded error and no more branches are produced.
+    /// Branches are counted as they are pulled, so the branches behind the limit are never created
+    #[allow(clippy::type_complexity)]
+    pub fn bounded(
+        self,
+        max_branches: usize,
+    ) -> GraphWalker<
+        'a,
+        T,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<T, NK, EK, S>)>,
+        H,
+    > {
+        let mut front = self.front;
+        let mut count = 0;
+        let mut exceeded = false;
+        GraphWalker {
+            g: self.g,
+            front: from_fn(move || {
+                if exceeded {
+                    return None;
+                }
+
+                let (state, res) = front.next()?;
+                if count == max_branches {
+                    exceeded = true;
+                    return Some((state, Err(TypedError::BranchLimitExceeded(max_branches))));
+                }
+
+                count += 1;
+                Some((state, res))
+            }),
+            visited: self.visited,
+        }
+    }
+
+    /// Start recording the ids of the nodes visited by each branch
+    ///
+    /// The path of each branch starts at its current node and is extended every time the walker is moved forward using progress_path
+    #[allow(clippy::type_complexity)]
+    pub fn with_path(
+        self,
+    ) -> GraphWalker<
+        'a,
+        T,
+        WalkerPath<NK, State>,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (WalkerPath<NK, State>, SchemaResult<T, NK, EK, S>)>,
+        H,
+    >
+    where
+        T: Deref,
+        T::Target: Id<NK>,
+    {
+        GraphWalker {
+            g: self.g,
+            front: self.front.map(|(state, res)| {
+                let path = res
+                    .as_ref()
+                    .map_or_else(|_| Vec::new(), |t| vec![t.get_id()]);
+                (WalkerPath { path, state }, res)
+            }),
+            visited: self.visited,
+        }
+    }
+
+    /// Remove all branches which are currently at a node that has already been visited by the walker
+    ///
+    /// The visited nodes are shared across the whole walk, so nodes seen at an earlier dedup point are also removed at later ones.
+    /// Only nodes which passes through this combinator or later ones are recorded as visited.
+    /// Calling this after every step ensures that the walker never visits the same node twice
+    #[allow(clippy::type_complexity)]
+    pub fn dedup_by_id(
+        self,
+    ) -> GraphWalker<
+        'a,
+        T,
+        State,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (State, SchemaResult<T, NK, EK, S>)>,
+        H,
+    >
+    where
+        T: Deref,
+        T::Target: Id<NK>,
+    {
+        let visited = self.visited.unwrap_or_default();
+        let seen = visited.clone();
+        GraphWalker {
+            g: self.g,
+            front: self.front.filter(move |(_, res)| match res {
+                Ok(t) => seen
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(t.get_id()),
+                // Errors are always let through
+                Err(_) => true,
+            }),
+            visited: Some(visited),
         }
     }
 
@@ -205,6 +614,21 @@ where
         Ok(results.into_iter().collect())
     }
 
+    /// Reduce all the branches into a single value
+    ///
+    /// Returns the first error encountered by any of the branches
+    pub fn fold<B, F>(self, init: B, mut f: F) -> SchemaResult<B, NK, EK, S>
+    where
+        F: FnMut(B, T) -> B,
+    {
+        let mut acc = init;
+        for (_, res) in self.front {
+            acc = f(acc, res?);
+        }
+
+        Ok(acc)
+    }
+
     pub fn many_with_state<TStateCollection>(self) -> SchemaResult<TStateCollection, NK, EK, S>
     where
         TStateCollection: FromIterator<WalkerTarget<T, State>>,
@@ -216,37 +640,185 @@ where
 
         Ok(results.into_iter().collect())
     }
+
+    /// Retrieve the state of all the branches keyed by the id of the node they ended at
+    ///
+    /// If multiple branches end at the same node the state of the last branch is kept
+    pub fn many_with_state_map(self) -> SchemaResult<HashMap<NK, State>, NK, EK, S>
+    where
+        T: Deref,
+        T::Target: Id<NK>,
+    {
+        self.many_with_state_map_by(|_, state| state)
+    }
+
+    /// Same as many_with_state_map but the states of branches ending at the same node are combined
+    ///
+    /// The combine function is given the state stored so far followed by the state of the next branch
+    pub fn many_with_state_map_by<F>(
+        self,
+        mut combine: F,
+    ) -> SchemaResult<HashMap<NK, State>, NK, EK, S>
+    where
+        T: Deref,
+        T::Target: Id<NK>,
+        F: FnMut(State, State) -> State,
+    {
+        let mut results = HashMap::new();
+        for (state, res) in self.front {
+            let id = res?.get_id();
+            let state = match results.remove(&id) {
+                Some(previous) => combine(previous, state),
+                None => state,
+            };
+            results.insert(id, state);
+        }
+
+        Ok(results)
+    }
 }
 
-pub trait ToGraphWalker<NK, EK, S>: Id<NK>
+impl<'a, T, State, NK, EK, S, Progress, H>
+    GraphWalker<'a, T, WalkerPath<NK, State>, NK, EK, S, Progress, H>
 where
     NK: Key,
     EK: Key,
     S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+    State: Clone,
+    Progress: Iterator<Item = (WalkerPath<NK, State>, SchemaResult<T, NK, EK, S>)> + 'a,
+{
+    /// Moves the walker forward and adds the new node to the path of the branch
+    #[allow(clippy::type_complexity)]
+    pub fn progress_path<'b, NewT, NextStep, StateAddition, WalkerStep>(
+        self,
+        walker_step: WalkerStep,
+    ) -> GraphWalker<
+        'b,
+        NewT,
+        WalkerPath<NK, State>,
+        NK,
+        EK,
+        S,
+        impl Iterator<Item = (WalkerPath<NK, State>, SchemaResult<NewT, NK, EK, S>)> + 'b,
+        H,
+    >
+    where
+        'a: 'b,
+        'b: 'a,
+        NewT: Deref + 'b,
+        NewT::Target: Id<NK>,
+        State: 'b,
+        <NextStep as IntoIterator>::IntoIter: 'b,
+        NextStep: IntoIterator<Item = (StateAddition, NewT)>,
+        WalkerStep: Fn(T, &'a TypedGraph<NK, EK, S, H>) -> SchemaResult<NextStep, NK, EK, S> + 'b,
+    {
+        self.progress_with_state(
+            move |t, g| {
+                walker_step(t, g).map(|inner| inner.into_iter().map(|(_, t)| (t.get_id(), t)))
+            },
+            |mut state: WalkerPath<NK, State>, id| {
+                state.path.push(id);
+                state
+            },
+        )
+    }
+
+    /// Retrieve all the branches along with the path taken to reach them
+    pub fn many_with_path<TPathCollection>(self) -> SchemaResult<TPathCollection, NK, EK, S>
+    where
+        TPathCollection: FromIterator<(Vec<NK>, T)>,
+    {
+        let mut results = Vec::new();
+        for (state, res) in self.front {
+            results.push((state.path, res?))
+        }
+
+        Ok(results.into_iter().collect())
+    }
+}
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Create a walker with a branch starting at each of the seed nodes
+    ///
+    /// Seeds which are not in the graph are skipped if skip_missing is set, otherwise MissingNode is returned
+    #[allow(clippy::type_complexity)]
+    pub fn walker_from_many<I>(
+        &self,
+        seeds: I,
+        skip_missing: bool,
+    ) -> SchemaResult<
+        GraphWalker<
+            '_,
+            &S::N,
+            (),
+            NK,
+            EK,
+            S,
+            impl Iterator<Item = ((), SchemaResult<&S::N, NK, EK, S>)>,
+            H,
+        >,
+        NK,
+        EK,
+        S,
+    >
+    where
+        I: IntoIterator<Item = NK>,
+    {
+        let mut nodes = Vec::new();
+        for id in seeds {
+            match self.get_node(id) {
+                Ok(node) => nodes.push(node),
+                Err(_) if skip_missing => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(GraphWalker {
+            g: self,
+            front: nodes.into_iter().map(|n| ((), Ok(n))),
+            visited: Default::default(),
+        })
+    }
+}
+
+pub trait ToGraphWalker<NK, EK, S, H = RandomState>: Id<NK>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
 {
     fn to_walker<'a>(
         &'a self,
-        g: &'a TypedGraph<NK, EK, S>,
+        g: &'a TypedGraph<NK, EK, S, H>,
     ) -> SchemaResult<
-        GraphWalker<'a, &Self, (), NK, EK, S, Once<((), SchemaResult<&'a Self, NK, EK, S>)>>,
+        GraphWalker<'a, &Self, (), NK, EK, S, Once<((), SchemaResult<&'a Self, NK, EK, S>)>, H>,
         NK,
         EK,
         S,
     >;
 }
 
-impl<T, NK, EK, S> ToGraphWalker<NK, EK, S> for T
+impl<T, NK, EK, S, H> ToGraphWalker<NK, EK, S, H> for T
 where
     T: Typed<Type = <S::N as Typed>::Type> + Id<NK>,
     NK: Key,
     EK: Key,
     S: SchemaExt<NK, EK>,
+    H: BuildHasher,
 {
     fn to_walker<'a>(
         &'a self,
-        g: &'a TypedGraph<NK, EK, S>,
+        g: &'a TypedGraph<NK, EK, S, H>,
     ) -> SchemaResult<
-        GraphWalker<'a, &Self, (), NK, EK, S, Once<((), SchemaResult<&'a Self, NK, EK, S>)>>,
+        GraphWalker<'a, &Self, (), NK, EK, S, Once<((), SchemaResult<&'a Self, NK, EK, S>)>, H>,
         NK,
         EK,
         S,
@@ -256,6 +828,442 @@ where
         Ok(GraphWalker {
             g,
             front: once(((), Ok(self))),
+            visited: Default::default(),
+        })
+    }
+}
+
+#[test]
+fn walker_dedup_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..3 {
+        g.add_node((i, 0))?;
+    }
+
+    // A cycle 0 -> 1 -> 2 -> 0 along with a shortcut 0 -> 2
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(1, 2, (1, 0))?;
+    g.add_edge(2, 0, (2, 0))?;
+    g.add_edge(0, 2, (3, 0))?;
+
+    let ids = |nodes: Vec<&TestNode>| {
+        nodes
+            .into_iter()
+            .map(|n| n.get_id())
+            .collect::<Vec<usize>>()
+    };
+
+    // Without dedup the walker keeps going around the cycle
+    let nodes: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(move_forward)
+        .progress(move_forward)
+        .progress(move_forward)
+        .many()?;
+    assert_eq!(ids(nodes), vec![0, 1, 2]);
+
+    let nodes: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .dedup_by_id()
+        .progress(move_forward)
+        .dedup_by_id()
+        .many()?;
+    assert_eq!(ids(nodes), vec![1, 2]);
+
+    // Every node has been visited after the first step
+    let nodes: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .dedup_by_id()
+        .progress(move_forward)
+        .dedup_by_id()
+        .progress(move_forward)
+        .dedup_by_id()
+        .progress(move_forward)
+        .dedup_by_id()
+        .many()?;
+    assert!(nodes.is_empty());
+
+    // Sharing the visited nodes does not prevent the walker from being sent to another thread
+    fn assert_send<T: Send>(_: &T) {}
+    let walker = g.get_node(0)?.to_walker(&g)?.dedup_by_id().dedup_by_id();
+    assert_send(&walker);
+
+    Ok(())
+}
+
+#[test]
+fn walker_progress_n_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..5 {
+        g.add_node((i, 0))?;
+    }
+
+    // A line 0 -> 1 -> 2 -> 3 -> 4
+    for i in 0..4 {
+        g.add_edge(i, i + 1, (i, 0))?;
+    }
+
+    let start = g.get_node(0)?;
+    for n in 0..5 {
+        let node = start.to_walker(&g)?.progress_n(n, move_forward).one()?;
+        assert_eq!(node.map(|n| n.get_id()), Some(n));
+    }
+
+    let node = start.to_walker(&g)?.progress_n(5, move_forward).one()?;
+    assert!(node.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn walker_progress_until_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..6 {
+        g.add_node((i, i % 2))?;
+    }
+
+    // Two paths of different length from 0 to nodes of type 1
+    // 0 -> 2 -> 4 -> 5
+    // 0 -> 3
+    g.add_edge(0, 2, (0, 0))?;
+    g.add_edge(2, 4, (1, 0))?;
+    g.add_edge(4, 5, (2, 0))?;
+    g.add_edge(0, 3, (3, 0))?;
+
+    let nodes: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress_until(move_forward, |n| n.get_type() == 1)
+        .many()?;
+    let ids: Vec<usize> = nodes.into_iter().map(|n| n.get_id()).collect();
+
+    // The closest node is found first
+    assert_eq!(ids, vec![3, 5]);
+
+    Ok(())
+}
+
+#[test]
+fn walker_path_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..4 {
+        g.add_node((i, 0))?;
+    }
+
+    // Two routes from 0 to 3
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(1, 3, (1, 0))?;
+    g.add_edge(0, 2, (2, 0))?;
+    g.add_edge(2, 3, (3, 0))?;
+
+    let paths: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .with_path()
+        .progress_path(move_forward)
+        .progress_path(move_forward)
+        .many_with_path()?;
+
+    let paths: Vec<(Vec<usize>, usize)> = paths
+        .into_iter()
+        .map(|(path, n)| (path, n.get_id()))
+        .collect();
+    assert_eq!(paths, vec![(vec![0, 1, 3], 3), (vec![0, 2, 3], 3)]);
+
+    Ok(())
+}
+
+#[test]
+fn walker_fold_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..4 {
+        g.add_node((i, i))?;
+    }
+
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(0, 2, (1, 0))?;
+    g.add_edge(0, 3, (2, 0))?;
+
+    let sum = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(move_forward)
+        .fold(0, |acc, n| acc + n.get_type())?;
+    // Sum the types of all the reachable nodes
+    assert_eq!(sum, 6);
+
+    // Errors are propagated in the same way as many
+    let res = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(|_, g| g.get_node(4).map(|n| once(((), n))))
+        .fold(0, |acc, n| acc + n.get_type());
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn walker_filter_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..5 {
+        g.add_node((i, i % 2))?;
+    }
+
+    for i in 1..5 {
+        g.add_edge(0, i, (i, 0))?;
+    }
+
+    let nodes: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(move_forward)
+        .filter(|n| n.get_type() == 1)
+        .many()?;
+    let ids: Vec<usize> = nodes.into_iter().map(|n| n.get_id()).collect();
+    assert_eq!(ids, vec![1, 3]);
+
+    let ids: Vec<usize> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(move_forward)
+        .filter_map(|n| {
+            let id: usize = n.get_id();
+            (n.get_type() == 0).then_some(id * 10)
         })
+        .many()?;
+    assert_eq!(ids, vec![20, 40]);
+
+    Ok(())
+}
+
+#[test]
+fn walker_progress_edges_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..3 {
+        g.add_node((i, 0))?;
     }
+
+    g.add_edge(0, 1, (0, 1))?;
+    g.add_edge(1, 2, (1, 2))?;
+
+    fn move_forward_edges<'a>(
+        n: &'a TestNode,
+        g: &'a TestGraph,
+    ) -> TestResult<impl Iterator<Item = (EdgeRef<'a, usize, usize, TestSchema>, &'a TestNode)>>
+    {
+        Ok(g.get_outgoing(n.get_id())?
+            .map(|e| (e.clone(), g.get_node(e.get_target()).unwrap())))
+    }
+
+    let targets: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .set_state(Vec::new())
+        .progress_edges(move_forward_edges, |mut state, e| {
+            state.push(e.get_type());
+            state
+        })
+        .progress_edges(move_forward_edges, |mut state, e| {
+            state.push(e.get_type());
+            state
+        })
+        .many_with_state()?;
+
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].state, vec![1, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn walker_state_map_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..4 {
+        g.add_node((i, 0))?;
+    }
+
+    // Two branches reach node 3 with different states
+    g.add_edge(0, 1, (0, 1))?;
+    g.add_edge(0, 2, (1, 2))?;
+    g.add_edge(1, 3, (2, 3))?;
+    g.add_edge(2, 3, (3, 4))?;
+
+    let walker = || -> TestResult<_> {
+        Ok(g.get_node(0)?
+            .to_walker(&g)?
+            .set_state(0)
+            .progress_with_state(move_forward, |state, ty| state + ty))
+    };
+
+    let states = walker()?.many_with_state_map()?;
+    assert_eq!(states, HashMap::from([(1, 1), (2, 2)]));
+
+    let states = walker()?
+        .progress_with_state(move_forward, |state, ty| state + ty)
+        .many_with_state_map()?;
+    assert_eq!(states, HashMap::from([(3, 6)]));
+
+    let states = walker()?
+        .progress_with_state(move_forward, |state, ty| state + ty)
+        .many_with_state_map_by(|a, b| a.min(b))?;
+    assert_eq!(states, HashMap::from([(3, 4)]));
+
+    Ok(())
+}
+
+#[test]
+fn walker_bounded_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::cell::Cell;
+
+    let mut g = TestGraph::default();
+    for i in 0..6 {
+        g.add_node((i, 0))?;
+    }
+    for i in 1..6 {
+        g.add_edge(0, i, (i, 0))?;
+    }
+
+    let nodes: Vec<_> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(move_forward)
+        .bounded(5)
+        .many()?;
+    assert_eq!(nodes.len(), 5);
+
+    let res: TestResult<Vec<_>> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(move_forward)
+        .bounded(3)
+        .many();
+    assert!(matches!(res, Err(TypedError::BranchLimitExceeded(3))));
+
+    // Only the branches which are pulled are created
+    let pulled = Cell::new(0);
+    let first = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .progress(|n, g| {
+            move_forward(n, g).map(|nodes| nodes.inspect(|_| pulled.set(pulled.get() + 1)))
+        })
+        .bounded(1)
+        .one()?;
+    assert!(first.is_some());
+    assert_eq!(pulled.get(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn walker_from_many_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..5 {
+        g.add_node((i, 0))?;
+    }
+    g.add_edge(0, 2, (0, 0))?;
+    g.add_edge(1, 3, (1, 0))?;
+    g.add_edge(1, 4, (2, 0))?;
+
+    let nodes: Vec<_> = g
+        .walker_from_many([0, 1], false)?
+        .progress(move_forward)
+        .many()?;
+    let ids: Vec<usize> = nodes.into_iter().map(|n| n.get_id()).collect();
+    assert_eq!(ids, vec![2, 3, 4]);
+
+    // Unknown seeds are either skipped or reported
+    let nodes: Vec<_> = g.walker_from_many([9, 1], true)?.many()?;
+    assert_eq!(nodes.len(), 1);
+    assert!(matches!(
+        g.walker_from_many([9, 1], false).map(|_| ()),
+        Err(TypedError::MissingNode(9))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn walker_progress_with_state_aware_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..4 {
+        g.add_node((i, 0))?;
+    }
+
+    // A cycle 0 -> 1 -> 2 -> 0 with a branch 1 -> 3
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(1, 2, (1, 0))?;
+    g.add_edge(2, 0, (2, 0))?;
+    g.add_edge(1, 3, (3, 0))?;
+
+    /// Only move to nodes which are not already in the path of the branch
+    #[allow(clippy::ptr_arg)]
+    fn step<'a>(
+        path: &Vec<usize>,
+        n: &'a TestNode,
+        g: &'a TestGraph,
+    ) -> TestResult<Vec<(usize, &'a TestNode)>> {
+        let mut next = Vec::new();
+        for (_, n) in move_forward(n, g)? {
+            let id: usize = n.get_id();
+            if !path.contains(&id) {
+                next.push((id, n));
+            }
+        }
+        Ok(next)
+    }
+
+    fn update(mut path: Vec<usize>, id: usize) -> Vec<usize> {
+        path.push(id);
+        path
+    }
+
+    let targets: Vec<WalkerTarget<&TestNode, Vec<usize>>> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .set_state(vec![0])
+        .progress_with_state_aware(step, update)
+        .progress_with_state_aware(step, update)
+        .progress_with_state_aware(step, update)
+        .many_with_state()?;
+
+    // The cycle back to 0 is pruned so the walk ends after two steps
+    assert!(targets.is_empty());
+
+    let targets: Vec<WalkerTarget<&TestNode, Vec<usize>>> = g
+        .get_node(0)?
+        .to_walker(&g)?
+        .set_state(vec![0])
+        .progress_with_state_aware(step, update)
+        .progress_with_state_aware(step, update)
+        .many_with_state()?;
+    let paths: Vec<_> = targets.into_iter().map(|t| t.state).collect();
+    assert_eq!(paths, vec![vec![0, 1, 2], vec![0, 1, 3]]);
+
+    Ok(())
 }
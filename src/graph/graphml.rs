@@ -0,0 +1,363 @@
+use crate::{
+    EdgeView, GraphIoError, GraphIoResult, Id, Key, SchemaExt, SchemaResult, Typed, TypedError,
+    TypedGraph,
+};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::fmt::{Display, Write};
+use std::hash::BuildHasher;
+use std::io;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub type GraphMLResult<T, NK, EK, S> = Result<
+    T,
+    GraphMLError<
+        NK,
+        EK,
+        <<S as SchemaExt<NK, EK>>::N as Typed>::Type,
+        <<S as SchemaExt<NK, EK>>::E as Typed>::Type,
+    >,
+>;
+
+/// Errors produced while reading a GraphML document
+#[derive(Error, Debug)]
+pub enum GraphMLError<NK, EK, NT, ET> {
+    #[error(transparent)]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("Missing attribute {1} on <{0}>")]
+    MissingAttribute(&'static str, &'static str),
+
+    #[error("Missing type for {0} {1}")]
+    MissingType(&'static str, String),
+
+    #[error("Failed to parse {0} from {1:?}")]
+    InvalidValue(&'static str, String),
+
+    #[error(transparent)]
+    TypedError(#[from] TypedError<NK, EK, NT, ET>),
+}
+
+impl<NK, EK, NT, ET> From<GraphMLError<NK, EK, NT, ET>> for GraphIoError<NK, EK, NT, ET>
+where
+    GraphMLError<NK, EK, NT, ET>: Display,
+{
+    fn from(e: GraphMLError<NK, EK, NT, ET>) -> Self {
+        match e {
+            GraphMLError::TypedError(e) => GraphIoError::TypedError(e),
+            GraphMLError::Xml(e) => GraphIoError::format(e),
+            e => GraphIoError::Format(e.to_string().into()),
+        }
+    }
+}
+
+const NODE_TYPE_KEY: &str = "node_type";
+const EDGE_TYPE_KEY: &str = "edge_type";
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key + Display,
+    EK: Key + Display,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Serialize the graph into a GraphML document
+    ///
+    /// The id of every node and edge is used as the GraphML id and the type is stored as a data attribute.
+    /// Edges are written grouped by their source node in outgoing order.
+    /// Fails if the edges of the graph can not be resolved
+    pub fn to_graphml(&self) -> SchemaResult<String, NK, EK, S> {
+        // Export the edges in outgoing order, stopping at the first edge which can not be resolved
+        let mut error = None;
+        let edges = self
+            .edges_full_in_outgoing_order()
+            .map_while(|edge| edge.map_err(|e| error = Some(e)).ok());
+
+        let mut s = String::new();
+
+        // Writing to a String can not fail
+        let _ = self.write_graphml(&mut s, edges);
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(s),
+        }
+    }
+
+    /// Write the graph as a GraphML document to the writer
+    pub fn save_graphml<W: io::Write>(&self, mut writer: W) -> GraphIoResult<(), NK, EK, S> {
+        writer.write_all(self.to_graphml()?.as_bytes())?;
+        Ok(())
+    }
+
+    fn write_graphml<'a>(
+        &'a self,
+        s: &mut String,
+        edges: impl Iterator<Item = EdgeView<'a, NK, EK, S>>,
+    ) -> std::fmt::Result {
+        writeln!(s, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            s,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            s,
+            r#"  <key id="{}" for="node" attr.name="type" attr.type="string"/>"#,
+            NODE_TYPE_KEY
+        )?;
+        writeln!(
+            s,
+            r#"  <key id="{}" for="edge" attr.name="type" attr.type="string"/>"#,
+            EDGE_TYPE_KEY
+        )?;
+        let edge_default = if S::DIRECTED {
+            "directed"
+        } else {
+            "undirected"
+        };
+        writeln!(
+            s,
+            r#"  <graph id="{}" edgedefault="{}">"#,
+            escape(&self.get_schema().name()),
+            edge_default,
+        )?;
+
+        for node in self.nodes() {
+            writeln!(
+                s,
+                r#"    <node id="{}"><data key="{}">{}</data></node>"#,
+                escape(&node.get_id().to_string()),
+                NODE_TYPE_KEY,
+                escape(&node.get_type().to_string()),
+            )?;
+        }
+
+        for edge in edges {
+            writeln!(
+                s,
+                r#"    <edge id="{}" source="{}" target="{}"><data key="{}">{}</data></edge>"#,
+                escape(&edge.get_id().to_string()),
+                escape(&edge.get_source().to_string()),
+                escape(&edge.get_target().to_string()),
+                EDGE_TYPE_KEY,
+                escape(&edge.get_type().to_string()),
+            )?;
+        }
+
+        writeln!(s, "  </graph>")?;
+        writeln!(s, "</graphml>")?;
+
+        Ok(())
+    }
+}
+
+// Reading a graph creates it with the default hasher, so the type of the graph can be inferred
+impl<NK, EK, S> TypedGraph<NK, EK, S>
+where
+    NK: Key + Display,
+    EK: Key + Display,
+    S: SchemaExt<NK, EK>,
+{
+    /// Read a graph from a GraphML document in the reader
+    ///
+    /// See `from_graphml` for how the nodes and edges are reconstructed
+    pub fn load_graphml<R: io::Read>(schema: S, mut reader: R) -> GraphIoResult<Self, NK, EK, S>
+    where
+        NK: FromStr,
+        EK: FromStr,
+        <S::N as Typed>::Type: FromStr,
+        <S::E as Typed>::Type: FromStr,
+        S::N: From<(NK, <S::N as Typed>::Type)>,
+        S::E: From<(EK, <S::E as Typed>::Type)>,
+    {
+        let mut graphml = String::new();
+        reader.read_to_string(&mut graphml)?;
+        Ok(Self::from_graphml(schema, &graphml)?)
+    }
+
+    /// Read a graph from a GraphML document
+    ///
+    /// Only the id and type of the nodes and edges are stored in the document,
+    /// so the weights are reconstructed from (id, type) pairs.
+    /// Edges are added in document order which preserves the outgoing edge order written by `to_graphml`
+    pub fn from_graphml(schema: S, graphml: &str) -> GraphMLResult<Self, NK, EK, S>
+    where
+        NK: FromStr,
+        EK: FromStr,
+        <S::N as Typed>::Type: FromStr,
+        <S::E as Typed>::Type: FromStr,
+        S::N: From<(NK, <S::N as Typed>::Type)>,
+        S::E: From<(EK, <S::E as Typed>::Type)>,
+    {
+        let mut reader = Reader::from_str(graphml);
+        reader.trim_text(true);
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+
+        // The element currently being read along with the key of the data field we are inside
+        let mut current: Option<GraphMLElement<NK, EK, _, _>> = None;
+        let mut data_key: Option<String> = None;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"node" => current = Some(read_node(&e)?),
+                    b"edge" => current = Some(read_edge(&e)?),
+                    b"data" => data_key = Some(read_attribute(&e, "data", "key")?),
+                    _ => (),
+                },
+                Event::Empty(e) => match e.name().as_ref() {
+                    b"node" => nodes.push(read_node(&e)?),
+                    b"edge" => edges.push(read_edge(&e)?),
+                    _ => (),
+                },
+                Event::Text(e) => {
+                    let text = e.unescape()?;
+                    match (&mut current, data_key.as_deref()) {
+                        (Some(GraphMLElement::Node(_, ty)), Some(NODE_TYPE_KEY)) => {
+                            *ty = Some(parse_value("node type", &text)?);
+                        }
+                        (Some(GraphMLElement::Edge(_, _, _, ty)), Some(EDGE_TYPE_KEY)) => {
+                            *ty = Some(parse_value("edge type", &text)?);
+                        }
+                        _ => (),
+                    }
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"node" | b"edge" => match current.take() {
+                        Some(node @ GraphMLElement::Node(..)) => nodes.push(node),
+                        Some(edge @ GraphMLElement::Edge(..)) => edges.push(edge),
+                        None => (),
+                    },
+                    b"data" => data_key = None,
+                    _ => (),
+                },
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        let mut g = TypedGraph::new(schema);
+
+        // Nodes have to be inserted before any of the edges
+        for node in nodes {
+            if let GraphMLElement::Node(id, ty) = node {
+                let ty = ty.ok_or_else(|| GraphMLError::MissingType("node", id.to_string()))?;
+                g.add_node((id, ty))?;
+            }
+        }
+
+        for edge in edges {
+            if let GraphMLElement::Edge(id, source, target, ty) = edge {
+                let ty = ty.ok_or_else(|| GraphMLError::MissingType("edge", id.to_string()))?;
+                g.add_edge(source, target, (id, ty))?;
+            }
+        }
+
+        Ok(g)
+    }
+}
+
+/// A node or edge which has been read but not yet added to the graph
+enum GraphMLElement<NK, EK, NT, ET> {
+    Node(NK, Option<NT>),
+    Edge(EK, NK, NK, Option<ET>),
+}
+
+type ElementResult<NK, EK, NT, ET> =
+    Result<GraphMLElement<NK, EK, NT, ET>, GraphMLError<NK, EK, NT, ET>>;
+
+fn read_attribute<NK, EK, NT, ET>(
+    e: &BytesStart,
+    element: &'static str,
+    name: &'static str,
+) -> Result<String, GraphMLError<NK, EK, NT, ET>> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(quick_xml::Error::from)?;
+        if attr.key.as_ref() == name.as_bytes() {
+            return Ok(attr.unescape_value()?.into_owned());
+        }
+    }
+
+    Err(GraphMLError::MissingAttribute(element, name))
+}
+
+fn parse_value<T: FromStr, NK, EK, NT, ET>(
+    name: &'static str,
+    value: &str,
+) -> Result<T, GraphMLError<NK, EK, NT, ET>> {
+    value
+        .parse()
+        .map_err(|_| GraphMLError::InvalidValue(name, value.to_string()))
+}
+
+fn read_node<NK: FromStr, EK, NT, ET>(e: &BytesStart) -> ElementResult<NK, EK, NT, ET> {
+    let id = read_attribute(e, "node", "id")?;
+    Ok(GraphMLElement::Node(parse_value("node id", &id)?, None))
+}
+
+fn read_edge<NK: FromStr, EK: FromStr, NT, ET>(e: &BytesStart) -> ElementResult<NK, EK, NT, ET> {
+    let id = read_attribute(e, "edge", "id")?;
+    let source = read_attribute(e, "edge", "source")?;
+    let target = read_attribute(e, "edge", "target")?;
+    Ok(GraphMLElement::Edge(
+        parse_value("edge id", &id)?,
+        parse_value("node id", &source)?,
+        parse_value("node id", &target)?,
+        None,
+    ))
+}
+
+#[test]
+fn graphml_compose_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use fake::Dummy;
+
+    let config = CompleteGraph {
+        nodes: 20,
+        node_types: 5,
+        edge_types: 5,
+    };
+
+    let g = TestGraph::dummy(&config);
+    let s = g.to_graphml()?;
+    let ng = TestGraph::from_graphml(g.get_schema().clone(), &s).unwrap();
+    g.assert_eq(&ng)?;
+
+    Ok(())
+}
+
+#[test]
+fn graphml_save_load_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 1)], true)?;
+    g.add_edge(0, 1, (0, 2))?;
+
+    let mut buffer = Vec::new();
+    g.save_graphml(&mut buffer).unwrap();
+    let ng = TestGraph::load_graphml(TestSchema::new(), buffer.as_slice()).unwrap();
+    g.assert_eq(&ng)?;
+
+    // Every kind of failure is reported through the same error type
+    let invalid_utf8: &[u8] = &[0xff, 0xfe];
+    let err = TestGraph::load_graphml(TestSchema::new(), invalid_utf8).unwrap_err();
+    assert!(matches!(err, GraphIoError::Io(_)));
+
+    let missing_type = r#"<graphml><graph><node id="0"/></graph></graphml>"#;
+    let err = TestGraph::load_graphml(TestSchema::new(), missing_type.as_bytes()).unwrap_err();
+    assert!(matches!(err, GraphIoError::Format(_)));
+
+    let schema = TestSchema::new().node_blacklist(Some(vec![1]));
+    let err = TestGraph::load_graphml(schema, buffer.as_slice()).unwrap_err();
+    assert!(matches!(
+        err,
+        GraphIoError::TypedError(TypedError::InvalidNodeType(1, ..))
+    ));
+
+    Ok(())
+}
@@ -1,23 +1,30 @@
 use crate::{EdgeKey, NodeKey};
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+use std::hash::Hash;
 use std::ops::{Deref, DerefMut};
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub(crate) struct EdgeMetadata<E> {
     pub(crate) weight: E,
     pub(crate) source: NodeKey,
     pub(crate) target: NodeKey,
+    /// The edge going in the opposite direction if the edge is symmetric
+    /// Both edges points to each other and are added and removed together
+    pub(crate) mirror: Option<EdgeKey>,
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
-pub(crate) struct NodeMetadata<N> {
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct NodeMetadata<N, ET, NT> {
     pub(crate) weight: N,
     /// Look Up Table to help quickly find the incoming edges of a given node.
     /// Notice that the contained information can be produced from `edges`.
     /// Since this is duplicate information, no external mutation must be allowed.
-    /// No edge order is guaranteed.
+    /// No edge order is guaranteed unless the schema sets `ORDERED_INCOMING`.
     pub(crate) incoming_edges: IndexSet<EdgeKey>,
 
     /// Same as `incoming_edges` (just for outgoing edges) with a notable exception:
@@ -28,22 +35,89 @@ pub(crate) struct NodeMetadata<N> {
     /// This means that `incoming_edges` can be seen as an expendable cache, but `outgoing_edges`
     /// can not!
     pub(crate) outgoing_edges: IndexSet<EdgeKey>,
+
+    /// Number of outgoing edges grouped by the type of the edge and the type of the target.
+    /// Like `incoming_edges` this is a cache which can be produced from `edges`.
+    /// It is used to check the quantity limits of the schema without looking at every edge.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) outgoing_quantities: Quantities<ET, NT>,
+
+    /// Same as `outgoing_quantities` grouped by the type of the edge and the type of the source
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) incoming_quantities: Quantities<ET, NT>,
+}
+
+/// Counts of edges grouped by (edge type, node type)
+///
+/// Types are only required to implement PartialEq, so the counts are stored in a list.
+/// The list only grows with the number of distinct type combinations and not the number of edges
+#[derive(Debug, Clone)]
+pub(crate) struct Quantities<ET, NT> {
+    counts: Vec<(ET, NT, usize)>,
+}
+
+impl<ET, NT> Default for Quantities<ET, NT> {
+    fn default() -> Self {
+        Quantities { counts: Vec::new() }
+    }
+}
+
+impl<ET: PartialEq, NT: PartialEq> Quantities<ET, NT> {
+    pub(crate) fn get(&self, edge_type: &ET, node_type: &NT) -> usize {
+        self.counts
+            .iter()
+            .find(|(et, nt, _)| et == edge_type && nt == node_type)
+            .map_or(0, |(_, _, count)| *count)
+    }
+
+    pub(crate) fn increment(&mut self, edge_type: ET, node_type: NT) {
+        let entry = self
+            .counts
+            .iter_mut()
+            .find(|(et, nt, _)| et == &edge_type && nt == &node_type);
+        match entry {
+            Some((_, _, count)) => *count += 1,
+            None => self.counts.push((edge_type, node_type, 1)),
+        }
+    }
+
+    pub(crate) fn decrement(&mut self, edge_type: &ET, node_type: &NT) {
+        let idx = self
+            .counts
+            .iter()
+            .position(|(et, nt, _)| et == edge_type && nt == node_type);
+        if let Some(idx) = idx {
+            self.counts[idx].2 -= 1;
+            if self.counts[idx].2 == 0 {
+                self.counts.swap_remove(idx);
+            }
+        }
+    }
+
+    /// Check if both hold the same counts regardless of the order they were added in
+    pub(crate) fn same_counts(&self, other: &Self) -> bool {
+        self.counts.len() == other.counts.len()
+            && self
+                .counts
+                .iter()
+                .all(|(et, nt, count)| other.get(et, nt) == *count)
+    }
 }
 
-impl<N> AsRef<N> for NodeMetadata<N> {
+impl<N, ET, NT> AsRef<N> for NodeMetadata<N, ET, NT> {
     fn as_ref(&self) -> &N {
         &self.weight
     }
 }
 
-impl<N> Deref for NodeMetadata<N> {
+impl<N, ET, NT> Deref for NodeMetadata<N, ET, NT> {
     type Target = N;
     fn deref(&self) -> &Self::Target {
         &self.weight
     }
 }
 
-impl<N> DerefMut for NodeMetadata<N> {
+impl<N, ET, NT> DerefMut for NodeMetadata<N, ET, NT> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.weight
     }
@@ -69,3 +143,177 @@ impl<E> DerefMut for EdgeMetadata<E> {
         &mut self.weight
     }
 }
+
+/// Look Up Table from a type to all the keys with that type
+///
+/// Types are only required to implement PartialEq, so the types are stored in a list.
+/// No order is guaranteed for the keys of a type
+#[derive(Debug, Clone)]
+pub(crate) struct TypeIndex<T, K> {
+    entries: Vec<(T, IndexSet<K>)>,
+    /// Keys whose weight has been borrowed mutably since the last repair, together with the type they are indexed under.
+    /// The type of a weight can be changed through a mutable borrow, so these keys may be indexed under the wrong type
+    changed: IndexMap<K, T>,
+}
+
+impl<T, K> Default for TypeIndex<T, K> {
+    fn default() -> Self {
+        TypeIndex {
+            entries: Vec::new(),
+            changed: IndexMap::new(),
+        }
+    }
+}
+
+impl<T: PartialEq, K: Hash + Eq> TypeIndex<T, K> {
+    pub(crate) fn get(&self, ty: &T) -> Option<&IndexSet<K>> {
+        self.entries
+            .iter()
+            .find(|(t, _)| t == ty)
+            .map(|(_, keys)| keys)
+    }
+
+    pub(crate) fn insert(&mut self, ty: T, key: K) {
+        match self.entries.iter_mut().find(|(t, _)| t == &ty) {
+            Some((_, keys)) => {
+                keys.insert(key);
+            }
+            None => self.entries.push((ty, IndexSet::from([key]))),
+        }
+    }
+
+    /// Remove the key given its current type
+    ///
+    /// If the key has been marked as changed, it is removed from the type it is indexed under instead
+    pub(crate) fn remove(&mut self, ty: &T, key: &K) {
+        let indexed_type = self.changed.swap_remove(key);
+        let ty = indexed_type.as_ref().unwrap_or(ty);
+        let idx = self.entries.iter().position(|(t, _)| t == ty);
+        if let Some(idx) = idx {
+            self.entries[idx].1.swap_remove(key);
+            if self.entries[idx].1.is_empty() {
+                self.entries.swap_remove(idx);
+            }
+        }
+    }
+
+    /// Iterate over the keys with the given type
+    ///
+    /// Marked keys are looked up using their current type, so a type changed through a mutable borrow is seen before the index is repaired
+    pub(crate) fn keys<'a>(
+        &'a self,
+        ty: T,
+        type_of: impl Fn(&K) -> T + Copy + 'a,
+    ) -> impl Iterator<Item = &'a K> + 'a
+    where
+        T: Clone + 'a,
+    {
+        let indexed = self
+            .get(&ty)
+            .into_iter()
+            .flatten()
+            .filter(|key| !self.changed.contains_key(*key));
+        let changed = self.changed.keys().filter(move |key| type_of(key) == ty);
+        indexed.chain(changed)
+    }
+
+    /// Count the keys with the given type in the same way as `keys`
+    pub(crate) fn count(&self, ty: &T, type_of: impl Fn(&K) -> T) -> usize {
+        let indexed = self.get(ty).map_or(0, |keys| keys.len());
+        let moved_out = self.changed.values().filter(|t| *t == ty).count();
+        let moved_in = self
+            .changed
+            .keys()
+            .filter(|key| &type_of(key) == ty)
+            .count();
+        indexed - moved_out + moved_in
+    }
+
+    /// List every type together with the number of keys of that type in the same way as `keys`
+    pub(crate) fn counts(&self, type_of: impl Fn(&K) -> T) -> Vec<(T, usize)>
+    where
+        T: Clone,
+    {
+        let mut counts: Vec<(T, usize)> = self
+            .entries
+            .iter()
+            .map(|(ty, keys)| (ty.clone(), keys.len()))
+            .collect();
+
+        for (key, indexed_type) in &self.changed {
+            let ty = type_of(key);
+            if &ty == indexed_type {
+                continue;
+            }
+
+            if let Some((_, count)) = counts.iter_mut().find(|(t, _)| t == indexed_type) {
+                *count -= 1;
+            }
+            match counts.iter_mut().find(|(t, _)| t == &ty) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((ty, 1)),
+            }
+        }
+
+        counts.retain(|(_, count)| *count != 0);
+        counts
+    }
+
+    /// Check if the key is indexed under the given type, which must be its current type
+    ///
+    /// Marked keys are always considered to be indexed under their current type
+    pub(crate) fn contains(&self, ty: &T, key: &K) -> bool {
+        self.changed.contains_key(key) || self.get(ty).is_some_and(|keys| keys.contains(key))
+    }
+
+    /// Total number of keys in the index
+    pub(crate) fn len(&self) -> usize {
+        self.entries.iter().map(|(_, keys)| keys.len()).sum()
+    }
+
+    /// Replace every key using the given mapping
+    pub(crate) fn map_keys(&mut self, f: impl Fn(&K) -> K) {
+        for (_, keys) in &mut self.entries {
+            *keys = keys.iter().map(&f).collect();
+        }
+        self.changed = self
+            .changed
+            .drain(..)
+            .map(|(key, ty)| (f(&key), ty))
+            .collect();
+    }
+
+    /// Mark that the weight of the key is about to be borrowed mutably
+    ///
+    /// The type must be the current type of the weight
+    pub(crate) fn mark_changed(&mut self, key: K, ty: T) {
+        // The first mark holds the type the key is indexed under
+        self.changed.entry(key).or_insert(ty);
+    }
+
+    /// Iterate over the marked keys whose type is no longer the type they are indexed under
+    pub(crate) fn retyped<'a>(
+        &'a self,
+        type_of: impl Fn(&K) -> T + 'a,
+    ) -> impl Iterator<Item = &'a K> {
+        self.changed
+            .iter()
+            .filter(move |(key, ty)| &type_of(key) != *ty)
+            .map(|(key, _)| key)
+    }
+
+    pub(crate) fn has_changes(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// Move every marked key to its current type and clear the marks
+    pub(crate) fn repair(&mut self, type_of: impl Fn(&K) -> T) {
+        for (key, indexed_type) in std::mem::take(&mut self.changed) {
+            let ty = type_of(&key);
+            if ty != indexed_type {
+                self.remove(&indexed_type, &key);
+                self.insert(ty, key);
+            }
+        }
+    }
+}
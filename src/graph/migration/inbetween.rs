@@ -21,6 +21,16 @@ impl<NK, EK, Old, New> InBetween<NK, EK, Old, New> {
             new,
         }
     }
+
+    /// The schema the graph is migrating from
+    pub fn old_schema(&self) -> &Old {
+        &self.old
+    }
+
+    /// The schema the graph is migrating to
+    pub fn new_schema(&self) -> &New {
+        &self.new
+    }
 }
 
 impl<NK, EK, OldVersion, NewVersion> SchemaExt<NK, EK> for InBetween<NK, EK, OldVersion, NewVersion>
@@ -78,6 +88,32 @@ where
             }
         }
     }
+
+    fn allow_self_loop(
+        &self,
+        edge_ty: <Self::E as Typed>::Type,
+        node_ty: <Self::N as Typed>::Type,
+    ) -> bool {
+        match (edge_ty, node_ty) {
+            (EitherVersion::Old(edge_ty), EitherVersion::Old(node_ty)) => {
+                self.old.allow_self_loop(edge_ty, node_ty)
+            }
+            (EitherVersion::New(edge_ty), EitherVersion::New(node_ty)) => {
+                self.new.allow_self_loop(edge_ty, node_ty)
+            }
+            (edge_ty, node_ty) => {
+                let updated_content = (
+                    self.update_edge_type(&self.new, edge_ty),
+                    self.update_node_type(&self.new, node_ty),
+                );
+                if let (Some(edge_ty), Some(node_ty)) = updated_content {
+                    self.new.allow_self_loop(edge_ty, node_ty)
+                } else {
+                    false
+                }
+            }
+        }
+    }
 }
 
 impl<NK, EK, OldVersion, NewVersion> MigrateSchema<NK, EK, NewVersion>
@@ -13,6 +13,9 @@ where
     NewVersion: SchemaExt<NK, EK>,
     OldVersion: MigrateSchema<NK, EK, NewVersion>,
 {
+    /// Make changes to the data before it is converted into the new schema
+    ///
+    /// The old and new schema can be accessed through `g.get_schema().old_schema()` and `g.get_schema().new_schema()`
     fn update_data(
         &self,
         g: &mut MigrationGraph<NK, EK, OldVersion, NewVersion>,
@@ -69,24 +72,31 @@ where
         handler: &Self::Handler,
         new_schema: NewVersion,
     ) -> GenericTypedResult<TypedGraph<NK, EK, NewVersion>, NK, EK> {
+        Self::migrate_with_report(g, handler, new_schema).map(|(new_g, _)| new_g)
+    }
+
+    /// migrate the data and report every node and edge that was dropped because it did not fit in the new schema
+    fn migrate_with_report(
+        g: TypedGraph<NK, EK, Self>,
+        handler: &Self::Handler,
+        new_schema: NewVersion,
+    ) -> GenericTypedResult<ReportedMigration<NK, EK, NewVersion>, NK, EK> {
         // Setup migration enviroment
         let old_schema = g.get_schema().clone();
         let old_name = old_schema.name();
         let new_name = new_schema.name();
 
+        let node_type_name = |nt: EitherVersion<_, _>| match nt {
+            EitherVersion::Old(nt) => format!("{}::{}", old_name, nt),
+            EitherVersion::New(nt) => format!("{}::{}", new_name, nt),
+        };
+        let edge_type_name = |et: EitherVersion<_, _>| match et {
+            EitherVersion::Old(et) => format!("{}::{}", old_name, et),
+            EitherVersion::New(et) => format!("{}::{}", new_name, et),
+        };
+
         let to_generic_error = |e: SchemaError<NK, EK, InBetween<NK, EK, Self, NewVersion>>| {
-            e.map(
-                identity,
-                identity,
-                |nt| match nt {
-                    EitherVersion::Old(nt) => format!("{}::{}", old_name, nt),
-                    EitherVersion::New(nt) => format!("{}::{}", new_name, nt),
-                },
-                |et| match et {
-                    EitherVersion::Old(et) => format!("{}::{}", old_name, et),
-                    EitherVersion::New(et) => format!("{}::{}", new_name, et),
-                },
-            )
+            e.map(identity, identity, node_type_name, edge_type_name)
         };
 
         let mut migration_g: MigrationGraph<NK, EK, Self, NewVersion> = g
@@ -102,8 +112,8 @@ where
             .map_err(to_generic_error)?;
 
         // Finalize migration
-        let new_g = migration_g
-            .update_schema(
+        let (new_g, report) = migration_g
+            .update_schema_with_report(
                 new_schema,
                 |current_schema, new_schema, node| current_schema.update_node(&new_schema, node),
                 |current_schema, new_schema, edge| current_schema.update_edge(&new_schema, edge),
@@ -114,10 +124,15 @@ where
             // And then we can use the same formatter as for the other results
             .map_err(to_generic_error)?;
 
-        Ok(new_g)
+        let report = report.map(identity, identity, node_type_name, edge_type_name);
+
+        Ok((new_g, report))
     }
 }
 
+/// Migrate directly from one schema to another without going through an InBetween schema
+///
+/// Schemas using the same node and edge weights can implement this by calling TypedGraph::update_schema_rules
 pub trait DirectMigration<NK, EK, NewVersion>: SchemaExt<NK, EK> + Sized
 where
     NK: Key,
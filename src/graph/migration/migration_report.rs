@@ -0,0 +1,82 @@
+use crate::{SchemaExt, Typed, TypedGraph};
+use std::collections::hash_map::RandomState;
+
+/// A record of the nodes and edges that were dropped while changing the schema of a graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport<NK, EK, NT, ET> {
+    /// Nodes with no equivalent in the new schema
+    pub dropped_nodes: Vec<(NK, NT)>,
+    /// Edges with no equivalent in the new schema or where the source or target was dropped
+    pub dropped_edges: Vec<(EK, ET)>,
+    /// Edges removed because the source or target had too many edges of that type in the new schema
+    pub excess_edges: Vec<(EK, ET)>,
+}
+
+/// A migration report where the types have been formatted as strings
+pub type GenericMigrationReport<NK, EK> = MigrationReport<NK, EK, String, String>;
+
+/// A migration report using the types of the schema S
+pub type SchemaMigrationReport<NK, EK, S> = MigrationReport<
+    NK,
+    EK,
+    <<S as SchemaExt<NK, EK>>::N as Typed>::Type,
+    <<S as SchemaExt<NK, EK>>::E as Typed>::Type,
+>;
+
+/// A migrated graph together with the report of what was dropped during the migration
+pub type ReportedMigration<NK, EK, NS> = (TypedGraph<NK, EK, NS>, GenericMigrationReport<NK, EK>);
+
+/// A graph converted from the schema S to NS together with the report of what was dropped during the conversion
+pub type ReportedSchemaUpdate<NK, EK, S, NS, H = RandomState> =
+    (TypedGraph<NK, EK, NS, H>, SchemaMigrationReport<NK, EK, S>);
+
+impl<NK, EK, NT, ET> MigrationReport<NK, EK, NT, ET> {
+    /// Check if nothing was dropped
+    pub fn is_empty(&self) -> bool {
+        self.dropped_nodes.is_empty()
+            && self.dropped_edges.is_empty()
+            && self.excess_edges.is_empty()
+    }
+
+    pub fn map<NK1, EK1, NT1, ET1, NKF, EKF, NTF, ETF>(
+        self,
+        nk_map: NKF,
+        ek_map: EKF,
+        nt_map: NTF,
+        et_map: ETF,
+    ) -> MigrationReport<NK1, EK1, NT1, ET1>
+    where
+        NKF: Fn(NK) -> NK1,
+        EKF: Fn(EK) -> EK1,
+        NTF: Fn(NT) -> NT1,
+        ETF: Fn(ET) -> ET1,
+    {
+        MigrationReport {
+            dropped_nodes: self
+                .dropped_nodes
+                .into_iter()
+                .map(|(id, ty)| (nk_map(id), nt_map(ty)))
+                .collect(),
+            dropped_edges: self
+                .dropped_edges
+                .into_iter()
+                .map(|(id, ty)| (ek_map(id), et_map(ty)))
+                .collect(),
+            excess_edges: self
+                .excess_edges
+                .into_iter()
+                .map(|(id, ty)| (ek_map(id), et_map(ty)))
+                .collect(),
+        }
+    }
+}
+
+impl<NK, EK, NT, ET> Default for MigrationReport<NK, EK, NT, ET> {
+    fn default() -> Self {
+        MigrationReport {
+            dropped_nodes: Vec::new(),
+            dropped_edges: Vec::new(),
+            excess_edges: Vec::new(),
+        }
+    }
+}
@@ -2,8 +2,10 @@ mod either;
 mod inbetween;
 mod migrate;
 mod migration_handler;
+mod migration_report;
 
 pub use either::*;
 pub use inbetween::*;
 pub use migrate::*;
 pub use migration_handler::*;
+pub use migration_report::*;
@@ -1,13 +1,31 @@
+mod components;
+mod diff;
+mod edge_classification;
 mod edge_ref;
+mod entry;
 mod graph_traits;
 mod graph_walker;
+#[cfg(feature = "graphml")]
+mod graphml;
 mod metadata;
 mod migration;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "petgraph")]
+mod petgraph;
+mod shortest_path;
+mod spanning_tree;
 mod typed_graph;
 
+pub use components::*;
+pub use diff::*;
+pub use edge_classification::*;
 pub use edge_ref::*;
+pub use entry::*;
 pub use graph_traits::*;
 pub use graph_walker::*;
+#[cfg(feature = "graphml")]
+pub use graphml::*;
 pub(crate) use metadata::*;
 pub use migration::*;
 pub use typed_graph::*;
@@ -0,0 +1,62 @@
+use crate::{EdgeView, Key, SchemaExt, TypedGraph};
+use rayon::prelude::*;
+use std::hash::BuildHasher;
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key + Send + Sync,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    S::N: Sync,
+    S::E: Sync,
+    H: BuildHasher,
+{
+    /// Iterate over all nodes in parallel
+    ///
+    /// The nodes are collected before being handed to rayon, since `HopSlotMap` can not be split
+    pub fn par_nodes(&self) -> impl ParallelIterator<Item = &S::N> {
+        self.nodes().collect::<Vec<_>>().into_par_iter()
+    }
+
+    /// Iterate over all edges along with their endpoints in parallel
+    ///
+    /// The edges are collected before being handed to rayon, since `HopSlotMap` can not be split
+    pub fn par_edges_full(&self) -> impl ParallelIterator<Item = EdgeView<'_, NK, EK, S>> {
+        self.edges_full().collect::<Vec<_>>().into_par_iter()
+    }
+}
+
+#[test]
+fn parallel_sum_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use crate::{Id, Typed};
+    use fake::Dummy;
+
+    let config = CompleteGraph {
+        nodes: 50,
+        node_types: 5,
+        edge_types: 5,
+    };
+    let g = TestGraph::dummy(&config);
+
+    let score = |n: &TestNode| {
+        let id: usize = n.get_id();
+        n.get_type() * 100 + id
+    };
+
+    let serial: usize = g.nodes().map(score).sum();
+    let parallel: usize = g.par_nodes().map(score).sum();
+    assert_eq!(serial, parallel);
+
+    let serial: usize = g
+        .edges_full()
+        .map(|e| e.get_source() * e.get_target() + e.get_type())
+        .sum();
+    let parallel: usize = g
+        .par_edges_full()
+        .map(|e| e.get_source() * e.get_target() + e.get_type())
+        .sum();
+    assert_eq!(serial, parallel);
+
+    Ok(())
+}
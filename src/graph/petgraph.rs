@@ -0,0 +1,89 @@
+use crate::{Id, Key, SchemaExt, SchemaResult, TypedError, TypedGraph};
+use petgraph::graph::DiGraph;
+use petgraph::visit::EdgeRef;
+
+impl<NK, EK, S> TypedGraph<NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    /// Import a petgraph graph by adding every node and edge to a new graph with the given schema
+    ///
+    /// Nodes are added in the order of their indices and the outgoing edges of each node keep the order given by petgraph.
+    /// The ids are taken from the weights and must be unique
+    pub fn from_petgraph(graph: DiGraph<S::N, S::E>, schema: S) -> SchemaResult<Self, NK, EK, S> {
+        // petgraph does not keep the edges of a node in insertion order so the order is recorded before the graph is taken apart
+        let edge_order: Vec<_> = graph
+            .node_indices()
+            .flat_map(|node_idx| graph.edges(node_idx).map(|edge| edge.id().index()))
+            .collect();
+
+        let (nodes, edges) = graph.into_nodes_edges();
+        let mut g = TypedGraph::with_capacity(schema, nodes.len(), edges.len());
+
+        let mut node_ids = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let node_id = node.weight.get_id();
+            if g.has_node(node_id.clone()) {
+                return Err(TypedError::NodeIdCollision(node_id));
+            }
+            node_ids.push(g.add_node(node.weight)?);
+        }
+
+        let mut edges: Vec<_> = edges.into_iter().map(Some).collect();
+        for edge_idx in edge_order {
+            let Some(edge) = edges[edge_idx].take() else {
+                continue;
+            };
+
+            let edge_id = edge.weight.get_id();
+            if g.has_edge(edge_id.clone()) {
+                return Err(TypedError::EdgeIdCollision(edge_id));
+            }
+            let source = node_ids[edge.source().index()].clone();
+            let target = node_ids[edge.target().index()].clone();
+            g.add_edge(source, target, edge.weight)?;
+        }
+
+        Ok(g)
+    }
+}
+
+#[test]
+fn from_petgraph_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut pg = DiGraph::new();
+    let a = pg.add_node(TestNode::from((3, 0)));
+    let b = pg.add_node(TestNode::from((1, 1)));
+    let c = pg.add_node(TestNode::from((2, 0)));
+    pg.add_edge(a, b, TestEdge::from((10, 0)));
+    pg.add_edge(a, c, TestEdge::from((11, 1)));
+    pg.add_edge(c, c, TestEdge::from((12, 2)));
+
+    let g = TestGraph::from_petgraph(pg.clone(), TestSchema::new())?;
+    assert_eq!(g.node_ids().collect::<Vec<_>>(), vec![3, 1, 2]);
+    assert_eq!(g.edge_endpoints(11)?, (3, 2));
+
+    let expected: Vec<usize> = pg.edges(a).map(|edge| edge.weight().get_id()).collect();
+    assert_eq!(g.outgoing_edge_order(3)?, expected);
+
+    // Ids must be unique
+    let mut duplicate_nodes = pg.clone();
+    duplicate_nodes.add_node(TestNode::from((1, 0)));
+    let e = TestGraph::from_petgraph(duplicate_nodes, TestSchema::new());
+    assert!(matches!(e, Err(TypedError::NodeIdCollision(1))));
+
+    let mut duplicate_edges = pg.clone();
+    duplicate_edges.add_edge(b, c, TestEdge::from((10, 0)));
+    let e = TestGraph::from_petgraph(duplicate_edges, TestSchema::new());
+    assert!(matches!(e, Err(TypedError::EdgeIdCollision(10))));
+
+    // The schema is checked for every node and edge
+    let schema = TestSchema::new().node_whitelist(Some(vec![0]));
+    let e = TestGraph::from_petgraph(pg, schema);
+    assert!(matches!(e, Err(TypedError::InvalidNodeType(1, ..))));
+
+    Ok(())
+}
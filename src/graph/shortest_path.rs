@@ -0,0 +1,272 @@
+use crate::{EdgeRef, Key, SchemaExt, SchemaResult, TypedError, TypedGraph};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::BuildHasher;
+use std::ops::Add;
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Find the cheapest path from source to target using Dijkstra's algorithm
+    ///
+    /// The cost of every edge is given by the cost function and must not be negative.
+    /// Returns the total cost together with the ids of the edges along the path, or None if the target can not be reached.
+    /// Since the edges are returned, parallel edges between the same nodes can be told apart
+    pub fn shortest_path_edges<F, C>(
+        &self,
+        source: NK,
+        target: NK,
+        cost: F,
+    ) -> SchemaResult<Option<(C, Vec<EK>)>, NK, EK, S>
+    where
+        F: Fn(&EdgeRef<'_, NK, EK, S>) -> C,
+        C: Ord + Copy + Default + Add<Output = C>,
+    {
+        self.get_node(source.clone())?;
+        self.get_node(target.clone())?;
+
+        let mut distances = HashMap::from([(source.clone(), C::default())]);
+        // The edge used to reach each node together with the node it came from
+        let mut previous: HashMap<NK, (NK, EK)> = HashMap::new();
+        let mut front = BinaryHeap::from([FrontEntry {
+            distance: C::default(),
+            node: source,
+        }]);
+
+        while let Some(FrontEntry { distance, node }) = front.pop() {
+            if node == target {
+                break;
+            }
+
+            // The node has already been reached by a cheaper path
+            if distances.get(&node).is_some_and(|d| *d < distance) {
+                continue;
+            }
+
+            for edge in self.get_outgoing(node.clone())? {
+                let next = edge.get_outer();
+                let next_distance = distance + cost(&edge);
+                if distances.get(&next).is_some_and(|d| *d <= next_distance) {
+                    continue;
+                }
+
+                distances.insert(next.clone(), next_distance);
+                previous.insert(next.clone(), (node.clone(), edge.get_id()));
+                front.push(FrontEntry {
+                    distance: next_distance,
+                    node: next,
+                });
+            }
+        }
+
+        let Some(total) = distances.get(&target).copied() else {
+            return Ok(None);
+        };
+
+        let mut path = Vec::new();
+        let mut current = target;
+        while let Some((node, edge_id)) = previous.get(&current) {
+            path.push(edge_id.clone());
+            current = node.clone();
+        }
+        path.reverse();
+
+        Ok(Some((total, path)))
+    }
+
+    /// Find the cost of the cheapest path from the source to every node it can reach using the Bellman-Ford algorithm
+    ///
+    /// Unlike shortest_path_edges the cost of an edge may be negative, at the price of taking O(V * E) time.
+    /// If a cycle with a negative total cost can be reached from the source the cheapest paths are not defined,
+    /// so NegativeCycle is returned with one of the nodes on the cycle
+    pub fn shortest_path_bellman_ford<F, C>(
+        &self,
+        source: NK,
+        cost: F,
+    ) -> SchemaResult<HashMap<NK, C>, NK, EK, S>
+    where
+        F: Fn(&EdgeRef<'_, NK, EK, S>) -> C,
+        C: Ord + Copy + Default + Add<Output = C>,
+    {
+        self.get_node(source.clone())?;
+
+        // The edges are relaxed many times so the costs are only computed once
+        let mut edges = Vec::with_capacity(self.edge_count());
+        for node in self.node_ids() {
+            for edge in self.get_outgoing(node.clone())? {
+                edges.push((node.clone(), edge.get_outer(), cost(&edge)));
+            }
+        }
+
+        let mut distances = HashMap::from([(source, C::default())]);
+        // The node each node was last reached from
+        let mut previous: HashMap<NK, NK> = HashMap::new();
+
+        // Every path without cycles uses at most V - 1 edges
+        for _ in 1..self.node_count() {
+            let mut changed = false;
+            for (from, to, edge_cost) in &edges {
+                let Some(distance) = distances.get(from).copied() else {
+                    continue;
+                };
+                let next_distance = distance + *edge_cost;
+                if distances.get(to).is_some_and(|d| *d <= next_distance) {
+                    continue;
+                }
+
+                distances.insert(to.clone(), next_distance);
+                previous.insert(to.clone(), from.clone());
+                changed = true;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        // If an edge can still make a path cheaper, the path must contain a negative cycle
+        for (from, to, edge_cost) in &edges {
+            let Some(distance) = distances.get(from).copied() else {
+                continue;
+            };
+            if distances
+                .get(to)
+                .is_some_and(|d| *d <= distance + *edge_cost)
+            {
+                continue;
+            }
+
+            // Following the path backwards V times is guaranteed to end up on the cycle
+            previous.insert(to.clone(), from.clone());
+            let mut node = to.clone();
+            for _ in 0..self.node_count() {
+                match previous.get(&node) {
+                    Some(prev) => node = prev.clone(),
+                    None => break,
+                }
+            }
+            return Err(TypedError::NegativeCycle(node));
+        }
+
+        Ok(distances)
+    }
+}
+
+/// A node waiting to be visited by Dijkstra's algorithm
+///
+/// The entries are ordered so the cheapest node is at the top of the heap
+struct FrontEntry<C, NK> {
+    distance: C,
+    node: NK,
+}
+
+impl<C: Ord, NK> PartialEq for FrontEntry<C, NK> {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl<C: Ord, NK> Eq for FrontEntry<C, NK> {}
+
+impl<C: Ord, NK> PartialOrd for FrontEntry<C, NK> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: Ord, NK> Ord for FrontEntry<C, NK> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.cmp(&self.distance)
+    }
+}
+
+#[test]
+fn shortest_path_edges_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..4 {
+        g.add_node((i, 0))?;
+    }
+
+    // The type of each edge is used as its cost
+    // There are two parallel edges from 0 to 1 with different costs
+    g.add_edges(
+        [
+            (0, 1, (0, 5)),
+            (0, 1, (1, 2)),
+            (1, 2, (2, 1)),
+            (0, 2, (3, 10)),
+            (2, 3, (4, 0)),
+        ],
+        true,
+    )?;
+    let cost = |e: &EdgeRef<'_, usize, usize, TestSchema>| e.get_type();
+
+    assert_eq!(g.shortest_path_edges(0, 2, cost)?, Some((3, vec![1, 2])));
+    assert_eq!(g.shortest_path_edges(0, 3, cost)?, Some((3, vec![1, 2, 4])));
+    assert_eq!(g.shortest_path_edges(1, 1, cost)?, Some((0, vec![])));
+
+    // Edges are only followed in their direction
+    assert_eq!(g.shortest_path_edges(2, 0, cost)?, None);
+    assert!(g.shortest_path_edges(0, 9, cost).is_err());
+
+    // Undirected edges can be followed both ways
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges([(0, 1, (0, 1)), (2, 1, (1, 1))], true)?;
+    let path = g.shortest_path_edges(0, 2, |e| e.get_type())?;
+    assert_eq!(path, Some((2, vec![0, 1])));
+
+    Ok(())
+}
+
+#[test]
+fn shortest_path_bellman_ford_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..5 {
+        g.add_node((i, 0))?;
+    }
+
+    // The type of each edge is used as its cost, with types above 100 being negative
+    let cost = |e: &EdgeRef<'_, usize, usize, TestSchema>| {
+        let ty = e.get_type() as i64;
+        if ty > 100 {
+            100 - ty
+        } else {
+            ty
+        }
+    };
+    g.add_edges(
+        [
+            (0, 1, (0, 4)),
+            (0, 2, (1, 5)),
+            (2, 1, (2, 103)),
+            (1, 3, (3, 2)),
+            (3, 2, (4, 6)),
+        ],
+        true,
+    )?;
+
+    // The discount on the edge from 2 to 1 makes the longer path cheaper
+    let distances = g.shortest_path_bellman_ford(0, cost)?;
+    assert_eq!(distances, HashMap::from([(0, 0), (1, 2), (2, 5), (3, 4)]));
+    assert!(g.shortest_path_bellman_ford(9, cost).is_err());
+
+    // The cycle 1 -> 3 -> 2 -> 1 now costs -1
+    g.add_edge(3, 2, (4, 0))?;
+    let e = g.shortest_path_bellman_ford(0, cost).unwrap_err();
+    assert!(matches!(e, TypedError::NegativeCycle(1..=3)));
+
+    // The cycle can not be reached from 4
+    let distances = g.shortest_path_bellman_ford(4, cost)?;
+    assert_eq!(distances, HashMap::from([(4, 0)]));
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use super::components::UnionFind;
+use crate::{Direction, EdgeRef, Key, SchemaExt, SchemaResult, TypedGraph};
+use std::hash::BuildHasher;
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Find a minimum spanning tree for every connected component of the graph using Kruskal's algorithm
+    ///
+    /// The direction of the edges is ignored and the cost of every edge is given by the cost function.
+    /// Of parallel edges between the same nodes only the cheapest can be part of the forest.
+    /// Returns the ids of the edges in the forest ordered by their cost
+    pub fn minimum_spanning_forest<F, C>(&self, cost: F) -> SchemaResult<Vec<EK>, NK, EK, S>
+    where
+        F: Fn(&EdgeRef<'_, NK, EK, S>) -> C,
+        C: Ord,
+    {
+        let mut components = UnionFind::new(self.node_ids());
+
+        let mut edges = Vec::with_capacity(self.edge_count());
+        for edge in self.try_edges_full() {
+            let edge = edge?;
+            let edge = EdgeRef {
+                weight: edge.get_weight(),
+                source: edge.get_source_node(),
+                target: edge.get_target_node(),
+                direction: Direction::Outgoing,
+            };
+            edges.push((cost(&edge), edge));
+        }
+        edges.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut forest = Vec::new();
+        for (_, edge) in edges {
+            if components.union(&edge.get_source(), &edge.get_target()) {
+                forest.push(edge.get_id());
+            }
+        }
+
+        Ok(forest)
+    }
+}
+
+#[test]
+fn minimum_spanning_forest_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..6 {
+        g.add_node((i, 0))?;
+    }
+
+    // The type of each edge is used as its cost
+    // Nodes 0 to 3 form one component and 4 and 5 another
+    g.add_edges(
+        [
+            (0, 1, (0, 4)),
+            (1, 0, (1, 1)),
+            (1, 2, (2, 2)),
+            (2, 0, (3, 3)),
+            (3, 2, (4, 5)),
+            (3, 3, (5, 0)),
+            (4, 5, (6, 7)),
+        ],
+        true,
+    )?;
+
+    // Only the cheapest of the parallel edges between 0 and 1 is used
+    let forest = g.minimum_spanning_forest(|e| e.get_type())?;
+    assert_eq!(forest, vec![1, 2, 4, 6]);
+
+    let empty = TestGraph::default();
+    assert!(empty.minimum_spanning_forest(|e| e.get_type())?.is_empty());
+
+    Ok(())
+}
@@ -1,14 +1,21 @@
 use super::*;
-use crate::{GenericTypedResult, SchemaResult, TypedError};
+use crate::{GenericTypedResult, SchemaError, SchemaResult, TypedError};
+use indexmap::IndexSet;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use slotmap::{new_key_type, HopSlotMap};
-use std::collections::HashMap;
-use std::fmt::{self, Debug, Display};
-use std::hash::Hash;
+use std::cmp::Ordering;
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+#[cfg(feature = "serde")]
+use std::fmt::{self, Display};
+use std::hash::{BuildHasher, Hash};
+#[cfg(feature = "serde")]
 use std::marker::PhantomData;
 use std::ops::Deref;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Direction {
     Outgoing,
     Incoming,
@@ -25,28 +32,165 @@ new_key_type! {
     pub struct EdgeKey;
 }
 
+/// A handle to a node which can be used instead of its id to skip the id lookup
+///
+/// A handle is only meaningful for the graph it was created from.
+/// Using it after the node has been removed fails, but using it with another graph may give an unrelated node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeHandle(NodeKey);
+
+/// The memory reserved by a graph, as returned by TypedGraph::capacity
+///
+/// The capacities are the number of entries which fit before reallocating, not the number of entries in use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraphCapacity {
+    pub nodes: usize,
+    pub edges: usize,
+    pub node_lut: usize,
+    pub edge_lut: usize,
+    /// The total number of entries in the outgoing edges of every node
+    pub outgoing_edges: usize,
+    /// The total number of entries in the incoming edges of every node
+    pub incoming_edges: usize,
+}
+
+/// Tells whether a node or edge was created or an existing one was updated, as returned by TypedGraph::upsert_node and TypedGraph::upsert_edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Upsert<K> {
+    /// There was no node or edge with the id
+    Inserted(K),
+    /// The existing node or edge with the id was replaced
+    Updated(K),
+}
+
+impl<K> Upsert<K> {
+    /// Get the id whether it was inserted or updated
+    pub fn into_id(self) -> K {
+        match self {
+            Upsert::Inserted(id) | Upsert::Updated(id) => id,
+        }
+    }
+
+    pub fn is_inserted(&self) -> bool {
+        matches!(self, Upsert::Inserted(_))
+    }
+}
+
+/// Move the source edge to the position of the target edge and shift all the edges in between
+///
+/// Returns None if either edge is not in the set
+fn move_edge_in_order(
+    edges: &mut IndexSet<EdgeKey>,
+    source_key: EdgeKey,
+    target_key: EdgeKey,
+    insert_position: InsertPosition,
+) -> Option<()> {
+    let source_idx = edges.get_index_of(&source_key)?;
+
+    // Then we find out where in the order we want the node to be places
+    let mut target_idx = edges.get_index_of(&target_key)?;
+
+    // Figure out where to place the source relative to the target
+    // The position has to be adjusted as to not go out of bounds and
+    // play nicely with the behaviour of move_index
+    match insert_position {
+        InsertPosition::After => {
+            if target_idx + 1 != edges.len() && source_idx > target_idx {
+                target_idx += 1;
+            }
+        }
+        InsertPosition::Before => {
+            if target_idx != 0 && source_idx < target_idx {
+                target_idx -= 1;
+            }
+        }
+    }
+
+    // We then place the edge at the index and shift all the other edges to the right
+    edges.move_index(source_idx, target_idx);
+
+    Some(())
+}
+
+/// Keep the edge if the filter accepts it and pass on any error from resolving the edge or the filter
+fn try_filter_edge<'a, NK, EK, S, F>(
+    edge: TryEdgeRef<'a, NK, EK, S>,
+    filter: &F,
+) -> Option<TryEdgeRef<'a, NK, EK, S>>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    F: Fn(&S::E) -> SchemaResult<bool, NK, EK, S>,
+{
+    let edge = match edge {
+        Ok(edge) => edge,
+        Err(e) => return Some(Err(e)),
+    };
+
+    match filter(edge.weight) {
+        Ok(true) => Some(Ok(edge)),
+        Ok(false) => None,
+        Err(e) => Some(Err(e)),
+    }
+}
+
+/// The state of an edge before it was changed
+struct EdgeSnapshot<NK, E> {
+    source: NK,
+    target: NK,
+    weight: E,
+    /// Position of the edge in the outgoing edges of the source
+    idx: usize,
+}
+
+/// The number of nodes and edges of each type in a graph
+pub type TypeHistogram<NK, EK, S> = (
+    Vec<(<<S as SchemaExt<NK, EK>>::N as Typed>::Type, usize)>,
+    Vec<(<<S as SchemaExt<NK, EK>>::E as Typed>::Type, usize)>,
+);
+
+/// An edge yielded by a filter which can fail
+pub type TryEdgeRef<'a, NK, EK, S> = SchemaResult<EdgeRef<'a, NK, EK, S>, NK, EK, S>;
+
+/// The metadata stored for every node in a graph using the schema S
+type SchemaNodeMetadata<NK, EK, S> = NodeMetadata<
+    <S as SchemaExt<NK, EK>>::N,
+    <<S as SchemaExt<NK, EK>>::E as Typed>::Type,
+    <<S as SchemaExt<NK, EK>>::N as Typed>::Type,
+>;
+
+/// A graph where every node and edge is checked against the schema S
+///
+/// The ids of the nodes and edges are looked up using hash maps built with the hasher H.
+/// Use with_hasher to pick a faster hasher when the ids do not come from untrusted input
 #[derive(Debug, Clone)]
-pub struct TypedGraph<NK, EK, S: SchemaExt<NK, EK>>
+pub struct TypedGraph<NK, EK, S: SchemaExt<NK, EK>, H = RandomState>
 where
     NK: Key,
     EK: Key,
 {
     /// Mapping from node ids to node keys
-    node_lut: HashMap<NK, NodeKey>,
+    node_lut: HashMap<NK, NodeKey, H>,
     /// Mapping from edge ids to edge keys
-    edge_lut: HashMap<EK, EdgeKey>,
+    edge_lut: HashMap<EK, EdgeKey, H>,
     /// Contains the node weights and adjecency list
     ///
     /// Since the nodes stores its own id this can be used to convert node keys to node ids
-    nodes: HopSlotMap<NodeKey, NodeMetadata<S::N>>,
+    nodes: HopSlotMap<NodeKey, SchemaNodeMetadata<NK, EK, S>>,
     /// Contains the edge weights, and edge endpoints
     ///
     /// Since the edges stores its own id this can be used to convert edge keys to edge ids
     edges: HopSlotMap<EdgeKey, EdgeMetadata<S::E>>,
+    /// Mapping from node types to the nodes of that type
+    node_types: TypeIndex<<S::N as Typed>::Type, NodeKey>,
+    /// Mapping from edge types to the edges of that type
+    edge_types: TypeIndex<<S::E as Typed>::Type, EdgeKey>,
 
     schema: S,
 }
 
+// Constructors create the graph with the default hasher, so the type of the graph can be inferred
 impl<NK, EK, S> TypedGraph<NK, EK, S>
 where
     NK: Key,
@@ -54,12 +198,169 @@ where
     S: SchemaExt<NK, EK>,
 {
     pub fn new(schema: S) -> Self {
+        TypedGraph::with_hasher(schema, RandomState::new())
+    }
+
+    /// Create an empty graph with room for at least the given number of nodes and edges
+    pub fn with_capacity(schema: S, nodes: usize, edges: usize) -> Self {
+        TypedGraph::with_capacity_and_hasher(schema, nodes, edges, RandomState::new())
+    }
+
+    /// Create a graph from a list of edges together with the weights of their source and target
+    ///
+    /// The nodes are added in the order they appear before any of the edges are added.
+    /// A node appearing more than once is updated the same way as when calling add_node
+    pub fn from_edges<N, E, I>(schema: S, edges: I) -> SchemaResult<Self, NK, EK, S>
+    where
+        N: Into<S::N>,
+        E: Into<S::E>,
+        I: IntoIterator<Item = (N, N, E)>,
+    {
+        let mut graph = TypedGraph::new(schema);
+
+        let mut endpoints = Vec::new();
+        for (source, target, edge) in edges {
+            let source_id = graph.add_node(source)?;
+            let target_id = graph.add_node(target)?;
+            endpoints.push((source_id, target_id, edge));
+        }
+
+        graph.add_edges(endpoints, false)?;
+        Ok(graph)
+    }
+}
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher + Clone,
+{
+    /// Create an empty graph using the given hasher for the lookup of node and edge ids
+    pub fn with_hasher(schema: S, hasher: H) -> Self {
+        Self::with_capacity_and_hasher(schema, 0, 0, hasher)
+    }
+
+    /// Create an empty graph with room for at least the given number of nodes and edges using the given hasher
+    pub fn with_capacity_and_hasher(schema: S, nodes: usize, edges: usize, hasher: H) -> Self {
         TypedGraph {
-            node_lut: Default::default(),
-            edge_lut: Default::default(),
-            nodes: HopSlotMap::with_key(),
-            edges: HopSlotMap::with_key(),
-            schema: schema,
+            node_lut: HashMap::with_capacity_and_hasher(nodes, hasher.clone()),
+            edge_lut: HashMap::with_capacity_and_hasher(edges, hasher),
+            nodes: HopSlotMap::with_capacity_and_key(nodes),
+            edges: HopSlotMap::with_capacity_and_key(edges),
+            node_types: Default::default(),
+            edge_types: Default::default(),
+            schema,
+        }
+    }
+}
+
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Create an empty graph using a new instance of the hasher
+    fn with_default_hasher(schema: S, nodes: usize, edges: usize) -> Self
+    where
+        H: Default,
+    {
+        TypedGraph {
+            node_lut: HashMap::with_capacity_and_hasher(nodes, H::default()),
+            edge_lut: HashMap::with_capacity_and_hasher(edges, H::default()),
+            nodes: HopSlotMap::with_capacity_and_key(nodes),
+            edges: HopSlotMap::with_capacity_and_key(edges),
+            node_types: Default::default(),
+            edge_types: Default::default(),
+            schema,
+        }
+    }
+
+    /// Reserve room for at least the given number of additional nodes and edges
+    pub fn reserve(&mut self, nodes: usize, edges: usize) {
+        self.node_lut.reserve(nodes);
+        self.edge_lut.reserve(edges);
+        self.nodes.reserve(nodes);
+        self.edges.reserve(edges);
+    }
+
+    /// Release as much unused capacity as possible
+    ///
+    /// `HopSlotMap` can not shrink in place, so the nodes and edges are moved into new maps sized to fit.
+    /// The relative order of the nodes, edges and outgoing edges is kept the same
+    pub fn shrink_to_fit(&mut self) {
+        let old_nodes: Vec<_> = self.nodes.drain().collect();
+        let old_edges: Vec<_> = self.edges.drain().collect();
+
+        let mut nodes = HopSlotMap::with_capacity_and_key(old_nodes.len());
+        let mut edges = HopSlotMap::with_capacity_and_key(old_edges.len());
+        let mut node_keys = HashMap::with_capacity(old_nodes.len());
+        let mut edge_keys = HashMap::with_capacity(old_edges.len());
+
+        // Edges are inserted without any references to other edges, since their new keys are not known yet
+        for (old_key, edge) in old_edges {
+            let mirror = edge.mirror;
+            let new_key = edges.insert(EdgeMetadata {
+                mirror: None,
+                ..edge
+            });
+            edge_keys.insert(old_key, (new_key, mirror));
+        }
+
+        for (old_key, node) in old_nodes {
+            let NodeMetadata {
+                weight,
+                incoming_edges,
+                outgoing_edges,
+                outgoing_quantities,
+                incoming_quantities,
+            } = node;
+            let new_key = nodes.insert(NodeMetadata {
+                weight,
+                incoming_edges: incoming_edges.iter().map(|e| edge_keys[e].0).collect(),
+                outgoing_edges: outgoing_edges.iter().map(|e| edge_keys[e].0).collect(),
+                outgoing_quantities,
+                incoming_quantities,
+            });
+            node_keys.insert(old_key, new_key);
+        }
+
+        for (new_key, mirror) in edge_keys.values() {
+            let edge = &mut edges[*new_key];
+            edge.source = node_keys[&edge.source];
+            edge.target = node_keys[&edge.target];
+            edge.mirror = mirror.map(|m| edge_keys[&m].0);
+        }
+
+        for node_key in self.node_lut.values_mut() {
+            *node_key = node_keys[node_key];
+        }
+        for edge_key in self.edge_lut.values_mut() {
+            *edge_key = edge_keys[edge_key].0;
+        }
+        self.node_types.map_keys(|node_key| node_keys[node_key]);
+        self.edge_types.map_keys(|edge_key| edge_keys[edge_key].0);
+
+        self.node_lut.shrink_to_fit();
+        self.edge_lut.shrink_to_fit();
+        self.nodes = nodes;
+        self.edges = edges;
+    }
+
+    /// Get the capacity reserved by the graph
+    ///
+    /// This can be used together with shrink_to_fit to keep track of unused memory
+    pub fn capacity(&self) -> GraphCapacity {
+        GraphCapacity {
+            nodes: self.nodes.capacity(),
+            edges: self.edges.capacity(),
+            node_lut: self.node_lut.capacity(),
+            edge_lut: self.edge_lut.capacity(),
+            outgoing_edges: self.nodes.values().map(|n| n.outgoing_edges.len()).sum(),
+            incoming_edges: self.nodes.values().map(|n| n.incoming_edges.len()).sum(),
         }
     }
 
@@ -75,21 +376,31 @@ where
         self.edges.len()
     }
 
-    fn get_node_key(&self, node_id: NK) -> SchemaResult<NodeKey, NK, EK, S> {
+    /// Check if the graph contains no nodes
+    ///
+    /// A graph without nodes can not contain any edges either
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn get_node_key(&self, node_id: &NK) -> SchemaResult<NodeKey, NK, EK, S> {
         self.node_lut
-            .get(&node_id)
+            .get(node_id)
             .copied()
-            .ok_or_else(|| TypedError::MissingNode(node_id))
+            .ok_or_else(|| TypedError::MissingNode(node_id.clone()))
     }
 
-    fn get_edge_key(&self, edge_id: EK) -> SchemaResult<EdgeKey, NK, EK, S> {
+    fn get_edge_key(&self, edge_id: &EK) -> SchemaResult<EdgeKey, NK, EK, S> {
         self.edge_lut
-            .get(&edge_id)
+            .get(edge_id)
             .copied()
-            .ok_or_else(|| TypedError::MissingEdge(edge_id))
+            .ok_or_else(|| TypedError::MissingEdge(edge_id.clone()))
     }
 
-    fn get_node_internal(&self, node_key: NodeKey) -> SchemaResult<&NodeMetadata<S::N>, NK, EK, S> {
+    fn get_node_internal(
+        &self,
+        node_key: NodeKey,
+    ) -> SchemaResult<&SchemaNodeMetadata<NK, EK, S>, NK, EK, S> {
         self.nodes
             .get(node_key)
             .ok_or_else(|| TypedError::MissingNodeKey(node_key))
@@ -104,7 +415,7 @@ where
     fn get_node_mut_internal(
         &mut self,
         node_key: NodeKey,
-    ) -> SchemaResult<&mut NodeMetadata<S::N>, NK, EK, S> {
+    ) -> SchemaResult<&mut SchemaNodeMetadata<NK, EK, S>, NK, EK, S> {
         self.nodes
             .get_mut(node_key)
             .ok_or_else(|| TypedError::MissingNodeKey(node_key))
@@ -119,1098 +430,6950 @@ where
             .ok_or_else(|| TypedError::MissingEdgeKey(edge_key))
     }
 
-    pub fn get_node_safe(&self, node_id: NK) -> Option<&S::N> {
-        let key = self.node_lut.get(&node_id)?;
-        let node = self.nodes.get(*key)?;
-        Some(&node.weight)
-    }
-
-    pub fn get_node_safe_mut(&mut self, node_id: NK) -> Option<&mut S::N> {
-        let key = self.node_lut.get(&node_id)?;
-        let node = self.nodes.get_mut(*key)?;
-        Some(&mut node.weight)
-    }
-
-    pub fn get_edge_safe(&self, edge_id: EK) -> Option<&S::E> {
-        let key = self.edge_lut.get(&edge_id)?;
-        self.edges.get(*key).map(|x| &x.weight)
-    }
+    /// Register the edge with the outgoing edges of the source and incoming edges of the target
+    ///
+    /// In an undirected graph the edge is registered as both outgoing and incoming on both endpoints
+    fn attach_edge(
+        &mut self,
+        edge_key: EdgeKey,
+        source_key: NodeKey,
+        target_key: NodeKey,
+    ) -> SchemaResult<(), NK, EK, S> {
+        self.get_node_mut_internal(source_key)?
+            .outgoing_edges
+            .insert(edge_key);
+        self.get_node_mut_internal(target_key)?
+            .incoming_edges
+            .insert(edge_key);
 
-    pub fn get_edge_safe_mut(&mut self, edge_id: EK) -> Option<&mut S::E> {
-        let key = self.edge_lut.get(&edge_id)?;
-        self.edges.get_mut(*key).map(|x| &mut x.weight)
-    }
+        if !S::DIRECTED {
+            self.get_node_mut_internal(target_key)?
+                .outgoing_edges
+                .insert(edge_key);
+            self.get_node_mut_internal(source_key)?
+                .incoming_edges
+                .insert(edge_key);
+        }
 
-    pub fn get_node(&self, node_id: NK) -> SchemaResult<&S::N, NK, EK, S> {
-        self.get_node_safe(node_id)
-            .ok_or_else(|| TypedError::MissingNode(node_id))
+        Ok(())
     }
 
-    pub fn get_edge(&self, edge_id: EK) -> SchemaResult<&S::E, NK, EK, S> {
-        self.get_edge_safe(edge_id)
-            .ok_or_else(|| TypedError::MissingEdge(edge_id))
+    /// Remove an edge from a set of incoming edges
+    ///
+    /// The order of the remaining edges is only kept if the schema asks for it, since that is slower
+    fn remove_incoming_edge(incoming_edges: &mut IndexSet<EdgeKey>, edge_key: &EdgeKey) {
+        if S::ORDERED_INCOMING {
+            incoming_edges.shift_remove(edge_key);
+        } else {
+            incoming_edges.swap_remove(edge_key);
+        }
     }
 
-    pub fn get_edge_full(&self, edge_id: EK) -> SchemaResult<EdgeRef<'_, NK, EK, S>, NK, EK, S> {
-        let edge_key = self.get_edge_key(edge_id)?;
-        let edge = self.get_edge_internal(edge_key)?;
-        Ok(EdgeRef {
-            weight: &edge.weight,
-            source: self.nodes.get(edge.source).unwrap().get_id(),
-            target: self.nodes.get(edge.target).unwrap().get_id(),
-            direction: Direction::Outgoing,
-        })
-    }
+    /// Remove the edge from the outgoing and incoming edges of its endpoints
+    ///
+    /// Endpoints which has already been removed from the graph are skipped
+    fn detach_edge(&mut self, edge_key: EdgeKey, source_key: NodeKey, target_key: NodeKey) {
+        if let Some(source) = self.nodes.get_mut(source_key) {
+            source.outgoing_edges.shift_remove(&edge_key);
+            if !S::DIRECTED {
+                Self::remove_incoming_edge(&mut source.incoming_edges, &edge_key);
+            }
+        }
 
-    pub fn get_node_mut(&mut self, node_id: NK) -> SchemaResult<&mut S::N, NK, EK, S> {
-        self.get_node_safe_mut(node_id)
-            .ok_or_else(|| TypedError::MissingNode(node_id))
+        if let Some(target) = self.nodes.get_mut(target_key) {
+            Self::remove_incoming_edge(&mut target.incoming_edges, &edge_key);
+            if !S::DIRECTED {
+                target.outgoing_edges.shift_remove(&edge_key);
+            }
+        }
     }
 
-    pub fn get_edge_mut(&mut self, edge_id: EK) -> SchemaResult<&mut S::E, NK, EK, S> {
-        self.get_edge_safe_mut(edge_id)
-            .ok_or_else(|| TypedError::MissingEdge(edge_id))
+    /// List the quantities an edge is counted in as (node, direction, type of the other endpoint)
+    ///
+    /// Undirected edges are counted as both outgoing and incoming edges of both endpoints
+    fn quantity_endpoints(
+        source_key: NodeKey,
+        source_type: <S::N as Typed>::Type,
+        target_key: NodeKey,
+        target_type: <S::N as Typed>::Type,
+    ) -> Vec<(NodeKey, Direction, <S::N as Typed>::Type)> {
+        let mut endpoints = vec![
+            (source_key, Direction::Outgoing, target_type.clone()),
+            (target_key, Direction::Incoming, source_type.clone()),
+        ];
+        if !S::DIRECTED && source_key != target_key {
+            endpoints.push((target_key, Direction::Outgoing, source_type));
+            endpoints.push((source_key, Direction::Incoming, target_type));
+        }
+        endpoints
     }
 
-    pub fn get_node_downcast<'a: 'b, 'b, N: 'b>(&'a self, node_id: NK) -> SchemaResult<N, NK, EK, S>
-    where
-        S::N: Downcast<'b, NK, EK, N, S>,
-    {
-        self.get_node(node_id).and_then(|n| n.downcast())
-    }
+    /// Add or remove an edge from the quantities of its endpoints
+    ///
+    /// Both endpoints must still be in the graph, since their types are used to group the edge.
+    /// In an undirected graph the edge is counted as both outgoing and incoming on both endpoints
+    fn count_edge(
+        &mut self,
+        edge_type: <S::E as Typed>::Type,
+        source_key: NodeKey,
+        target_key: NodeKey,
+        increment: bool,
+    ) {
+        let (Some(source), Some(target)) = (self.nodes.get(source_key), self.nodes.get(target_key))
+        else {
+            return;
+        };
+        let endpoints =
+            Self::quantity_endpoints(source_key, source.get_type(), target_key, target.get_type());
+
+        for (node_key, direction, node_type) in endpoints {
+            let node = &mut self.nodes[node_key];
+            let quantities = match direction {
+                Direction::Outgoing => &mut node.outgoing_quantities,
+                Direction::Incoming => &mut node.incoming_quantities,
+            };
 
-    pub fn get_node_downcast_mut<'a: 'b, 'b, N: 'b>(&'a mut self, node_id: NK) -> SchemaResult<N, NK, EK, S>
-    where
-        S::N: DowncastMut<'b, NK, EK, N, S>,
-    {
-        self.get_node_mut(node_id).and_then(|n| n.downcast_mut())
+            if increment {
+                quantities.increment(edge_type.clone(), node_type);
+            } else {
+                quantities.decrement(&edge_type, &node_type);
+            }
+        }
     }
 
-    pub fn get_edge_downcast<'a: 'b, 'b, E: 'b>(&'a self, edge_id: EK) -> SchemaResult<E, NK, EK, S>
-    where
-        S::E: Downcast<'b, NK, EK, E, S>,
-    {
-        self.get_edge(edge_id).and_then(|e| e.downcast())
-    }
+    /// Replace the weight of a node while keeping the quantities of its neighbours up to date
+    ///
+    /// Returns the old weight of the node or None if the node does not exist
+    fn replace_node_weight(&mut self, node_key: NodeKey, weight: S::N) -> Option<S::N> {
+        let node = self.nodes.get(node_key)?;
 
-    pub fn get_edge_downcast_mut<'a: 'b, 'b, E: 'b>(&'a mut self, edge_id: EK) -> SchemaResult<E, NK, EK, S>
-    where
-        S::E: DowncastMut<'b, NK, EK, E, S>,
-    {
-        self.get_edge_mut(edge_id).and_then(|e| e.downcast_mut())
-    }
+        if node.get_type() == weight.get_type() {
+            return Some(std::mem::replace(&mut self.nodes[node_key].weight, weight));
+        }
 
-    pub fn has_node(&self, node_id: NK) -> bool {
-        self.node_lut.contains_key(&node_id)
-    }
+        self.node_types.remove(&node.get_type(), &node_key);
+        self.node_types.insert(weight.get_type(), node_key);
 
-    pub fn has_edge(&self, edge_id: EK) -> bool {
-        self.edge_lut.contains_key(&edge_id)
-    }
+        // Edge loops and undirected edges will show up in both incoming and outgoing edges
+        let edge_keys: IndexSet<EdgeKey> = node
+            .outgoing_edges
+            .iter()
+            .chain(node.incoming_edges.iter())
+            .copied()
+            .collect();
+        let edges: Vec<_> = edge_keys
+            .iter()
+            .filter_map(|edge_key| self.edges.get(*edge_key))
+            .map(|edge| (edge.get_type(), edge.source, edge.target))
+            .collect();
 
-    /// Reorder the outgoing edge order by inserting the source edge and shifting all other edges
-    ///
-    /// This will fail if both edges do not have the same source node
-    ///
-    /// InsertPosition::Before the source edge will have the same index as the target edge currently has
-    /// InsertPosition::After the source edge will be placed at target_idx + 1 instead
-    pub fn move_edge_order(
-        &mut self,
-        source_id: EK,
-        target_id: EK,
-        insert_position: InsertPosition,
-    ) -> SchemaResult<(), NK, EK, S> {
-        if source_id == target_id {
-            return Ok(());
+        for (edge_type, source_key, target_key) in &edges {
+            self.count_edge(edge_type.clone(), *source_key, *target_key, false);
         }
 
-        let source_edge = self.get_edge_full(source_id)?;
+        let old_weight = std::mem::replace(&mut self.nodes[node_key].weight, weight);
 
-        let source_key = self.get_edge_key(source_id)?;
-        let target_key = self.get_edge_key(target_id)?;
+        for (edge_type, source_key, target_key) in edges {
+            self.count_edge(edge_type, source_key, target_key, true);
+        }
 
-        // We base the search on the source node of the source edge
-        // Since both edges have the same source node this is fine
-        let node_key = self.get_node_key(source_edge.source)?;
-        let node = self.get_node_mut_internal(node_key)?;
+        Some(old_weight)
+    }
 
-        // Somehow an edge was created without it being registered with the node
-        if node.outgoing_edges.is_empty() {
-            return Err(TypedError::InvalidInternalState);
+    /// Bring the type index and the quantities up to date with weights which have been borrowed mutably
+    ///
+    /// The type of a node or edge can be changed through a mutable reference to its weight,
+    /// so every method which reads or updates the quantities repairs them first
+    fn reindex(&mut self) {
+        if !self.node_types.has_changes() && !self.edge_types.has_changes() {
+            return;
         }
 
-        if !node.outgoing_edges.contains(&target_key) {
-            return Err(TypedError::InvalidEdgeMove(source_id, target_id));
+        let affected_nodes = self.retyped_endpoints();
+
+        let nodes = &self.nodes;
+        let edges = &self.edges;
+        self.node_types
+            .repair(|node_key| nodes[*node_key].get_type());
+        self.edge_types
+            .repair(|edge_key| edges[*edge_key].get_type());
+
+        for node_key in affected_nodes {
+            if let Ok((outgoing_quantities, incoming_quantities)) =
+                self.recount_quantities(node_key)
+            {
+                let node = &mut self.nodes[node_key];
+                node.outgoing_quantities = outgoing_quantities;
+                node.incoming_quantities = incoming_quantities;
+            }
         }
+    }
 
-        let source_idx = node
-            .outgoing_edges
-            .get_index_of(&source_key)
-            .ok_or_else(|| TypedError::InvalidEdgeMove(source_id, target_id))?;
-
-        // Then we find out where in the order we want the node to be places
-        let mut target_idx = node
-            .outgoing_edges
-            .get_index_of(&target_key)
-            .ok_or_else(|| TypedError::InvalidEdgeMove(source_id, target_id))?;
-
-        // Figure out where to place the source relative to the target
-        // The position has to be adjusted as to not go out of bounds and
-        // play nicely with the behaviour of move_index
-        match insert_position {
-            InsertPosition::After => {
-                if target_idx + 1 != node.outgoing_edges.len() && source_idx > target_idx {
-                    target_idx += 1;
-                }
-            }
-            InsertPosition::Before => {
-                if target_idx != 0 && source_idx < target_idx {
-                    target_idx -= 1;
+    /// Find the nodes whose quantities are out of date because a node or edge has changed type through a mutable reference
+    ///
+    /// The quantities of a node are grouped by the type of its edges and neighbours,
+    /// so these are the neighbours of the retyped nodes and the endpoints of the retyped edges
+    fn retyped_endpoints(&self) -> IndexSet<NodeKey> {
+        let mut affected_nodes = IndexSet::new();
+
+        for node_key in self
+            .node_types
+            .retyped(|node_key| self.nodes[*node_key].get_type())
+        {
+            let node = &self.nodes[*node_key];
+            for edge_key in node.outgoing_edges.iter().chain(node.incoming_edges.iter()) {
+                if let Some(edge) = self.edges.get(*edge_key) {
+                    affected_nodes.insert(edge.source);
+                    affected_nodes.insert(edge.target);
                 }
             }
         }
 
-        // We then place the edge at the index and shift all the other edges to the right
-        node.outgoing_edges.move_index(source_idx, target_idx);
+        for edge_key in self
+            .edge_types
+            .retyped(|edge_key| self.edges[*edge_key].get_type())
+        {
+            let edge = &self.edges[*edge_key];
+            affected_nodes.insert(edge.source);
+            affected_nodes.insert(edge.target);
+        }
 
-        Ok(())
+        affected_nodes
     }
 
-    /// Add a node or update an existing one
-    /// Updating a node is only allowed if all the connected edges allows for the new type
-    pub fn add_node<N>(&mut self, node: N) -> SchemaResult<NK, NK, EK, S>
-    where
-        N: Into<S::N>,
-    {
-        let weight: S::N = node.into();
+    /// Count the outgoing and incoming quantities of a node from its edges
+    ///
+    /// This gives the same result as counting every edge using count_edge, but only for the one node
+    #[allow(clippy::type_complexity)]
+    fn recount_quantities(
+        &self,
+        node_key: NodeKey,
+    ) -> SchemaResult<
+        (
+            Quantities<<S::E as Typed>::Type, <S::N as Typed>::Type>,
+            Quantities<<S::E as Typed>::Type, <S::N as Typed>::Type>,
+        ),
+        NK,
+        EK,
+        S,
+    > {
+        let node = self.get_node_internal(node_key)?;
+
+        let mut outgoing_quantities = Quantities::default();
+        for edge_key in &node.outgoing_edges {
+            let edge = self.get_edge_internal(*edge_key)?;
+            // Undirected edges are also outgoing edges of their target
+            let other = if edge.source == node_key {
+                edge.target
+            } else {
+                edge.source
+            };
+            outgoing_quantities
+                .increment(edge.get_type(), self.get_node_internal(other)?.get_type());
+        }
 
-        // Check that the schema allows the type of the node
-        let weight_type = weight.get_type();
-        let allowed = self.schema.allow_node(weight.get_type());
-        if let Err(e) = allowed {
-            return Err(TypedError::InvalidNodeType(weight_type, e));
+        let mut incoming_quantities = Quantities::default();
+        for edge_key in &node.incoming_edges {
+            let edge = self.get_edge_internal(*edge_key)?;
+            let other = if edge.target == node_key {
+                edge.source
+            } else {
+                edge.target
+            };
+            incoming_quantities
+                .increment(edge.get_type(), self.get_node_internal(other)?.get_type());
         }
 
-        let node_id = weight.get_id();
-        // Check if there already exists a node at the given id
-        if let Ok(node_key) = self.get_node_key(node_id) {
-            let node = self.get_node_internal(node_key)?;
+        Ok((outgoing_quantities, incoming_quantities))
+    }
 
-            // Check if the existing node has the same type as the new one
-            if node.get_type() != weight_type {
-                // We now check if the new node is a replacement for the old one
+    /// Create a reference to an edge as seen from the given node
+    ///
+    /// In an undirected graph the edge is flipped such that the node is the source of outgoing edges
+    /// and the target of incoming edges
+    fn get_edge_ref<'a>(
+        &'a self,
+        node_key: NodeKey,
+        edge: &'a EdgeMetadata<S::E>,
+        direction: Direction,
+    ) -> SchemaResult<EdgeRef<'a, NK, EK, S>, NK, EK, S> {
+        let (source, target) = match direction {
+            Direction::Outgoing if !S::DIRECTED && edge.source != node_key => {
+                (edge.target, edge.source)
+            }
+            Direction::Incoming if !S::DIRECTED && edge.target != node_key => {
+                (edge.target, edge.source)
+            }
+            _ => (edge.source, edge.target),
+        };
 
-                // Join incoming and outgoing edges
-                let edge_keys = node.incoming_edges.iter().chain(node.outgoing_edges.iter());
+        Ok(EdgeRef {
+            weight: &edge.weight,
+            source: &self.get_node_internal(source)?.weight,
+            target: &self.get_node_internal(target)?.weight,
+            direction,
+        })
+    }
 
-                // Check if the new type is compatible with all the existing edges
-                for edge_key in edge_keys {
-                    let edge = self.get_edge_internal(*edge_key)?;
-                    let weight_type = edge.weight.get_type();
+    /// Lazily resolve the edges of a node in the given direction
+    fn edge_refs<'a>(
+        &'a self,
+        node_key: NodeKey,
+        direction: Direction,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        let node = self.get_node_internal(node_key)?;
+        Ok(self.node_edge_refs(node_key, node, direction))
+    }
 
-                    // Replace the source node with the new node if it is an outgoing edge
-                    let source_node = if edge.source == node_key {
-                        &weight
-                    } else {
-                        self.get_node_internal(edge.source)?
-                    };
+    /// Same as edge_refs for a node which has already been looked up
+    fn node_edge_refs<'a>(
+        &'a self,
+        node_key: NodeKey,
+        node: &'a SchemaNodeMetadata<NK, EK, S>,
+        direction: Direction,
+    ) -> impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>> {
+        let edges = match direction {
+            Direction::Outgoing => &node.outgoing_edges,
+            Direction::Incoming => &node.incoming_edges,
+        };
 
-                    // Replace the target node with the new node if it is an incoming edge
-                    let target_node = if edge.target == node_key {
-                        &weight
-                    } else {
-                        self.get_node_internal(edge.target)?
-                    };
+        edges.iter().map(move |edge_key| {
+            let edge = self.get_edge_internal(*edge_key)?;
+            self.get_edge_ref(node_key, edge, direction)
+        })
+    }
 
-                    let outgoing_quantity = self.count_quantity(
-                        source_node.get_id(), 
-                        Direction::Outgoing, 
-                        target_node.get_type(), 
-                        weight_type.clone()
-                    )?;
-                    let incoming_quantity = self.count_quantity(
-                        target_node.get_id(), 
-                        Direction::Incoming, 
-                        source_node.get_type(), 
-                        weight_type.clone()
-                    )?;
-
-                    let allowed = self.schema.allow_edge(
-                        // Account for the new type adding a new edge
-                        outgoing_quantity + 1,
-                        incoming_quantity + 1,
-                        weight_type.clone(),
-                        source_node.get_type(),
-                        target_node.get_type(),
-                    );
-                    if let Err(e) = allowed {
-                        return Err(TypedError::InvalidEdgeType(
-                            weight_type,
-                            source_node.get_type(),
-                            target_node.get_type(),
-                            e,
-                        ));
-                    }
-                }
+    fn get_edge_view<'a>(
+        &'a self,
+        edge: &'a EdgeMetadata<S::E>,
+    ) -> SchemaResult<EdgeView<'a, NK, EK, S>, NK, EK, S> {
+        Ok(EdgeView {
+            weight: &edge.weight,
+            source: &self.get_node_internal(edge.source)?.weight,
+            target: &self.get_node_internal(edge.target)?.weight,
+        })
+    }
+
+    /// Find groups of parallel edges which share the same source, target and type
+    ///
+    /// Returns the endpoints and type of every group with more than one edge together with the ids of its edges.
+    /// In an undirected graph the order of the endpoints does not matter
+    #[allow(clippy::type_complexity)]
+    pub fn duplicate_edges(&self) -> Vec<(NK, NK, <S::E as Typed>::Type, Vec<EK>)> {
+        self.duplicate_edges_by(|_| 1)
+    }
 
-                // Update the node
-                let node = self.get_node_mut_internal(node_key)?;
-                node.weight = weight;
+    /// Same as duplicate_edges but only groups with more edges than expected for their type are reported
+    ///
+    /// This allows edge types which are meant to be repeated between the same nodes to be checked against their own limit
+    #[allow(clippy::type_complexity)]
+    pub fn duplicate_edges_by<F>(
+        &self,
+        expected: F,
+    ) -> Vec<(NK, NK, <S::E as Typed>::Type, Vec<EK>)>
+    where
+        F: Fn(&<S::E as Typed>::Type) -> usize,
+    {
+        let mut groups: Vec<(NodeKey, NodeKey, <S::E as Typed>::Type, Vec<EK>)> = Vec::new();
+        // The groups between each pair of nodes, the types are compared within the pair
+        let mut pairs: HashMap<(NodeKey, NodeKey), Vec<usize>> = HashMap::new();
+
+        for (_, edge) in self.edges_in_outgoing_order(self.nodes.keys()) {
+            let endpoints = if !S::DIRECTED && edge.target < edge.source {
+                (edge.target, edge.source)
             } else {
-                // Just replace the node
-                let node = self.get_node_mut_internal(node_key)?;
-                node.weight = weight;
+                (edge.source, edge.target)
+            };
+
+            let edge_type = edge.weight.get_type();
+            let pair = pairs.entry(endpoints).or_default();
+            match pair.iter().find(|idx| groups[**idx].2 == edge_type) {
+                Some(idx) => groups[*idx].3.push(edge.get_id()),
+                None => {
+                    pair.push(groups.len());
+                    groups.push((endpoints.0, endpoints.1, edge_type, vec![edge.get_id()]));
+                }
             }
-        } else {
-            // Add the node to the graph
-            let node_key = self.nodes.insert(NodeMetadata {
-                weight: weight,
-                outgoing_edges: Default::default(),
-                incoming_edges: Default::default(),
-            });
-            self.node_lut.insert(node_id, node_key);
         }
 
-        Ok(node_id)
+        groups
+            .into_iter()
+            .filter(|(_, _, edge_type, edge_ids)| edge_ids.len() > expected(edge_type))
+            .map(|(source, target, edge_type, edge_ids)| {
+                (
+                    self.nodes[source].get_id(),
+                    self.nodes[target].get_id(),
+                    edge_type,
+                    edge_ids,
+                )
+            })
+            .collect()
     }
 
-    fn count_quantity(&self, node_id: NK, dir: Direction, node_type: <S::N as Typed>::Type, edge_type: <S::E as Typed>::Type) -> SchemaResult<usize, NK, EK, S> {
-        let mut quantity = 0;
-        let edges: Vec<_> = match dir {
-            Direction::Outgoing => self.get_outgoing(node_id)?.collect(),
-            Direction::Incoming => self.get_incoming(node_id)?.collect(),
-        };
-        for edge in edges {
-            // Only look at edges of the same type
-            if edge.get_type() != edge_type {
-                continue;
-            }
-
-            // Only look at edges going to nodes of the same type
-            let out_target_node = self.get_node(edge.get_outer())?;
-            if out_target_node.get_type() != node_type {
-                continue;
-            }
+    /// Iterate over all the edges of the given nodes grouped by their source node following the outgoing order
+    ///
+    /// Every edge is only visited once even if the graph is undirected
+    fn edges_in_outgoing_order<'a>(
+        &'a self,
+        node_keys: impl IntoIterator<Item = NodeKey> + 'a,
+    ) -> impl Iterator<Item = (EdgeKey, &'a EdgeMetadata<S::E>)> + 'a {
+        node_keys
+            .into_iter()
+            .filter_map(|node_key| self.nodes.get(node_key).map(|node| (node_key, node)))
+            .flat_map(move |(node_key, node)| {
+                node.outgoing_edges
+                    .iter()
+                    .filter_map(|edge_key| self.edges.get(*edge_key).map(|edge| (*edge_key, edge)))
+                    // Undirected edges are also in the outgoing edges of the target
+                    .filter(move |(_, edge)| edge.source == node_key)
+            })
+    }
 
-            quantity += 1;
-        }
+    pub fn get_node_safe(&self, node_id: NK) -> Option<&S::N> {
+        let key = self.node_lut.get(&node_id)?;
+        let node = self.nodes.get(*key)?;
+        Some(&node.weight)
+    }
 
-        Ok(quantity)
+    /// Get a mutable reference to the node weight
+    ///
+    /// If the type of the node is changed through the reference, the graph is updated to the new type the next time it is changed.
+    /// The new type is not checked against the schema, use `add_node` for that or `validate` the graph afterwards
+    pub fn get_node_safe_mut(&mut self, node_id: NK) -> Option<&mut S::N> {
+        let key = *self.node_lut.get(&node_id)?;
+        let node = self.nodes.get_mut(key)?;
+        self.node_types.mark_changed(key, node.get_type());
+        Some(&mut node.weight)
     }
 
-    /// Add an edge and if it already exists update the weight and enpoints of the edge
-    /// The edge will preserve the order of endpoints that does not change
-    pub fn add_edge<E>(&mut self, source: NK, target: NK, edge: E) -> SchemaResult<EK, NK, EK, S>
-    where
-        E: Into<S::E>,
-    {
-        let weight: S::E = edge.into();
-        let edge_id = weight.get_id();
+    pub fn get_edge_safe(&self, edge_id: EK) -> Option<&S::E> {
+        let key = self.edge_lut.get(&edge_id)?;
+        self.edges.get(*key).map(|x| &x.weight)
+    }
 
-        let source_key = self.get_node_key(source)?;
-        let target_key = self.get_node_key(target)?;
+    /// Get a mutable reference to the edge weight
+    ///
+    /// If the type of the edge is changed through the reference, the graph is updated to the new type the next time it is changed.
+    /// The new type is not checked against the schema, use `add_edge` for that or `validate` the graph afterwards
+    pub fn get_edge_safe_mut(&mut self, edge_id: EK) -> Option<&mut S::E> {
+        let key = *self.edge_lut.get(&edge_id)?;
+        let edge = self.edges.get_mut(key)?;
+        self.edge_types.mark_changed(key, edge.get_type());
+        Some(&mut edge.weight)
+    }
 
-        let weight_type = weight.get_type();
-        let source_node = self.get_node_internal(source_key)?;
-        let target_node = self.get_node_internal(target_key)?;
+    pub fn get_node(&self, node_id: NK) -> SchemaResult<&S::N, NK, EK, S> {
+        self.get_node_safe(node_id.clone())
+            .ok_or_else(|| TypedError::MissingNode(node_id))
+    }
 
-        let outgoing_quantity = self.count_quantity(
-            source_node.get_id(), 
-            Direction::Outgoing, 
-            target_node.get_type(), 
-            weight.get_type())
-            ?;
-        let incoming_quantity = self.count_quantity(
-            target_node.get_id(), 
-            Direction::Incoming, 
-            source_node.get_type(), 
-            weight.get_type()
-        )?;
+    pub fn get_edge(&self, edge_id: EK) -> SchemaResult<&S::E, NK, EK, S> {
+        self.get_edge_safe(edge_id.clone())
+            .ok_or_else(|| TypedError::MissingEdge(edge_id))
+    }
 
-        let allowed = self.schema.allow_edge(
-            outgoing_quantity + 1,
-            incoming_quantity + 1,
-            weight_type.clone(),
-            source_node.get_type(),
-            target_node.get_type(),
-        );
-        if let Err(e) = allowed {
-            return Err(TypedError::InvalidEdgeType(
-                weight_type,
-                source_node.get_type(),
-                target_node.get_type(),
-                e,
-            ));
+    /// Get mutable references to two different nodes at the same time
+    ///
+    /// Fails if the ids are the same or either node is missing
+    pub fn get_two_nodes_mut(
+        &mut self,
+        a: NK,
+        b: NK,
+    ) -> SchemaResult<(&mut S::N, &mut S::N), NK, EK, S> {
+        if a == b {
+            return Err(TypedError::AliasedNode(a));
         }
 
-        if let Some(edge_key) = self.edge_lut.get(&edge_id).copied() {
-            let edge = self.get_edge_mut_internal(edge_key)?;
-            edge.weight = weight;
+        let a_key = self.get_node_key(&a)?;
+        let b_key = self.get_node_key(&b)?;
+        let [a_node, b_node] = self
+            .nodes
+            .get_disjoint_mut([a_key, b_key])
+            .ok_or(TypedError::InvalidInternalState)?;
+        self.node_types.mark_changed(a_key, a_node.get_type());
+        self.node_types.mark_changed(b_key, b_node.get_type());
 
-            let source_key = edge.source;
-            let target_key = edge.target;
+        Ok((&mut a_node.weight, &mut b_node.weight))
+    }
 
-            // Update the source endpoint
-            let old_source = self.get_node_internal(source_key)?.get_id();
-            if old_source != source {
-                let old_source_key = self.get_node_key(old_source)?;
-                self.get_node_mut_internal(old_source_key)?
-                    .outgoing_edges
-                    .shift_remove(&edge_key);
+    /// Get a handle to a node for repeated access without looking up the id again
+    pub fn node_handle(&self, node_id: NK) -> Option<NodeHandle> {
+        self.node_lut.get(&node_id).copied().map(NodeHandle)
+    }
 
-                self.get_node_mut_internal(source_key)?
-                    .outgoing_edges
-                    .shift_remove(&edge_key);
-            }
+    pub fn get_node_by_handle(&self, handle: NodeHandle) -> SchemaResult<&S::N, NK, EK, S> {
+        Ok(&self.get_node_internal(handle.0)?.weight)
+    }
 
-            // Update the target
-            let old_target = self.get_node_internal(target_key)?.get_id();
-            if old_target != target {
-                let old_target_key = self.get_node_key(old_target)?;
-                self.get_node_mut_internal(old_target_key)?
-                    .incoming_edges
-                    .swap_remove(&edge_key);
-
-                self.get_node_mut_internal(target_key)?
-                    .incoming_edges
-                    .swap_remove(&edge_key);
-            }
-        } else {
-            // Insert the edge
-            let full_weight = EdgeMetadata {
-                weight,
-                source: source_key,
-                target: target_key,
-            };
-            let edge_key = self.edges.insert(full_weight);
-            self.edge_lut.insert(edge_id, edge_key);
+    /// Same as get_node_mut but using a handle
+    pub fn get_node_mut_by_handle(
+        &mut self,
+        handle: NodeHandle,
+    ) -> SchemaResult<&mut S::N, NK, EK, S> {
+        let node = self
+            .nodes
+            .get_mut(handle.0)
+            .ok_or_else(|| TypedError::MissingNodeKey(handle.0))?;
+        self.node_types.mark_changed(handle.0, node.get_type());
+        Ok(&mut node.weight)
+    }
 
-            // Add the edge to the source
-            self.get_node_mut_internal(source_key)?
-                .outgoing_edges
-                .insert(edge_key);
+    /// Get the number of outgoing edges of a node
+    pub fn out_degree_by_handle(&self, handle: NodeHandle) -> SchemaResult<usize, NK, EK, S> {
+        Ok(self.get_node_internal(handle.0)?.outgoing_edges.len())
+    }
 
-            // Add the edge to the target
-            self.get_node_mut_internal(target_key)?
-                .incoming_edges
-                .insert(edge_key);
-        }
+    /// Get the number of incoming edges of a node
+    pub fn in_degree_by_handle(&self, handle: NodeHandle) -> SchemaResult<usize, NK, EK, S> {
+        Ok(self.get_node_internal(handle.0)?.incoming_edges.len())
+    }
 
-        Ok(edge_id)
+    /// Same as try_get_outgoing but using a handle
+    pub fn get_outgoing_by_handle<'a>(
+        &'a self,
+        handle: NodeHandle,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        self.edge_refs(handle.0, Direction::Outgoing)
     }
 
-    /// Remove a node and all edges to and from it
-    pub fn remove_node(&mut self, node_id: NK) -> SchemaResult<S::N, NK, EK, S> {
-        let node_key = self
-            .node_lut
-            .remove(&node_id)
-            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
-        let node = self.nodes.remove(node_key).unwrap();
+    /// Same as try_get_incoming but using a handle
+    pub fn get_incoming_by_handle<'a>(
+        &'a self,
+        handle: NodeHandle,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        self.edge_refs(handle.0, Direction::Incoming)
+    }
 
-        for edge_key in node.outgoing_edges {
-            let edge = self
-                .edges
-                .remove(edge_key)
-                .ok_or_else(|| TypedError::InvalidInternalState)?;
-            self.edge_lut.remove(&edge.weight.get_id());
-            if edge.target != node_key {
-                self.get_node_mut_internal(edge.target)?
-                    .incoming_edges
-                    .swap_remove(&edge_key);
-            }
+    /// Get several nodes at once
+    ///
+    /// Fails with the first id which is not in the graph
+    pub fn get_nodes_many<const N: usize>(
+        &self,
+        node_ids: [NK; N],
+    ) -> SchemaResult<[&S::N; N], NK, EK, S> {
+        let nodes = node_ids
+            .into_iter()
+            .map(|node_id| self.get_node(node_id))
+            .collect::<SchemaResult<Vec<_>, NK, EK, S>>()?;
+
+        match nodes.try_into() {
+            Ok(nodes) => Ok(nodes),
+            Err(_) => unreachable!("One node is retrieved per id"),
         }
+    }
 
-        for edge_key in node.incoming_edges {
-            // edge loops will show up in both incoming and outgoing edges
-            // So the egde might already have been deleted
-            if !self.edges.contains_key(edge_key) {
-                continue;
-            }
+    /// Get several edges at once
+    ///
+    /// Fails with the first id which is not in the graph
+    pub fn get_edges_many<const N: usize>(
+        &self,
+        edge_ids: [EK; N],
+    ) -> SchemaResult<[&S::E; N], NK, EK, S> {
+        let edges = edge_ids
+            .into_iter()
+            .map(|edge_id| self.get_edge(edge_id))
+            .collect::<SchemaResult<Vec<_>, NK, EK, S>>()?;
 
-            let edge = self
-                .edges
-                .remove(edge_key)
-                .ok_or_else(|| TypedError::InvalidInternalState)?;
-            self.edge_lut.remove(&edge.weight.get_id());
-            if edge.source != node_key {
-                self.get_node_mut_internal(edge.source)?
-                    .outgoing_edges
-                    .shift_remove(&edge_key);
-            }
+        match edges.try_into() {
+            Ok(edges) => Ok(edges),
+            Err(_) => unreachable!("One edge is retrieved per id"),
         }
-
-        Ok(node.weight)
     }
 
-    /// Remove an edge.
-    pub fn remove_edge(&mut self, edge_id: EK) -> SchemaResult<S::E, NK, EK, S> {
-        let edge_key = self
-            .edge_lut
-            .remove(&edge_id)
-            .ok_or_else(|| TypedError::EdgeIdMissing(edge_id))?;
+    pub fn get_edge_full(&self, edge_id: EK) -> SchemaResult<EdgeView<'_, NK, EK, S>, NK, EK, S> {
+        let edge_key = self.get_edge_key(&edge_id)?;
+        let edge = self.get_edge_internal(edge_key)?;
+        self.get_edge_view(edge)
+    }
 
-        // Remove the edge itself.
-        let edge = self
-            .edges
-            .remove(edge_key)
-            .ok_or_else(|| TypedError::InvalidInternalState)?;
-        self.get_node_mut_internal(edge.source)?
-            .outgoing_edges
-            .shift_remove(&edge_key);
-        self.get_node_mut_internal(edge.target)?
-            .incoming_edges
-            .swap_remove(&edge_key);
+    /// Get the ids of the source and target of an edge
+    pub fn edge_endpoints(&self, edge_id: EK) -> SchemaResult<(NK, NK), NK, EK, S> {
+        let edge_key = self.get_edge_key(&edge_id)?;
+        let edge = self.get_edge_internal(edge_key)?;
+        Ok((
+            self.get_node_internal(edge.source)?.get_id(),
+            self.get_node_internal(edge.target)?.get_id(),
+        ))
+    }
 
-        Ok(edge.weight)
+    pub fn get_node_mut(&mut self, node_id: NK) -> SchemaResult<&mut S::N, NK, EK, S> {
+        self.get_node_safe_mut(node_id.clone())
+            .ok_or_else(|| TypedError::MissingNode(node_id))
     }
 
-    /// Get all incoming edges
-    pub fn get_incoming<'a>(
-        &'a self,
-        node_id: NK,
-    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S> {
-        let node_key = *self
-            .node_lut
-            .get(&node_id)
-            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
-        Ok(self
-            .get_node_internal(node_key)?
-            .incoming_edges
-            .iter()
-            .map(|edge_key| {
-                let edge = self.edges.get(*edge_key).unwrap();
-                EdgeRef {
-                    weight: &edge.weight,
-                    source: self.nodes.get(edge.source).unwrap().get_id(),
-                    target: self.nodes.get(edge.target).unwrap().get_id(),
-                    direction: Direction::Incoming,
-                }
-            }))
+    pub fn get_edge_mut(&mut self, edge_id: EK) -> SchemaResult<&mut S::E, NK, EK, S> {
+        self.get_edge_safe_mut(edge_id.clone())
+            .ok_or_else(|| TypedError::MissingEdge(edge_id))
     }
 
-    pub fn get_outgoing<'a>(
-        &'a self,
-        node_id: NK,
-    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S> {
-        let node_key = *self
-            .node_lut
-            .get(&node_id)
-            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
-        Ok(self
-            .get_node_internal(node_key)?
-            .outgoing_edges
-            .iter()
-            .map(|edge_key| {
-                let edge = self.edges.get(*edge_key).unwrap();
-                EdgeRef {
-                    weight: &edge.weight,
-                    source: self.nodes.get(edge.source).unwrap().get_id(),
-                    target: self.nodes.get(edge.target).unwrap().get_id(),
-                    direction: Direction::Outgoing,
-                }
-            }))
+    /// Apply the function to the weight of every node without changing the topology of the graph
+    ///
+    /// The function must not change the id of the nodes, which is checked in debug builds.
+    /// Changing the type of a node is handled the same way as through get_node_mut
+    pub fn map_nodes<F: FnMut(&mut S::N)>(&mut self, mut f: F) {
+        for (node_key, node) in self.nodes.iter_mut() {
+            let id = node.get_id();
+            let ty = node.get_type();
+
+            f(&mut node.weight);
+
+            debug_assert!(id == node.get_id(), "map_nodes changed the id of a node");
+            if ty != node.get_type() {
+                self.node_types.mark_changed(node_key, ty);
+            }
+        }
     }
 
-    pub fn get_incoming_and_outgoing<'a>(
-        &'a self,
-        node_id: NK,
-    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S> {
-        self.get_incoming(node_id)
-            .and_then(|inc| self.get_outgoing(node_id).map(|out| inc.chain(out)))
+    /// Apply the function to the weight of every edge without changing the topology of the graph
+    ///
+    /// The function must not change the id of the edges, which is checked in debug builds.
+    /// Changing the type of an edge is handled the same way as through get_edge_mut
+    pub fn map_edges<F: FnMut(&mut S::E)>(&mut self, mut f: F) {
+        for (edge_key, edge) in self.edges.iter_mut() {
+            let id = edge.get_id();
+            let ty = edge.get_type();
+
+            f(&mut edge.weight);
+
+            debug_assert!(id == edge.get_id(), "map_edges changed the id of an edge");
+            if ty != edge.get_type() {
+                self.edge_types.mark_changed(edge_key, ty);
+            }
+        }
     }
 
-    pub fn get_outgoing_filter<'a, F>(
-        &'a self,
-        node_id: NK,
-        filter: F,
-    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S>
-    where
-        F: Fn(&S::E) -> bool,
-    {
-        Ok(self
-            .get_outgoing(node_id)?
-            .filter(move |e| filter(&e.weight)))
+    /// Borrow the weight of a node which is known to exist, the type may be changed through the reference
+    pub(crate) fn node_weight_mut(&mut self, node_key: NodeKey) -> &mut S::N {
+        let node = &mut self.nodes[node_key];
+        self.node_types.mark_changed(node_key, node.get_type());
+        &mut node.weight
     }
 
+    /// Borrow the weight of an edge which is known to exist, the type may be changed through the reference
+    pub(crate) fn edge_weight_mut(&mut self, edge_key: EdgeKey) -> &mut S::E {
+        let edge = &mut self.edges[edge_key];
+        self.edge_types.mark_changed(edge_key, edge.get_type());
+        &mut edge.weight
+    }
 
-    pub fn get_incoming_filter<'a, F>(
-        &'a self,
+    /// Get the entry of a node id for in-place modification or insertion
+    pub fn node_entry(
+        &mut self,
         node_id: NK,
-        filter: F,
-    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S>
-    where
-        F: Fn(&S::E) -> bool,
-    {
-        Ok(self
-            .get_incoming(node_id)?
-            .filter(move |e| filter(&e.weight)))
+    ) -> SchemaResult<NodeEntry<'_, NK, EK, S, H>, NK, EK, S> {
+        match self.node_lut.get(&node_id).copied() {
+            Some(node_key) => {
+                let node = self
+                    .nodes
+                    .get_mut(node_key)
+                    .ok_or_else(|| TypedError::MissingNodeKey(node_key))?;
+                self.node_types.mark_changed(node_key, node.get_type());
+                Ok(NodeEntry::Occupied(OccupiedNodeEntry {
+                    weight: &mut node.weight,
+                }))
+            }
+            None => Ok(NodeEntry::Vacant(VacantNodeEntry {
+                graph: self,
+                node_id,
+            })),
+        }
     }
 
-    pub fn get_incoming_and_outgoing_filter<'a, F>(
-        &'a self,
-        node_id: NK,
-        filter: F,
-    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S>
-    where
-        F: Fn(&S::E) -> bool,
-    {
-        Ok(self
-            .get_incoming_and_outgoing(node_id)?
-            .filter(move |e| filter(&e.weight)))
+    /// Get the entry of an edge id for in-place modification or insertion
+    pub fn edge_entry(
+        &mut self,
+        edge_id: EK,
+    ) -> SchemaResult<EdgeEntry<'_, NK, EK, S, H>, NK, EK, S> {
+        match self.edge_lut.get(&edge_id).copied() {
+            Some(edge_key) => {
+                let edge = self
+                    .edges
+                    .get_mut(edge_key)
+                    .ok_or_else(|| TypedError::MissingEdgeKey(edge_key))?;
+                self.edge_types.mark_changed(edge_key, edge.get_type());
+                Ok(EdgeEntry::Occupied(OccupiedEdgeEntry {
+                    weight: &mut edge.weight,
+                }))
+            }
+            None => Ok(EdgeEntry::Vacant(VacantEdgeEntry {
+                graph: self,
+                edge_id,
+            })),
+        }
     }
 
-    pub fn nodes(&self) -> impl Iterator<Item = &S::N> + '_ {
-        self.nodes.values().map(Deref::deref)
+    pub fn get_node_downcast<'a: 'b, 'b, N: 'b>(&'a self, node_id: NK) -> SchemaResult<N, NK, EK, S>
+    where
+        S::N: Downcast<'b, NK, EK, N, S>,
+    {
+        self.get_node(node_id).and_then(|n| n.downcast())
     }
 
-    pub fn edges(&self) -> impl Iterator<Item = &S::E> + '_ {
-        self.edges.values().map(Deref::deref)
+    pub fn get_node_downcast_mut<'a: 'b, 'b, N: 'b>(&'a mut self, node_id: NK) -> SchemaResult<N, NK, EK, S>
+    where
+        S::N: DowncastMut<'b, NK, EK, N, S>,
+    {
+        self.get_node_mut(node_id).and_then(|n| n.downcast_mut())
     }
 
-    pub fn edges_full<'a>(&'a self) -> impl Iterator<Item = EdgeRef<'a, NK, EK, S>> + 'a {
-        self.edges.values().map(|edge| EdgeRef {
-            weight: &edge.weight,
-            source: self.nodes.get(edge.source).unwrap().get_id(),
-            target: self.nodes.get(edge.target).unwrap().get_id(),
-            direction: Direction::Outgoing,
-        })
+    pub fn get_edge_downcast<'a: 'b, 'b, E: 'b>(&'a self, edge_id: EK) -> SchemaResult<E, NK, EK, S>
+    where
+        S::E: Downcast<'b, NK, EK, E, S>,
+    {
+        self.get_edge(edge_id).and_then(|e| e.downcast())
     }
 
-    pub fn nodes_downcast<'a, T: 'a>(&'a self) -> impl Iterator<Item = T> + 'a where S::N: Downcast<'a, NK, EK, T, S> {
-        self.nodes.values().map(Deref::deref).filter_map(|f| f.downcast().ok())
+    pub fn get_edge_downcast_mut<'a: 'b, 'b, E: 'b>(&'a mut self, edge_id: EK) -> SchemaResult<E, NK, EK, S>
+    where
+        S::E: DowncastMut<'b, NK, EK, E, S>,
+    {
+        self.get_edge_mut(edge_id).and_then(|e| e.downcast_mut())
     }
 
-    pub fn edges_downcast<'a, T: 'a>(&'a self) -> impl Iterator<Item = T> + 'a where S::E: Downcast<'a, NK, EK, T, S> {
-        self.edges.values().map(Deref::deref).filter_map(|f| f.downcast().ok())
-    }
+    /// Call the function with the id and downcast weight of every outgoing edge of the node
+    ///
+    /// Iterating the outgoing edges borrows the graph immutably, so their weights can not be changed while iterating.
+    /// Instead the ids of the edges are collected first and each edge is then borrowed mutably one at a time,
+    /// which is the same as collecting the ids from get_outgoing and calling get_edge_downcast_mut for each of them.
+    ///
+    /// Edges which can not be downcast into E are skipped
+    pub fn for_each_outgoing_mut<E, F>(
+        &mut self,
+        node_id: NK,
+        mut f: F,
+    ) -> SchemaResult<(), NK, EK, S>
+    where
+        S::E: for<'b> DowncastMut<'b, NK, EK, &'b mut E, S>,
+        F: FnMut(EK, &mut E),
+    {
+        let edge_ids: Vec<EK> = self
+            .get_outgoing(node_id)?
+            .map(|edge| edge.get_id())
+            .collect();
 
-    pub fn node_ids(&self) -> impl Iterator<Item = NK> + '_ {
-        self.nodes.values().map(|n| n.get_id())
-    }
+        for edge_id in edge_ids {
+            let weight = self.get_edge_mut(edge_id.clone())?;
+            if let Ok(edge) = DowncastMut::<'_, NK, EK, &mut E, S>::downcast_mut(weight) {
+                f(edge_id, edge);
+            }
+        }
 
-    pub fn edge_ids(&self) -> impl Iterator<Item = EK> + '_ {
-        self.edges.values().map(|e| e.get_id())
+        Ok(())
     }
 
-    /// Apply a Migration to the current graph
-    pub fn migrate<NS>(
-        self,
-        new_schema: NS,
-        handler: &S::Handler,
-    ) -> GenericTypedResult<TypedGraph<NK, EK, NS>, NK, EK>
+    /// Get an edge together with its source and target where all three are cast into more specific types
+    ///
+    /// Fails if any of the three casts fails
+    pub fn get_edge_typed<'a: 'b, 'b, E: 'b, SrcN: 'b, TgtN: 'b>(
+        &'a self,
+        edge_id: EK,
+    ) -> SchemaResult<(E, SrcN, TgtN), NK, EK, S>
     where
-        S: Migration<NK, EK, NS>,
-        NS: SchemaExt<NK, EK> + Clone,
+        S::E: Downcast<'b, NK, EK, E, S>,
+        S::N: Downcast<'b, NK, EK, SrcN, S> + Downcast<'b, NK, EK, TgtN, S>,
     {
-        Migration::migrate(self, handler, new_schema)
+        let edge = self.get_edge_full(edge_id)?;
+        let weight = edge.get_weight().downcast()?;
+        let source = Downcast::<'b, NK, EK, SrcN, S>::downcast(self.get_node(edge.get_source())?)?;
+        let target = Downcast::<'b, NK, EK, TgtN, S>::downcast(self.get_node(edge.get_target())?)?;
+        Ok((weight, source, target))
     }
 
-    /// Migrate directly from one version to another
-    pub fn migrate_direct<NS>(self) -> GenericTypedResult<TypedGraph<NK, EK, NS>, NK, EK>
-    where
-        S: DirectMigration<NK, EK, NS>,
-        NS: SchemaExt<NK, EK>,
-    {
-        DirectMigration::migrate(self)
+    pub fn has_node(&self, node_id: NK) -> bool {
+        self.node_lut.contains_key(&node_id)
     }
 
-    /// Convert the graph from one schema to another using two mapping functions
-    /// The mapping functions are not allowed to change the id of any of the nodes only their data
-    ///
-    ///
-    /// Returning None from a mapping function will delete the node from the resulting graph
+    pub fn has_edge(&self, edge_id: EK) -> bool {
+        self.edge_lut.contains_key(&edge_id)
+    }
+
+    /// Reorder the outgoing edge order by inserting the source edge and shifting all other edges
     ///
+    /// This will fail if both edges do not have the same source node
     ///
-    /// When mapping to a schema with lower bounds on the number of edges allowed from a node.
-    /// Edges higher in the outgoing edge order will be removed
-    pub fn update_schema<NS, NF, EF>(
-        mut self,
-        schema: NS,
-        node_map: NF,
-        edge_map: EF,
-    ) -> SchemaResult<TypedGraph<NK, EK, NS>, NK, EK, NS>
-    where
-        NS: SchemaExt<NK, EK>,
-        NF: Fn(&S, &NS, S::N) -> Option<NS::N>,
-        EF: Fn(&S, &NS, S::E) -> Option<NS::E>,
-    {
-        let old_schema = self.schema;
-        let mut new_graph = TypedGraph::new(schema);
-
-        // Create a list of all the edges that stores them in outgoing order
-        let mut edges = Vec::new();
-        for (_, node) in &self.nodes {
-            for e in &node.outgoing_edges {
-                edges.push(
-                    self.edges
-                        .remove(*e)
-                        .ok_or_else(|| TypedError::InvalidInternalState)?,
-                );
-            }
+    /// InsertPosition::Before the source edge will have the same index as the target edge currently has
+    /// InsertPosition::After the source edge will be placed at target_idx + 1 instead
+    pub fn move_edge_order(
+        &mut self,
+        source_id: EK,
+        target_id: EK,
+        insert_position: InsertPosition,
+    ) -> SchemaResult<(), NK, EK, S> {
+        if source_id == target_id {
+            return Ok(());
         }
 
-        let mut node_id_lut = HashMap::new();
+        let source_edge = self.get_edge_full(source_id.clone())?;
 
-        for (nk, node) in self.nodes {
-            let old_id = node.get_id();
-            node_id_lut.insert(nk, old_id);
+        let source_key = self.get_edge_key(&source_id)?;
+        let target_key = self.get_edge_key(&target_id)?;
 
-            // Remove the node if it is not part of the new schema
-            if let Some(n) = node_map(&old_schema, new_graph.get_schema(), node.weight) {
-                // Check that the mapping function is not changing the id
-                if n.get_id() != old_id {
-                    return Err(TypedError::InconsistentNodeIds(old_id, n.get_id()));
-                }
+        // We base the search on the source node of the source edge
+        // Since both edges have the same source node this is fine
+        let node_key = self.get_node_key(&source_edge.get_source())?;
+        let node = self.get_node_mut_internal(node_key)?;
 
-                new_graph.add_node(n)?;
-            }
+        // Somehow an edge was created without it being registered with the node
+        if node.outgoing_edges.is_empty() {
+            return Err(TypedError::InvalidInternalState);
         }
 
-        // Update the edges in outgoing order
-        for edge in edges {
-            let old_id = edge.weight.get_id();
+        move_edge_in_order(
+            &mut node.outgoing_edges,
+            source_key,
+            target_key,
+            insert_position,
+        )
+        .ok_or_else(|| TypedError::InvalidEdgeMove(source_id, target_id))
+    }
 
-            // Remove the edge if it is not part of the new schema
-            if let Some(e) = edge_map(&old_schema, new_graph.get_schema(), edge.weight) {
-                // Check that the mapping function is not changing the id
-                if e.get_id() != old_id {
-                    return Err(TypedError::InconsistentEdgeIds(old_id, e.get_id()));
-                }
+    /// Reorder the incoming edge order by inserting the source edge and shifting all other edges
+    ///
+    /// This works the same way as `move_edge_order` but requires both edges to have the same target node.
+    /// The order is only kept when edges are removed if the schema sets `ORDERED_INCOMING`
+    pub fn move_incoming_edge_order(
+        &mut self,
+        source_id: EK,
+        target_id: EK,
+        insert_position: InsertPosition,
+    ) -> SchemaResult<(), NK, EK, S> {
+        if source_id == target_id {
+            return Ok(());
+        }
 
-                let source_id = *node_id_lut
-                    .get(&edge.source)
-                    .ok_or_else(|| TypedError::InvalidInternalState)?;
-                let target_id = *node_id_lut
-                    .get(&edge.target)
-                    .ok_or_else(|| TypedError::InvalidInternalState)?;
+        let source_edge = self.get_edge_full(source_id.clone())?;
 
-                // Don't include the edge if the source or target has been removed
-                if new_graph.has_node(source_id) && new_graph.has_node(target_id) {
-                    let e = new_graph.add_edge(source_id, target_id, e);
+        let source_key = self.get_edge_key(&source_id)?;
+        let target_key = self.get_edge_key(&target_id)?;
 
-                    match e {
-                        // Any excess edges are removed
-                        // Since edges are updated in outgoing order this will remove the last edges in the outgoing order
-                        Err(TypedError::InvalidEdgeType(_, _, _, DisAllowedEdge::ToManyOutgoing))
-                        | Err(TypedError::InvalidEdgeType(_, _, _, DisAllowedEdge::ToManyIncoming)) => (),
-                        Err(e) => Err(e)?,
-                        Ok(_) => (),
-                    }
-                }
-            }
+        // Both edges has the same target node so the target of the source edge is used
+        let node_key = self.get_node_key(&source_edge.get_target())?;
+        let node = self.get_node_mut_internal(node_key)?;
+
+        // Somehow an edge was created without it being registered with the node
+        if node.incoming_edges.is_empty() {
+            return Err(TypedError::InvalidInternalState);
         }
 
-        Ok(new_graph)
+        move_edge_in_order(
+            &mut node.incoming_edges,
+            source_key,
+            target_key,
+            insert_position,
+        )
+        .ok_or_else(|| TypedError::InvalidEdgeMove(source_id, target_id))
     }
-}
 
-impl<NK, EK, S> Default for TypedGraph<NK, EK, S>
-where
-    NK: Key,
-    EK: Key,
-    S: SchemaExt<NK, EK> + Default,
-{
-    fn default() -> Self {
-        TypedGraph {
-            node_lut: Default::default(),
-            edge_lut: Default::default(),
-            nodes: HopSlotMap::with_key(),
-            edges: HopSlotMap::with_key(),
-            schema: S::default(),
-        }
+    /// Get the ids of the outgoing edges of a node in their current order
+    pub fn outgoing_edge_order(&self, node_id: NK) -> SchemaResult<Vec<EK>, NK, EK, S> {
+        let node_key = self.get_node_key(&node_id)?;
+        self.get_node_internal(node_key)?
+            .outgoing_edges
+            .iter()
+            .map(|edge_key| Ok(self.get_edge_internal(*edge_key)?.get_id()))
+            .collect()
     }
-}
 
-use serde::de::Error;
-use serde::de::*;
-use serde::ser::*;
+    /// Replace the order of the outgoing edges of a node
+    ///
+    /// The order must contain each of the current outgoing edges exactly once,
+    /// otherwise the graph is left unchanged
+    pub fn set_outgoing_order(&mut self, node_id: NK, order: &[EK]) -> SchemaResult<(), NK, EK, S> {
+        let node_key = self.get_node_key(&node_id)?;
+        let node = self.get_node_internal(node_key)?;
+
+        let mut outgoing_edges = IndexSet::with_capacity(order.len());
+        for edge_id in order {
+            let edge_key = self
+                .edge_lut
+                .get(edge_id)
+                .copied()
+                .filter(|edge_key| node.outgoing_edges.contains(edge_key))
+                .ok_or_else(|| TypedError::InvalidEdgeOrder(node_id.clone()))?;
+
+            // The same edge is used twice
+            if !outgoing_edges.insert(edge_key) {
+                return Err(TypedError::InvalidEdgeOrder(node_id.clone()));
+            }
+        }
 
-/// A reference to an edge with its source and target id
-#[derive(Serialize)]
-struct EdgeWriteDTO<'a, NK, E> {
-    weight: &'a E,
-    source: NK,
-    target: NK,
-}
+        // Some of the edges were left out
+        if outgoing_edges.len() != node.outgoing_edges.len() {
+            return Err(TypedError::InvalidEdgeOrder(node_id));
+        }
 
-// This is what #[derive(Serialize)] would generate.
-impl<NK, EK, N, E, S> Serialize for TypedGraph<NK, EK, S>
-where
-    NK: Key + Serialize,
-    EK: Key + Serialize,
-    N: Serialize + NodeExt<NK>,
-    E: Serialize + EdgeExt<EK>,
-    S: SchemaExt<NK, EK, N = N, E = E> + Serialize,
-{
-    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+        self.get_node_mut_internal(node_key)?.outgoing_edges = outgoing_edges;
+        Ok(())
+    }
+
+    /// Sort the outgoing edges of a node using the given comparator
+    ///
+    /// The sort is stable, so edges which compare equal keep their current order
+    pub fn sort_outgoing_by<F>(&mut self, node_id: NK, mut cmp: F) -> SchemaResult<(), NK, EK, S>
     where
-        Ser: Serializer,
+        F: FnMut(&EdgeRef<'_, NK, EK, S>, &EdgeRef<'_, NK, EK, S>) -> Ordering,
     {
-        // Serialize the nodes as is
-        let nodes: Vec<_> = self.nodes.values().map(|n| &n.weight).collect();
+        let node_key = self.get_node_key(&node_id)?;
+        let node = self.get_node_internal(node_key)?;
 
-        // Wrap the edges in write dto
-        let edges: Vec<_> = self
-            .nodes
-            .values()
-            // Export the edges in outgoing order
-            // This ensure that the will be imported in the correct order
-            .flat_map(|n| n.outgoing_edges.iter())
-            .map(|ek| self.edges.get(*ek).unwrap())
-            .map(|e| EdgeWriteDTO {
-                weight: &e.weight,
-                source: self.nodes.get(e.source).unwrap().get_id(),
-                target: self.nodes.get(e.target).unwrap().get_id(),
+        let mut edges = node
+            .outgoing_edges
+            .iter()
+            .map(|edge_key| {
+                let edge = self.get_edge_internal(*edge_key)?;
+                let edge_ref = self.get_edge_ref(node_key, edge, Direction::Outgoing)?;
+                Ok((*edge_key, edge_ref))
             })
-            .collect();
+            .collect::<SchemaResult<Vec<_>, NK, EK, S>>()?;
+        edges.sort_by(|(_, a), (_, b)| cmp(a, b));
 
-        // Serialize the graph as a map with 3 fields
-        let mut s = serializer.serialize_map(Some(3))?;
-        s.serialize_entry("schema", &self.schema)?;
-        s.serialize_entry("nodes", &nodes)?;
-        s.serialize_entry("edges", &edges)?;
-        s.end()
+        let outgoing_edges = edges.into_iter().map(|(edge_key, _)| edge_key).collect();
+        self.get_node_mut_internal(node_key)?.outgoing_edges = outgoing_edges;
+
+        Ok(())
     }
-}
 
-/// An owned reference to aedge with its source and target id
-#[derive(Deserialize)]
-struct EdgeReadDTO<NK, E> {
-    weight: E,
-    source: NK,
-    target: NK,
-}
+    /// Add a node or update an existing one
+    /// Updating a node is only allowed if all the connected edges allows for the new node
+    pub fn add_node<N>(&mut self, node: N) -> SchemaResult<NK, NK, EK, S>
+    where
+        N: Into<S::N>,
+    {
+        let weight: S::N = node.into();
+        let node_id = weight.get_id();
+        self.insert_node_weight(weight)?;
+        Ok(node_id)
+    }
 
-/// A deserialize visitor that can generate a TypedGraph
-///
-/// this contains all the generics used by the TypeGraph since they would otherwise be seen as not used
-#[derive(Default)]
-struct TypedGraphVisitor<NK, EK, N, E, S>
-where
-    NK: Key,
-    EK: Key,
-    N: NodeExt<NK>,
-    E: EdgeExt<EK>,
-    S: SchemaExt<NK, EK, N = N, E = E>,
-{
-    nk: PhantomData<NK>,
-    ek: PhantomData<EK>,
-    n: PhantomData<N>,
-    e: PhantomData<E>,
-    s: PhantomData<S>,
-}
+    /// Same as add_node but returns the key of the node
+    pub(crate) fn insert_node_weight(&mut self, weight: S::N) -> SchemaResult<NodeKey, NK, EK, S> {
+        self.reindex();
 
-impl<'de, NK, EK, N, E, S> Visitor<'de> for TypedGraphVisitor<NK, EK, N, E, S>
-where
-    NK: Key + Display + Deserialize<'de>,
-    EK: Key + Display + Deserialize<'de>,
-    N: NodeExt<NK> + Deserialize<'de>,
-    E: EdgeExt<EK> + Deserialize<'de>,
-    S: SchemaExt<NK, EK, N = N, E = E> + Deserialize<'de>,
-{
-    /// Produce a typed graph
-    type Value = TypedGraph<NK, EK, S>;
+        // Check that the schema allows the type of the node
+        let weight_type = weight.get_type();
+        let allowed = self.schema.allow_node(weight.get_type());
+        if let Err(e) = allowed {
+            return Err(TypedError::InvalidNodeType(
+                weight_type,
+                e,
+                self.schema.name(),
+            ));
+        }
 
-    /// Message in case it all goes wrong
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("TypedGraph")
+        let node_id = weight.get_id();
+        // Check if there already exists a node at the given id
+        if let Ok(node_key) = self.get_node_key(&node_id) {
+            let node = self.get_node_internal(node_key)?;
+
+            // We now check if the new node is a replacement for the old one
+            // The new node is put in place so the quantities are counted using the new type
+            // and the old node is put back if any of the existing edges does not allow the new node.
+            // This is done even if the type is unchanged, as the schema may look at the whole weight
+            let edge_keys: Vec<EdgeKey> = node
+                .incoming_edges
+                .iter()
+                .chain(node.outgoing_edges.iter())
+                .copied()
+                .collect();
+
+            if let Some(old_weight) = self.replace_node_weight(node_key, weight) {
+                if let Err(e) = self.check_existing_edges(&edge_keys) {
+                    self.replace_node_weight(node_key, old_weight);
+                    return Err(e);
+                }
+            }
+
+            Ok(node_key)
+        } else {
+            // Add the node to the graph
+            let node_key = self.nodes.insert(NodeMetadata {
+                weight: weight,
+                outgoing_edges: Default::default(),
+                incoming_edges: Default::default(),
+                outgoing_quantities: Default::default(),
+                incoming_quantities: Default::default(),
+            });
+            self.node_lut.insert(node_id, node_key);
+            self.node_types.insert(weight_type, node_key);
+
+            Ok(node_key)
+        }
     }
 
-    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    /// Add a node or update an existing one of the same type
+    ///
+    /// Unlike add_node an existing node is never replaced by one of a different type,
+    /// instead NodeTypeChanged is returned and the graph is left unchanged
+    pub fn add_node_strict<N>(&mut self, node: N) -> SchemaResult<NK, NK, EK, S>
     where
-        M: MapAccess<'de>,
+        N: Into<S::N>,
+    {
+        let weight: S::N = node.into();
+        let node_id = weight.get_id();
+
+        if let Some(existing) = self.get_node_safe(node_id.clone()) {
+            let old_type = existing.get_type();
+            let new_type = weight.get_type();
+            if old_type != new_type {
+                return Err(TypedError::NodeTypeChanged(node_id, old_type, new_type));
+            }
+        }
+
+        self.add_node(weight)
+    }
+
+    /// Same as add_node but also tells whether the node was inserted or an existing node was updated
+    pub fn upsert_node<N>(&mut self, node: N) -> SchemaResult<Upsert<NK>, NK, EK, S>
+    where
+        N: Into<S::N>,
+    {
+        let weight: S::N = node.into();
+        let exists = self.has_node(weight.get_id());
+
+        let node_id = self.add_node(weight)?;
+        Ok(if exists {
+            Upsert::Updated(node_id)
+        } else {
+            Upsert::Inserted(node_id)
+        })
+    }
+
+    /// Get the node with the same id as the weight, or add the weight as a new node if there is none
+    ///
+    /// An existing node is returned as is and the given weight is dropped, so the type of the node is not changed.
+    /// Only a single lookup is made in either case
+    pub fn get_or_add_node<N>(&mut self, node: N) -> SchemaResult<&mut S::N, NK, EK, S>
+    where
+        N: Into<S::N>,
+    {
+        let weight: S::N = node.into();
+        let node_key = match self.node_lut.entry(weight.get_id()) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let weight_type = weight.get_type();
+                if let Err(e) = self.schema.allow_node(weight_type.clone()) {
+                    return Err(TypedError::InvalidNodeType(
+                        weight_type,
+                        e,
+                        self.schema.name(),
+                    ));
+                }
+
+                let node_key = self.nodes.insert(NodeMetadata {
+                    weight,
+                    outgoing_edges: Default::default(),
+                    incoming_edges: Default::default(),
+                    outgoing_quantities: Default::default(),
+                    incoming_quantities: Default::default(),
+                });
+                self.node_types.insert(weight_type, node_key);
+                *entry.insert(node_key)
+            }
+        };
+
+        let node = self
+            .nodes
+            .get_mut(node_key)
+            .ok_or_else(|| TypedError::MissingNodeKey(node_key))?;
+        self.node_types.mark_changed(node_key, node.get_type());
+        Ok(&mut node.weight)
+    }
+
+    /// Add or update multiple nodes
+    ///
+    /// Capacity is reserved up front based on the size hint of the iterator.
+    /// If a node fails to be added and `atomic` is true, all the changes made by the call are rolled back.
+    /// Otherwise the nodes added before the failing one are kept
+    pub fn add_nodes<N, I>(&mut self, nodes: I, atomic: bool) -> SchemaResult<Vec<NK>, NK, EK, S>
+    where
+        N: Into<S::N>,
+        I: IntoIterator<Item = N>,
+    {
+        let nodes = nodes.into_iter();
+        let (lower, _) = nodes.size_hint();
+        self.reserve(lower, 0);
+
+        let mut node_ids = Vec::with_capacity(lower);
+        // The previous weight of every node touched by the call
+        let mut previous: Vec<(NK, Option<S::N>)> = Vec::new();
+
+        for node in nodes {
+            let weight: S::N = node.into();
+
+            if atomic {
+                let node_id = weight.get_id();
+                let old_weight = self.get_node_safe(node_id.clone()).cloned();
+                previous.push((node_id, old_weight));
+            }
+
+            match self.add_node(weight) {
+                Ok(node_id) => node_ids.push(node_id),
+                Err(e) => {
+                    // Undo the changes in reverse order so the oldest weight is restored last
+                    for (node_id, old_weight) in previous.into_iter().rev() {
+                        let Ok(node_key) = self.get_node_key(&node_id) else {
+                            continue;
+                        };
+
+                        match old_weight {
+                            Some(old_weight) => {
+                                self.replace_node_weight(node_key, old_weight);
+                            }
+                            None => {
+                                // Newly added nodes have not had a chance to get any edges
+                                if let Some(node) = self.nodes.remove(node_key) {
+                                    self.node_types.remove(&node.get_type(), &node_key);
+                                }
+                                self.node_lut.remove(&node_id);
+                            }
+                        }
+                    }
+
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(node_ids)
+    }
+
+    /// Look up the number of edges of the given type going to or from nodes of the given type
+    fn get_quantity(
+        &self,
+        node_key: NodeKey,
+        dir: Direction,
+        edge_type: &<S::E as Typed>::Type,
+        node_type: &<S::N as Typed>::Type,
+    ) -> SchemaResult<usize, NK, EK, S> {
+        let node = self.get_node_internal(node_key)?;
+        let quantities = match dir {
+            Direction::Outgoing => &node.outgoing_quantities,
+            Direction::Incoming => &node.incoming_quantities,
+        };
+        Ok(quantities.get(edge_type, node_type))
+    }
+
+    /// Count the number of edges of the given type going to or from nodes of the given type
+    ///
+    /// Unlike `get_quantity` this looks at every edge of the node,
+    /// so it is only used to check the cached quantities in tests
+    #[cfg(test)]
+    fn count_quantity(&self, node_id: NK, dir: Direction, node_type: <S::N as Typed>::Type, edge_type: <S::E as Typed>::Type) -> SchemaResult<usize, NK, EK, S> {
+        let mut quantity = 0;
+        let edges: Vec<_> = match dir {
+            Direction::Outgoing => self.get_outgoing(node_id)?.collect(),
+            Direction::Incoming => self.get_incoming(node_id)?.collect(),
+        };
+        for edge in edges {
+            // Only look at edges of the same type
+            if edge.get_type() != edge_type {
+                continue;
+            }
+
+            // Only look at edges going to nodes of the same type
+            let out_target_node = self.get_node(edge.get_outer())?;
+            if out_target_node.get_type() != node_type {
+                continue;
+            }
+
+            quantity += 1;
+        }
+
+        Ok(quantity)
+    }
+
+    /// Add an edge and if it already exists update the weight and enpoints of the edge
+    /// The edge will preserve the order of endpoints that does not change
+    ///
+    /// If the schema declares the edge as symmetric, the mirrored edge going in the opposite direction is added or updated as well
+    pub fn add_edge<E>(&mut self, source: NK, target: NK, edge: E) -> SchemaResult<EK, NK, EK, S>
+    where
+        E: Into<S::E>,
+    {
+        let weight: S::E = edge.into();
+        let edge_id = weight.get_id();
+        self.insert_edge_weight(source, target, weight)?;
+        Ok(edge_id)
+    }
+
+    /// Same as add_edge but returns the key of the edge
+    pub(crate) fn insert_edge_weight(
+        &mut self,
+        source: NK,
+        target: NK,
+        weight: S::E,
+    ) -> SchemaResult<EdgeKey, NK, EK, S> {
+        self.reindex();
+        let edge_id = weight.get_id();
+
+        let old_mirror_key = self
+            .edge_lut
+            .get(&edge_id)
+            .and_then(|edge_key| self.edges.get(*edge_key))
+            .and_then(|edge| edge.mirror);
+
+        let Some(reverse_spec) = self.schema.symmetric_edge(weight.get_type()) else {
+            let edge_key = self.add_edge_internal(source, target, weight)?;
+
+            // The edge is no longer symmetric so the mirrored edge is removed
+            if let Some(old_mirror_key) = old_mirror_key {
+                self.get_edge_mut_internal(edge_key)?.mirror = None;
+                self.remove_edge_internal(old_mirror_key);
+            }
+
+            return Ok(edge_key);
+        };
+
+        let mirror_weight = (reverse_spec.reverse_edge)(&weight);
+        let mirror_id = mirror_weight.get_id();
+
+        // The mirrored edge may only replace the previous mirror of the edge
+        let is_collision = mirror_id == edge_id
+            || self
+                .edge_lut
+                .get(&mirror_id)
+                .is_some_and(|mirror_key| Some(*mirror_key) != old_mirror_key);
+        if is_collision {
+            return Err(TypedError::EdgeIdCollision(mirror_id));
+        }
+
+        // The edge is changed before its mirror, so it is put back if the mirror is not allowed
+        let snapshot = self.snapshot_edge(&edge_id);
+        let edge_key = self.add_edge_internal(source.clone(), target.clone(), weight)?;
+        let mirror_key = match self.add_edge_internal(target, source, mirror_weight) {
+            Ok(mirror_key) => mirror_key,
+            Err(e) => {
+                // Do not leave the edge without a matching mirror
+                match snapshot {
+                    Some(snapshot) => self.restore_edge_internal(snapshot),
+                    None => {
+                        self.remove_edge_internal(edge_key);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        self.get_edge_mut_internal(edge_key)?.mirror = Some(mirror_key);
+        self.get_edge_mut_internal(mirror_key)?.mirror = Some(edge_key);
+
+        Ok(edge_key)
+    }
+
+    /// Add an edge using only its id and type
+    ///
+    /// This is the same as add_edge for schemas where the edge weight can be built from the id and type alone, such as GenericWeight
+    pub fn add_edge_default(
+        &mut self,
+        source: NK,
+        target: NK,
+        id: EK,
+        ty: <S::E as Typed>::Type,
+    ) -> SchemaResult<EK, NK, EK, S>
+    where
+        S::E: From<(EK, <S::E as Typed>::Type)>,
+    {
+        self.add_edge(source, target, S::E::from((id, ty)))
+    }
+
+    /// Same as add_edge but also tells whether the edge was inserted or an existing edge was updated
+    pub fn upsert_edge<E>(
+        &mut self,
+        source: NK,
+        target: NK,
+        edge: E,
+    ) -> SchemaResult<Upsert<EK>, NK, EK, S>
+    where
+        E: Into<S::E>,
+    {
+        let weight: S::E = edge.into();
+        let exists = self.has_edge(weight.get_id());
+
+        let edge_id = self.add_edge(source, target, weight)?;
+        Ok(if exists {
+            Upsert::Updated(edge_id)
+        } else {
+            Upsert::Inserted(edge_id)
+        })
+    }
+
+    /// Add or update multiple edges given as (source, target, edge)
+    ///
+    /// Capacity is reserved up front based on the size hint of the iterator.
+    /// If an edge fails to be added and `atomic` is true, all the changes made by the call are rolled back.
+    /// Otherwise the edges added before the failing one are kept
+    pub fn add_edges<E, I>(&mut self, edges: I, atomic: bool) -> SchemaResult<Vec<EK>, NK, EK, S>
+    where
+        E: Into<S::E>,
+        I: IntoIterator<Item = (NK, NK, E)>,
+    {
+        let edges = edges.into_iter();
+        let (lower, _) = edges.size_hint();
+        self.reserve(0, lower);
+
+        let mut edge_ids = Vec::with_capacity(lower);
+        // The previous endpoints, weight and outgoing position of every edge touched by the call
+        let mut previous = Vec::new();
+
+        for (source, target, edge) in edges {
+            let weight: S::E = edge.into();
+
+            if atomic {
+                let edge_id = weight.get_id();
+                let snapshot = self.snapshot_edge(&edge_id);
+                previous.push((edge_id, snapshot));
+            }
+
+            match self.add_edge(source, target, weight) {
+                Ok(edge_id) => edge_ids.push(edge_id),
+                Err(e) => {
+                    // Undo the changes in reverse order so the oldest state is restored last
+                    for (edge_id, old_edge) in previous.into_iter().rev() {
+                        match old_edge {
+                            Some(old_edge) => self.restore_edge(old_edge),
+                            None => {
+                                let _ = self.remove_edge(edge_id);
+                            }
+                        }
+                    }
+
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(edge_ids)
+    }
+
+    /// Store the endpoints, weight and outgoing position of an edge so it can be restored later
+    fn snapshot_edge(&self, edge_id: &EK) -> Option<EdgeSnapshot<NK, S::E>> {
+        let edge_key = self.edge_lut.get(edge_id)?;
+        let edge = self.edges.get(*edge_key)?;
+        let source = self.nodes.get(edge.source)?;
+        let target = self.nodes.get(edge.target)?;
+
+        Some(EdgeSnapshot {
+            source: source.get_id(),
+            target: target.get_id(),
+            weight: edge.weight.clone(),
+            idx: source.outgoing_edges.get_index_of(edge_key)?,
+        })
+    }
+
+    /// Put an edge back the way it was when the snapshot was taken
+    fn restore_edge(&mut self, snapshot: EdgeSnapshot<NK, S::E>) {
+        let edge_id = snapshot.weight.get_id();
+        let source = snapshot.source.clone();
+
+        // The edge was allowed when the snapshot was taken, so restoring it will succeed
+        let _ = self.add_edge(snapshot.source, snapshot.target, snapshot.weight);
+        self.restore_outgoing_position(&source, &edge_id, snapshot.idx);
+    }
+
+    /// Same as restore_edge but the mirror of a symmetric edge is left as is
+    fn restore_edge_internal(&mut self, snapshot: EdgeSnapshot<NK, S::E>) {
+        let edge_id = snapshot.weight.get_id();
+        let source = snapshot.source.clone();
+
+        let _ = self.add_edge_internal(snapshot.source, snapshot.target, snapshot.weight);
+        self.restore_outgoing_position(&source, &edge_id, snapshot.idx);
+    }
+
+    /// Move an edge back to its previous position in the outgoing edges of its source
+    fn restore_outgoing_position(&mut self, source: &NK, edge_id: &EK, idx: usize) {
+        let (Ok(node_key), Ok(edge_key)) = (self.get_node_key(source), self.get_edge_key(edge_id))
+        else {
+            return;
+        };
+        let outgoing = &mut self.nodes[node_key].outgoing_edges;
+        if let Some(current_idx) = outgoing.get_index_of(&edge_key) {
+            outgoing.move_index(current_idx, idx.min(outgoing.len() - 1));
+        }
+    }
+
+    /// Swap the source and target of an edge
+    ///
+    /// The reversed edge is checked against the schema and the graph is left unchanged if it is not allowed.
+    /// The edge is placed last in the outgoing order of its new source
+    pub fn reverse_edge(&mut self, edge_id: EK) -> SchemaResult<(), NK, EK, S> {
+        let edge = self.get_edge_full(edge_id.clone())?;
+        let source = edge.get_source();
+        let target = edge.get_target();
+        let weight = edge.weight.clone();
+
+        if source == target {
+            return Ok(());
+        }
+
+        let snapshot = self.snapshot_edge(&edge_id);
+        if let Err(e) = self.add_edge(target, source, weight) {
+            // Symmetric edges may fail after the edge itself has been moved
+            if let Some(snapshot) = snapshot {
+                self.restore_edge(snapshot);
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Create a copy of the graph where the direction of every edge is flipped
+    ///
+    /// The outgoing edges of a node in the reversed graph are its incoming edges in the original graph.
+    /// The reversed edges are checked against the same schema, and the first edge which is not allowed is reported.
+    /// Undirected graphs are returned unchanged
+    pub fn reversed(&self) -> SchemaResult<Self, NK, EK, S>
+    where
+        S: Clone,
+        H: Clone,
     {
-        // Step 1: Deserialize the schema
-        let (schema_field, schema): (&'de str, S) = access
-            .next_entry()?
-            .ok_or_else(|| M::Error::missing_field("schema"))?;
-        if schema_field != "schema" {
-            return Err(M::Error::unknown_field(schema_field, &["schema"]));
+        if !S::DIRECTED {
+            return Ok(self.clone());
+        }
+
+        let mut g = TypedGraph::with_capacity_and_hasher(
+            self.schema.clone(),
+            self.node_count(),
+            self.edge_count(),
+            self.node_lut.hasher().clone(),
+        );
+        for node in self.nodes.values() {
+            g.add_node(node.weight.clone())?;
+        }
+
+        for node in self.nodes.values() {
+            for edge_key in &node.incoming_edges {
+                let edge = self.get_edge_internal(*edge_key)?;
+                let edge_id = edge.get_id();
+
+                // Symmetric edges are already added as the mirror of the other edge
+                if edge.mirror.is_some() && g.has_edge(edge_id.clone()) {
+                    continue;
+                }
+
+                let source = self.get_node_internal(edge.source)?.get_id();
+                let target = self.get_node_internal(edge.target)?.get_id();
+                g.add_edge(target, source, edge.weight.clone())
+                    .map_err(|e| match e {
+                        TypedError::InvalidEdgeType(_, _, _, reason, _) => {
+                            TypedError::ReversedEdgeNotAllowed(edge_id, reason)
+                        }
+                        e => e,
+                    })?;
+            }
+        }
+
+        Ok(g)
+    }
+
+    /// Merge the target of an edge into its source
+    ///
+    /// The edge is removed and every other edge of the target is moved to the source, after which the target is removed.
+    /// The moved edges are checked against the schema and the graph is left unchanged if any of them are not allowed.
+    /// Moved outgoing edges are placed last in the outgoing order of the source.
+    /// Returns the id of the source
+    pub fn contract_edge(&mut self, edge_id: EK) -> SchemaResult<NK, NK, EK, S> {
+        let (source, target) = self.edge_endpoints(edge_id.clone())?;
+
+        // A self loop has nothing to merge
+        if source == target {
+            self.remove_edge(edge_id)?;
+            return Ok(source);
+        }
+
+        let target_key = self.get_node_key(&target)?;
+        let target_node = self.get_node_internal(target_key)?;
+        let edge_ids = target_node
+            .outgoing_edges
+            .iter()
+            .chain(target_node.incoming_edges.iter())
+            .map(|edge_key| Ok(self.get_edge_internal(*edge_key)?.get_id()))
+            .collect::<SchemaResult<IndexSet<_>, NK, EK, S>>()?;
+
+        // The state of every edge touched by the contraction, starting with the contracted edge
+        let mut previous = Vec::with_capacity(edge_ids.len());
+        previous.extend(self.snapshot_edge(&edge_id));
+        self.remove_edge(edge_id)?;
+
+        for moved_id in edge_ids {
+            // The edge may have been removed as the mirror of the contracted edge
+            let Some(snapshot) = self.snapshot_edge(&moved_id) else {
+                continue;
+            };
+
+            let replace = |node_id| {
+                if node_id == target {
+                    source.clone()
+                } else {
+                    node_id
+                }
+            };
+            let moved = self.add_edge(
+                replace(snapshot.source.clone()),
+                replace(snapshot.target.clone()),
+                snapshot.weight.clone(),
+            );
+            previous.push(snapshot);
+
+            if let Err(e) = moved {
+                // Undo the changes in reverse order so the contracted edge is restored last
+                for snapshot in previous.into_iter().rev() {
+                    self.restore_edge(snapshot);
+                }
+                return Err(e);
+            }
         }
 
-        let mut g = TypedGraph::new(schema);
+        self.remove_node(target)?;
+        Ok(source)
+    }
+
+    /// Move some of the edges of a node to a new node
+    ///
+    /// Edges for which keep returns false are moved to the new node, while the rest stay on the original node.
+    /// Afterwards both nodes are checked against the schema, including any lower bound on their edges,
+    /// and the graph is left unchanged if either is not allowed.
+    /// Returns the id of the new node
+    pub fn split_node<N, F>(&mut self, node_id: NK, new: N, keep: F) -> SchemaResult<NK, NK, EK, S>
+    where
+        N: Into<S::N>,
+        F: Fn(&EdgeRef<'_, NK, EK, S>) -> bool,
+    {
+        let weight: S::N = new.into();
+        let new_id = weight.get_id();
+        if !self.has_node(node_id.clone()) {
+            return Err(TypedError::MissingNode(node_id));
+        }
+        if self.has_node(new_id.clone()) {
+            return Err(TypedError::NodeIdCollision(new_id));
+        }
+
+        let moved_ids: Vec<_> = self
+            .get_all_edges(node_id.clone())?
+            .filter(|edge| !keep(edge))
+            .map(|edge| edge.get_id())
+            .collect();
+
+        self.add_node(weight)?;
+
+        // The state of every moved edge so the split can be undone
+        let mut previous = Vec::with_capacity(moved_ids.len());
+        let mut result = Ok(());
+        for moved_id in moved_ids {
+            // The edge may have been moved as the mirror of another edge
+            let Some(snapshot) = self.snapshot_edge(&moved_id) else {
+                continue;
+            };
+
+            let replace = |id| if id == node_id { new_id.clone() } else { id };
+            result = self
+                .add_edge(
+                    replace(snapshot.source.clone()),
+                    replace(snapshot.target.clone()),
+                    snapshot.weight.clone(),
+                )
+                .map(|_| ());
+            previous.push(snapshot);
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        if result.is_ok() {
+            result = [node_id, new_id.clone()].into_iter().try_for_each(|id| {
+                let node_key = self.get_node_key(&id)?;
+                self.check_lower_bound(self.get_node_internal(node_key)?)
+            });
+        }
+
+        if let Err(e) = result {
+            // Undo the changes in reverse order so the oldest state is restored last
+            for snapshot in previous.into_iter().rev() {
+                self.restore_edge(snapshot);
+            }
+            self.remove_node(new_id)?;
+            return Err(e);
+        }
+
+        Ok(new_id)
+    }
+
+    /// Check that the schema still allows edges which are already part of the graph
+    fn check_existing_edges(&self, edge_keys: &[EdgeKey]) -> SchemaResult<(), NK, EK, S> {
+        for edge_key in edge_keys {
+            let edge = self.get_edge_internal(*edge_key)?;
+            let weight_type = edge.weight.get_type();
+            let source_node = self.get_node_internal(edge.source)?;
+            let target_node = self.get_node_internal(edge.target)?;
+
+            if edge.source == edge.target
+                && !self
+                    .schema
+                    .allow_self_loop(weight_type.clone(), source_node.get_type())
+            {
+                return Err(TypedError::InvalidEdgeType(
+                    weight_type,
+                    source_node.get_type(),
+                    target_node.get_type(),
+                    DisAllowedEdge::SelfLoop,
+                    self.schema.name(),
+                ));
+            }
+
+            // The quantities already include the edge itself
+            let outgoing_quantity = self.get_quantity(
+                edge.source,
+                Direction::Outgoing,
+                &weight_type,
+                &target_node.get_type(),
+            )?;
+            let incoming_quantity = self.get_quantity(
+                edge.target,
+                Direction::Incoming,
+                &weight_type,
+                &source_node.get_type(),
+            )?;
+
+            let allowed = self.schema.allow_edge_full(
+                outgoing_quantity,
+                incoming_quantity,
+                &edge.weight,
+                source_node,
+                target_node,
+            );
+            if let Err(e) = allowed {
+                return Err(TypedError::InvalidEdgeType(
+                    weight_type,
+                    source_node.get_type(),
+                    target_node.get_type(),
+                    e,
+                    self.schema.name(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the schema allows the edge to be added between the two nodes
+    fn check_edge(
+        &self,
+        source_key: NodeKey,
+        target_key: NodeKey,
+        weight: &S::E,
+    ) -> SchemaResult<(), NK, EK, S> {
+        let weight_type = weight.get_type();
+        let source_node = self.get_node_internal(source_key)?;
+        let target_node = self.get_node_internal(target_key)?;
+
+        if source_key == target_key
+            && !self
+                .schema
+                .allow_self_loop(weight_type.clone(), source_node.get_type())
+        {
+            return Err(TypedError::InvalidEdgeType(
+                weight_type,
+                source_node.get_type(),
+                target_node.get_type(),
+                DisAllowedEdge::SelfLoop,
+                self.schema.name(),
+            ));
+        }
+
+        let outgoing_quantity = self.get_quantity(
+            source_key,
+            Direction::Outgoing,
+            &weight_type,
+            &target_node.get_type(),
+        )?;
+        let incoming_quantity = self.get_quantity(
+            target_key,
+            Direction::Incoming,
+            &weight_type,
+            &source_node.get_type(),
+        )?;
+
+        // Account for the edge being added
+        let allowed = self.schema.allow_edge_full(
+            outgoing_quantity + 1,
+            incoming_quantity + 1,
+            weight,
+            source_node,
+            target_node,
+        );
+        if let Err(e) = allowed {
+            return Err(TypedError::InvalidEdgeType(
+                weight_type,
+                source_node.get_type(),
+                target_node.get_type(),
+                e,
+                self.schema.name(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn add_edge_internal(
+        &mut self,
+        source: NK,
+        target: NK,
+        weight: S::E,
+    ) -> SchemaResult<EdgeKey, NK, EK, S> {
+        let edge_id = weight.get_id();
+
+        let source_key = self.get_node_key(&source)?;
+        let target_key = self.get_node_key(&target)?;
+
+        let weight_type = weight.get_type();
+
+        // An edge being replaced should not count towards the quantities of its own endpoints
+        let existing_edge = match self.edge_lut.get(&edge_id).copied() {
+            Some(edge_key) => {
+                let edge = self.get_edge_internal(edge_key)?;
+                Some((edge_key, edge.get_type(), edge.source, edge.target))
+            }
+            None => None,
+        };
+        if let Some((_, old_weight_type, old_source_key, old_target_key)) = &existing_edge {
+            self.count_edge(
+                old_weight_type.clone(),
+                *old_source_key,
+                *old_target_key,
+                false,
+            );
+        }
+
+        if let Err(e) = self.check_edge(source_key, target_key, &weight) {
+            if let Some((_, old_weight_type, old_source_key, old_target_key)) = existing_edge {
+                self.count_edge(old_weight_type, old_source_key, old_target_key, true);
+            }
+            return Err(e);
+        }
+
+        if let Some((edge_key, old_weight_type, _, _)) = existing_edge {
+            self.edge_types.remove(&old_weight_type, &edge_key);
+            self.edge_types.insert(weight_type.clone(), edge_key);
+            self.count_edge(weight_type, source_key, target_key, true);
+
+            let edge = self.get_edge_mut_internal(edge_key)?;
+            edge.weight = weight;
+
+            let old_source_key = edge.source;
+            let old_target_key = edge.target;
+            edge.source = source_key;
+            edge.target = target_key;
+
+            if !S::DIRECTED {
+                // Undirected edges are registered on both endpoints in both directions
+                // so the edge is simply moved to the new endpoints
+                if old_source_key != source_key || old_target_key != target_key {
+                    self.detach_edge(edge_key, old_source_key, old_target_key);
+                    self.attach_edge(edge_key, source_key, target_key)?;
+                }
+            } else {
+                // Update the source endpoint
+                if old_source_key != source_key {
+                    self.get_node_mut_internal(old_source_key)?
+                        .outgoing_edges
+                        .shift_remove(&edge_key);
+
+                    self.get_node_mut_internal(source_key)?
+                        .outgoing_edges
+                        .insert(edge_key);
+                }
+
+                // Update the target
+                if old_target_key != target_key {
+                    Self::remove_incoming_edge(
+                        &mut self.get_node_mut_internal(old_target_key)?.incoming_edges,
+                        &edge_key,
+                    );
+
+                    self.get_node_mut_internal(target_key)?
+                        .incoming_edges
+                        .insert(edge_key);
+                }
+            }
+
+            Ok(edge_key)
+        } else {
+            // Insert the edge
+            let full_weight = EdgeMetadata {
+                weight,
+                source: source_key,
+                target: target_key,
+                mirror: None,
+            };
+            let edge_key = self.edges.insert(full_weight);
+            self.edge_lut.insert(edge_id, edge_key);
+            self.edge_types.insert(weight_type.clone(), edge_key);
+
+            // Add the edge to the source and target
+            self.attach_edge(edge_key, source_key, target_key)?;
+            self.count_edge(weight_type, source_key, target_key, true);
+
+            Ok(edge_key)
+        }
+    }
+
+    /// Remove a node and all edges to and from it
+    pub fn remove_node(&mut self, node_id: NK) -> SchemaResult<S::N, NK, EK, S> {
+        self.remove_node_internal(node_id, |_| ())
+    }
+
+    /// Remove a node and all edges to and from it, returning the weights of the removed edges as well
+    ///
+    /// Self loops and symmetric edges are only returned once.
+    /// The outgoing edges come first in their outgoing order followed by the incoming edges
+    #[allow(clippy::type_complexity)]
+    pub fn remove_node_with_edges(
+        &mut self,
+        node_id: NK,
+    ) -> SchemaResult<(S::N, Vec<S::E>), NK, EK, S> {
+        let mut edges = Vec::new();
+        let node = self.remove_node_internal(node_id, |edge| edges.push(edge))?;
+        Ok((node, edges))
+    }
+
+    /// Remove a node and its edges, passing the weight of every removed edge to the function
+    fn remove_node_internal<F>(
+        &mut self,
+        node_id: NK,
+        mut on_edge: F,
+    ) -> SchemaResult<S::N, NK, EK, S>
+    where
+        F: FnMut(S::E),
+    {
+        self.reindex();
+        let node_key = self
+            .node_lut
+            .remove(&node_id)
+            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
+        let node = self.get_node_mut_internal(node_key)?;
+
+        // Take the edges out of the node so removing them does not have to search through the node
+        let outgoing_edges = std::mem::take(&mut node.outgoing_edges);
+        let incoming_edges = std::mem::take(&mut node.incoming_edges);
+
+        // Edge loops and undirected edges will show up in both incoming and outgoing edges
+        // So the edge might already have been deleted
+        // The node is still in the graph, so the quantities of the other endpoints can be updated
+        for edge_key in outgoing_edges.iter().chain(incoming_edges.iter()) {
+            if let Some(edge) = self.remove_edge_internal(*edge_key) {
+                on_edge(edge.weight);
+            }
+        }
+
+        let node = self.nodes.remove(node_key).unwrap();
+        self.node_types.remove(&node.get_type(), &node_key);
+        Ok(node.weight)
+    }
+
+    /// Remove an edge.
+    pub fn remove_edge(&mut self, edge_id: EK) -> SchemaResult<S::E, NK, EK, S> {
+        self.reindex();
+        let edge_key = self
+            .edge_lut
+            .get(&edge_id)
+            .copied()
+            .ok_or_else(|| TypedError::EdgeIdMissing(edge_id))?;
+
+        // Remove the edge itself.
+        let edge = self
+            .remove_edge_internal(edge_key)
+            .ok_or_else(|| TypedError::InvalidInternalState)?;
+
+        // Symmetric edges are always removed together with their mirror
+        if let Some(mirror_key) = edge.mirror {
+            self.remove_edge_internal(mirror_key);
+        }
+
+        Ok(edge.weight)
+    }
+
+    /// Remove an edge from the graph and its endpoints
+    ///
+    /// Returns None if the edge has already been removed
+    fn remove_edge_internal(&mut self, edge_key: EdgeKey) -> Option<EdgeMetadata<S::E>> {
+        let edge = self.edges.remove(edge_key)?;
+        self.edge_lut.remove(&edge.weight.get_id());
+        self.edge_types.remove(&edge.get_type(), &edge_key);
+        self.count_edge(edge.get_type(), edge.source, edge.target, false);
+        self.detach_edge(edge_key, edge.source, edge.target);
+        Some(edge)
+    }
+
+    /// Get all incoming edges
+    ///
+    /// Edges which can not be resolved are skipped, use try_get_incoming to get an error for them instead
+    pub fn get_incoming<'a>(
+        &'a self,
+        node_id: NK,
+    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        let node_key = *self
+            .node_lut
+            .get(&node_id)
+            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
+        Ok(self
+            .edge_refs(node_key, Direction::Incoming)?
+            .filter_map(Result::ok))
+    }
+
+    /// Get all outgoing edges
+    ///
+    /// Edges which can not be resolved are skipped, use try_get_outgoing to get an error for them instead
+    pub fn get_outgoing<'a>(
+        &'a self,
+        node_id: NK,
+    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        let node_key = *self
+            .node_lut
+            .get(&node_id)
+            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
+        Ok(self
+            .edge_refs(node_key, Direction::Outgoing)?
+            .filter_map(Result::ok))
+    }
+
+    /// Get all incoming edges followed by all outgoing edges
+    ///
+    /// In a directed graph a self loop is both an incoming and outgoing edge, so it is returned twice.
+    /// Use get_all_edges to get every edge once
+    pub fn get_incoming_and_outgoing<'a>(
+        &'a self,
+        node_id: NK,
+    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        // In an undirected graph the incoming edges are the same as the outgoing ones
+        self.get_incoming(node_id.clone()).and_then(|inc| {
+            self.get_outgoing(node_id)
+                .map(|out| inc.filter(|_| S::DIRECTED).chain(out))
+        })
+    }
+
+    /// Get every edge connected to the node exactly once
+    ///
+    /// The outgoing edges come first followed by the incoming edges.
+    /// Self loops are only returned as outgoing edges
+    pub fn get_all_edges<'a>(
+        &'a self,
+        node_id: NK,
+    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        let outgoing = self.get_outgoing(node_id.clone())?;
+        // In an undirected graph the incoming edges are the same as the outgoing ones
+        let incoming = self
+            .get_incoming(node_id)?
+            .filter(|e| S::DIRECTED && e.get_source() != e.get_target());
+        Ok(outgoing.chain(incoming))
+    }
+
+    /// Same as get_incoming but each edge is resolved lazily
+    ///
+    /// An edge which can not be resolved is yielded as an error instead of failing the whole traversal
+    pub fn try_get_incoming<'a>(
+        &'a self,
+        node_id: NK,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        let node_key = *self
+            .node_lut
+            .get(&node_id)
+            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
+        self.edge_refs(node_key, Direction::Incoming)
+    }
+
+    /// Same as get_outgoing but each edge is resolved lazily
+    ///
+    /// An edge which can not be resolved is yielded as an error instead of failing the whole traversal
+    pub fn try_get_outgoing<'a>(
+        &'a self,
+        node_id: NK,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        let node_key = *self
+            .node_lut
+            .get(&node_id)
+            .ok_or_else(|| TypedError::NodeIdMissing(node_id))?;
+        self.edge_refs(node_key, Direction::Outgoing)
+    }
+
+    /// Same as get_incoming_and_outgoing but each edge is resolved lazily
+    pub fn try_get_incoming_and_outgoing<'a>(
+        &'a self,
+        node_id: NK,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S> {
+        // In an undirected graph the incoming edges are the same as the outgoing ones
+        self.try_get_incoming(node_id.clone()).and_then(|inc| {
+            self.try_get_outgoing(node_id)
+                .map(|out| inc.filter(|_| S::DIRECTED).chain(out))
+        })
+    }
+
+    pub fn get_outgoing_filter<'a, F>(
+        &'a self,
+        node_id: NK,
+        filter: F,
+    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S>
+    where
+        F: Fn(&S::E) -> bool,
+    {
+        Ok(self
+            .get_outgoing(node_id)?
+            .filter(move |e| filter(&e.weight)))
+    }
+
+
+    pub fn get_incoming_filter<'a, F>(
+        &'a self,
+        node_id: NK,
+        filter: F,
+    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S>
+    where
+        F: Fn(&S::E) -> bool,
+    {
+        Ok(self
+            .get_incoming(node_id)?
+            .filter(move |e| filter(&e.weight)))
+    }
+
+    pub fn get_incoming_and_outgoing_filter<'a, F>(
+        &'a self,
+        node_id: NK,
+        filter: F,
+    ) -> SchemaResult<impl Iterator<Item = EdgeRef<'a, NK, EK, S>>, NK, EK, S>
+    where
+        F: Fn(&S::E) -> bool,
+    {
+        Ok(self
+            .get_incoming_and_outgoing(node_id)?
+            .filter(move |e| filter(&e.weight)))
+    }
+
+    /// Same as get_outgoing_filter but the filter can fail
+    ///
+    /// Errors from the filter or from resolving an edge are yielded by the iterator,
+    /// so collecting into a Result stops at the first error
+    pub fn try_get_outgoing_filter<'a, F>(
+        &'a self,
+        node_id: NK,
+        filter: F,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S>
+    where
+        F: Fn(&S::E) -> SchemaResult<bool, NK, EK, S>,
+    {
+        Ok(self
+            .try_get_outgoing(node_id)?
+            .filter_map(move |e| try_filter_edge(e, &filter)))
+    }
+
+    /// Same as get_incoming_filter but the filter can fail
+    pub fn try_get_incoming_filter<'a, F>(
+        &'a self,
+        node_id: NK,
+        filter: F,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S>
+    where
+        F: Fn(&S::E) -> SchemaResult<bool, NK, EK, S>,
+    {
+        Ok(self
+            .try_get_incoming(node_id)?
+            .filter_map(move |e| try_filter_edge(e, &filter)))
+    }
+
+    /// Same as get_incoming_and_outgoing_filter but the filter can fail
+    pub fn try_get_incoming_and_outgoing_filter<'a, F>(
+        &'a self,
+        node_id: NK,
+        filter: F,
+    ) -> SchemaResult<impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>, NK, EK, S>
+    where
+        F: Fn(&S::E) -> SchemaResult<bool, NK, EK, S>,
+    {
+        Ok(self
+            .try_get_incoming_and_outgoing(node_id)?
+            .filter_map(move |e| try_filter_edge(e, &filter)))
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &S::N> + '_ {
+        self.nodes.values().map(Deref::deref)
+    }
+
+    /// Iterate over all nodes together with their outgoing edges
+    ///
+    /// This is the same as calling try_get_outgoing for every node but without looking up each node by its id.
+    /// An edge which can not be resolved is yielded as an error instead of failing the whole traversal
+    pub fn nodes_with_outgoing<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (&'a S::N, impl Iterator<Item = TryEdgeRef<'a, NK, EK, S>>)> {
+        self.nodes.iter().map(move |(node_key, node)| {
+            (
+                &node.weight,
+                self.node_edge_refs(node_key, node, Direction::Outgoing),
+            )
+        })
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &S::E> + '_ {
+        self.edges.values().map(Deref::deref)
+    }
+
+    /// Iterate over all nodes sorted by their id
+    ///
+    /// Unlike nodes the order does not depend on the order in which nodes were added and removed
+    pub fn nodes_ordered(&self) -> impl Iterator<Item = &S::N> + '_
+    where
+        NK: Ord,
+    {
+        let mut nodes: Vec<_> = self.nodes().collect();
+        nodes.sort_unstable_by_key(|node| node.get_id());
+        nodes.into_iter()
+    }
+
+    /// Iterate over all edges sorted by their id
+    ///
+    /// Unlike edges the order does not depend on the order in which edges were added and removed
+    pub fn edges_ordered(&self) -> impl Iterator<Item = &S::E> + '_
+    where
+        EK: Ord,
+    {
+        let mut edges: Vec<_> = self.edges().collect();
+        edges.sort_unstable_by_key(|edge| edge.get_id());
+        edges.into_iter()
+    }
+
+    /// Consume the graph and iterate over the owned node weights
+    ///
+    /// The nodes are returned in the same order as nodes
+    pub fn into_nodes(self) -> impl Iterator<Item = S::N> {
+        self.nodes.into_iter().map(|(_, node)| node.weight)
+    }
+
+    /// Consume the graph and iterate over the owned edge weights
+    ///
+    /// The edges are returned in the same order as edges.
+    /// Symmetric edges are returned together with their mirror
+    pub fn into_edges(self) -> impl Iterator<Item = S::E> {
+        self.edges.into_iter().map(|(_, edge)| edge.weight)
+    }
+
+    /// Consume the graph and split it into its schema and the owned node and edge weights
+    pub fn into_parts(self) -> (S, impl Iterator<Item = S::N>, impl Iterator<Item = S::E>) {
+        let nodes = self.nodes.into_iter().map(|(_, node)| node.weight);
+        let edges = self.edges.into_iter().map(|(_, edge)| edge.weight);
+        (self.schema, nodes, edges)
+    }
+
+    /// Iterate over all edges along with their endpoints
+    ///
+    /// Edges which can not be resolved are skipped, use try_edges_full to get an error for them instead
+    pub fn edges_full<'a>(&'a self) -> impl Iterator<Item = EdgeView<'a, NK, EK, S>> + 'a {
+        self.try_edges_full().filter_map(Result::ok)
+    }
+
+    /// Same as edges_full but each edge is resolved lazily
+    ///
+    /// An edge which can not be resolved is yielded as an error instead of being skipped
+    pub fn try_edges_full<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = SchemaResult<EdgeView<'a, NK, EK, S>, NK, EK, S>> + 'a {
+        self.edges.values().map(|edge| self.get_edge_view(edge))
+    }
+
+    /// Same as try_edges_full but the edges are grouped by their source node following the outgoing order
+    #[cfg(feature = "graphml")]
+    pub(crate) fn edges_full_in_outgoing_order(
+        &self,
+    ) -> impl Iterator<Item = SchemaResult<EdgeView<'_, NK, EK, S>, NK, EK, S>> + '_ {
+        self.edges_in_outgoing_order(self.nodes.keys())
+            .map(|(_, edge)| self.get_edge_view(edge))
+    }
+
+    pub fn nodes_downcast<'a, T: 'a>(&'a self) -> impl Iterator<Item = T> + 'a where S::N: Downcast<'a, NK, EK, T, S> {
+        self.nodes.values().map(Deref::deref).filter_map(|f| f.downcast().ok())
+    }
+
+    pub fn edges_downcast<'a, T: 'a>(&'a self) -> impl Iterator<Item = T> + 'a where S::E: Downcast<'a, NK, EK, T, S> {
+        self.edges.values().map(Deref::deref).filter_map(|f| f.downcast().ok())
+    }
+
+    pub fn node_ids(&self) -> impl Iterator<Item = NK> + '_ {
+        self.nodes.values().map(|n| n.get_id())
+    }
+
+    pub fn edge_ids(&self) -> impl Iterator<Item = EK> + '_ {
+        self.edges.values().map(|e| e.get_id())
+    }
+
+    /// Iterate over the ids of all nodes with the given type
+    ///
+    /// No order is guaranteed
+    pub fn nodes_of_type(&self, ty: <S::N as Typed>::Type) -> impl Iterator<Item = NK> + '_ {
+        self.node_types
+            .keys(ty, |node_key| self.nodes[*node_key].get_type())
+            .map(|node_key| self.nodes[*node_key].get_id())
+    }
+
+    pub fn count_nodes_of_type(&self, ty: <S::N as Typed>::Type) -> usize {
+        self.node_types
+            .count(&ty, |node_key| self.nodes[*node_key].get_type())
+    }
+
+    /// Iterate over the ids of all edges with the given type
+    ///
+    /// No order is guaranteed
+    pub fn edges_of_type(&self, ty: <S::E as Typed>::Type) -> impl Iterator<Item = EK> + '_ {
+        self.edge_types
+            .keys(ty, |edge_key| self.edges[*edge_key].get_type())
+            .map(|edge_key| self.edges[*edge_key].get_id())
+    }
+
+    pub fn count_edges_of_type(&self, ty: <S::E as Typed>::Type) -> usize {
+        self.edge_types
+            .count(&ty, |edge_key| self.edges[*edge_key].get_type())
+    }
+
+    /// Count the number of nodes and edges of each type
+    ///
+    /// The counts are read from the type index so this only takes time proportional to the number of types
+    /// and the number of weights borrowed mutably since the graph was last changed.
+    /// Types without any nodes or edges are left out and no order is guaranteed
+    pub fn type_histogram(&self) -> TypeHistogram<NK, EK, S> {
+        (
+            self.node_types
+                .counts(|node_key| self.nodes[*node_key].get_type()),
+            self.edge_types
+                .counts(|edge_key| self.edges[*edge_key].get_type()),
+        )
+    }
+
+    /// Create a new graph containing only the given nodes and the edges going between them
+    ///
+    /// The weights are cloned into the new graph and the outgoing edge order is preserved
+    pub fn subgraph<I>(&self, nodes: I) -> SchemaResult<Self, NK, EK, S>
+    where
+        I: IntoIterator<Item = NK>,
+        S: Clone,
+        H: Default,
+    {
+        let mut node_keys = IndexSet::new();
+        for node_id in nodes {
+            node_keys.insert(self.get_node_key(&node_id)?);
+        }
+
+        let mut new_graph = TypedGraph::with_default_hasher(self.schema.clone(), 0, 0);
+
+        for node_key in &node_keys {
+            let node = self.get_node_internal(*node_key)?;
+            new_graph.add_node(node.weight.clone())?;
+        }
+
+        // Add the edges in outgoing order
+        for (_, edge) in self.edges_in_outgoing_order(node_keys.iter().copied()) {
+            // Skip edges leaving the subgraph
+            if !node_keys.contains(&edge.target) {
+                continue;
+            }
+
+            let source_id = self.get_node_internal(edge.source)?.get_id();
+            let target_id = self.get_node_internal(edge.target)?.get_id();
+            new_graph.add_edge(source_id, target_id, edge.weight.clone())?;
+        }
+
+        Ok(new_graph)
+    }
+
+    /// Get the ids of all nodes which can be reached from the center using at most k edges
+    ///
+    /// Edges are followed in the given direction, or in both directions if None is given.
+    /// The center is always part of its own neighborhood.
+    /// The result can be passed to subgraph to extract the region around the center
+    pub fn neighborhood(
+        &self,
+        center: NK,
+        k: usize,
+        direction: Option<Direction>,
+    ) -> SchemaResult<HashSet<NK>, NK, EK, S> {
+        let center_key = self.get_node_key(&center)?;
+        let mut visited = HashSet::from([center_key]);
+        let mut front = vec![center_key];
+
+        for _ in 0..k {
+            let mut next_front = Vec::new();
+            for node_key in front {
+                let node = self.get_node_internal(node_key)?;
+                let outgoing = direction != Some(Direction::Incoming);
+                let incoming = direction != Some(Direction::Outgoing);
+                let edges = node
+                    .outgoing_edges
+                    .iter()
+                    .filter(|_| outgoing)
+                    .chain(node.incoming_edges.iter().filter(|_| incoming));
+
+                for edge_key in edges {
+                    let edge = self.get_edge_internal(*edge_key)?;
+                    let other = if edge.source == node_key {
+                        edge.target
+                    } else {
+                        edge.source
+                    };
+                    if visited.insert(other) {
+                        next_front.push(other);
+                    }
+                }
+            }
+
+            if next_front.is_empty() {
+                break;
+            }
+            front = next_front;
+        }
+
+        visited
+            .into_iter()
+            .map(|node_key| Ok(self.get_node_internal(node_key)?.get_id()))
+            .collect()
+    }
+
+    /// Count the nodes and edges in each connected component of the graph, ignoring the direction of edges
+    ///
+    /// Nodes without any edges are returned as components of their own.
+    /// The components are ordered by their representative, which is the first of their nodes returned by node_ids
+    pub fn component_stats(&self) -> Vec<ComponentStat<NK>> {
+        let mut components = UnionFind::new(self.nodes.keys());
+        for edge in self.edges.values() {
+            components.union(&edge.source, &edge.target);
+        }
+
+        let mut stats: Vec<ComponentStat<NK>> = Vec::new();
+        let mut stat_idx = HashMap::new();
+        for (node_key, node) in &self.nodes {
+            let Some(root) = components.root(&node_key) else {
+                continue;
+            };
+            let idx = *stat_idx.entry(root).or_insert_with(|| {
+                stats.push(ComponentStat {
+                    representative: node.get_id(),
+                    node_count: 0,
+                    edge_count: 0,
+                });
+                stats.len() - 1
+            });
+            stats[idx].node_count += 1;
+        }
+
+        for edge in self.edges.values() {
+            if let Some(idx) = components
+                .root(&edge.source)
+                .and_then(|root| stat_idx.get(&root))
+            {
+                stats[*idx].edge_count += 1;
+            }
+        }
+
+        stats
+    }
+
+    /// Create a dense adjacency matrix of the graph
+    ///
+    /// Returns the node ids along with a matrix where entry [i][j] counts the number of edges going from node i to node j.
+    /// The order of the node ids is the same as used to index the rows and columns of the matrix
+    pub fn adjacency_matrix(&self) -> (Vec<NK>, Vec<Vec<usize>>) {
+        let node_idx: HashMap<NodeKey, usize> = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(i, node_key)| (node_key, i))
+            .collect();
+
+        let node_ids = self.node_ids().collect();
+        let mut matrix = vec![vec![0; node_idx.len()]; node_idx.len()];
+
+        for edge in self.edges.values() {
+            if let (Some(source), Some(target)) =
+                (node_idx.get(&edge.source), node_idx.get(&edge.target))
+            {
+                matrix[*source][*target] += 1;
+
+                // Undirected edges goes both ways
+                if !S::DIRECTED && source != target {
+                    matrix[*target][*source] += 1;
+                }
+            }
+        }
+
+        (node_ids, matrix)
+    }
+
+    /// Check that the lookup tables and adjacency lists of the graph agree with each other and panic if they do not
+    ///
+    /// Every id in the lookup tables must point to a live node or edge with the same id,
+    /// every edge must be listed by both of its endpoints and no endpoint may list an edge which does not go through it.
+    /// The check visits every node and edge, so it is only performed in debug builds
+    pub fn debug_assert_consistent(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        assert_eq!(
+            self.node_lut.len(),
+            self.nodes.len(),
+            "node lookup table has the wrong size"
+        );
+        for (node_id, node_key) in &self.node_lut {
+            let node = self.nodes.get(*node_key);
+            assert!(
+                node.is_some_and(|node| &node.get_id() == node_id),
+                "node id {:?} does not point to a node with that id",
+                node_id
+            );
+        }
+
+        assert_eq!(
+            self.edge_lut.len(),
+            self.edges.len(),
+            "edge lookup table has the wrong size"
+        );
+        for (edge_id, edge_key) in &self.edge_lut {
+            let edge = self.edges.get(*edge_key);
+            assert!(
+                edge.is_some_and(|edge| &edge.get_id() == edge_id),
+                "edge id {:?} does not point to an edge with that id",
+                edge_id
+            );
+        }
+
+        for (edge_key, edge) in &self.edges {
+            let edge_id = edge.get_id();
+            let source = self.nodes.get(edge.source);
+            let target = self.nodes.get(edge.target);
+            assert!(
+                source.is_some_and(|source| source.outgoing_edges.contains(&edge_key)),
+                "edge {:?} is not an outgoing edge of its source",
+                edge_id
+            );
+            assert!(
+                target.is_some_and(|target| target.incoming_edges.contains(&edge_key)),
+                "edge {:?} is not an incoming edge of its target",
+                edge_id
+            );
+            if !S::DIRECTED {
+                // Undirected edges are registered on both endpoints in both directions
+                assert!(
+                    target.is_some_and(|target| target.outgoing_edges.contains(&edge_key))
+                        && source.is_some_and(|source| source.incoming_edges.contains(&edge_key)),
+                    "undirected edge {:?} is not registered in both directions",
+                    edge_id
+                );
+            }
+
+            if let Some(mirror_key) = edge.mirror {
+                assert!(
+                    self.edges
+                        .get(mirror_key)
+                        .is_some_and(|mirror| mirror.mirror == Some(edge_key)),
+                    "edge {:?} is not the mirror of its mirror",
+                    edge_id
+                );
+            }
+        }
+
+        for (node_key, node) in &self.nodes {
+            let node_id = node.get_id();
+            for edge_key in &node.outgoing_edges {
+                assert!(
+                    self.edges.get(*edge_key).is_some_and(|edge| {
+                        edge.source == node_key || !S::DIRECTED && edge.target == node_key
+                    }),
+                    "node {:?} has an outgoing edge which does not start at it",
+                    node_id
+                );
+            }
+            for edge_key in &node.incoming_edges {
+                assert!(
+                    self.edges.get(*edge_key).is_some_and(|edge| {
+                        edge.target == node_key || !S::DIRECTED && edge.source == node_key
+                    }),
+                    "node {:?} has an incoming edge which does not end at it",
+                    node_id
+                );
+            }
+        }
+
+        assert_eq!(
+            self.node_types.len(),
+            self.nodes.len(),
+            "node type index has the wrong size"
+        );
+        for (node_key, node) in &self.nodes {
+            assert!(
+                self.node_types.contains(&node.get_type(), &node_key),
+                "node {:?} is not indexed under its type",
+                node.get_id()
+            );
+        }
+
+        assert_eq!(
+            self.edge_types.len(),
+            self.edges.len(),
+            "edge type index has the wrong size"
+        );
+        for (edge_key, edge) in &self.edges {
+            assert!(
+                self.edge_types.contains(&edge.get_type(), &edge_key),
+                "edge {:?} is not indexed under its type",
+                edge.get_id()
+            );
+        }
+
+        // Quantities affected by a type changed through a mutable reference are only repaired when the graph is changed
+        let affected_nodes = self.retyped_endpoints();
+        for (node_key, node) in &self.nodes {
+            if affected_nodes.contains(&node_key) {
+                continue;
+            }
+
+            let (outgoing_quantities, incoming_quantities) = self
+                .recount_quantities(node_key)
+                .expect("edges of a node are in the graph");
+            assert!(
+                node.outgoing_quantities.same_counts(&outgoing_quantities)
+                    && node.incoming_quantities.same_counts(&incoming_quantities),
+                "node {:?} has quantities which does not match its edges",
+                node.get_id()
+            );
+        }
+    }
+
+    /// Check that the whole graph satisfies the constraints placed on it by the schema
+    ///
+    /// Every node and edge is checked again as if it was just added to the graph.
+    /// Unlike add_node and add_edge this also allows the schema to put a lower bound on the edges of a node,
+    /// since it is called after all the edges has been added.
+    /// All violations are collected instead of stopping at the first one
+    pub fn validate(&self) -> Result<(), Vec<SchemaError<NK, EK, S>>> {
+        let mut errors = Vec::new();
+
+        for node in self.nodes.values() {
+            let node_type = node.weight.get_type();
+            if let Err(e) = self.schema.allow_node(node_type.clone()) {
+                errors.push(TypedError::InvalidNodeType(
+                    node_type,
+                    e,
+                    self.schema.name(),
+                ));
+            }
+        }
+
+        // The quantities are counted once using the current types,
+        // since the cached ones are not repaired until the graph is changed again
+        let mut quantities: HashMap<NodeKey, (Quantities<_, _>, Quantities<_, _>)> = HashMap::new();
+        for edge in self.edges.values() {
+            let (Some(source_node), Some(target_node)) =
+                (self.nodes.get(edge.source), self.nodes.get(edge.target))
+            else {
+                continue;
+            };
+
+            let edge_type = edge.weight.get_type();
+            let endpoints = Self::quantity_endpoints(
+                edge.source,
+                source_node.get_type(),
+                edge.target,
+                target_node.get_type(),
+            );
+
+            for (node_key, direction, node_type) in endpoints {
+                let (outgoing, incoming) = quantities.entry(node_key).or_default();
+                match direction {
+                    Direction::Outgoing => outgoing.increment(edge_type.clone(), node_type),
+                    Direction::Incoming => incoming.increment(edge_type.clone(), node_type),
+                }
+            }
+        }
+
+        for edge in self.edges.values() {
+            let (Some(source_node), Some(target_node)) =
+                (self.nodes.get(edge.source), self.nodes.get(edge.target))
+            else {
+                errors.push(TypedError::InvalidInternalState);
+                continue;
+            };
+
+            let weight_type = edge.weight.get_type();
+
+            if edge.source == edge.target
+                && !self
+                    .schema
+                    .allow_self_loop(weight_type.clone(), source_node.get_type())
+            {
+                errors.push(TypedError::InvalidEdgeType(
+                    weight_type,
+                    source_node.get_type(),
+                    target_node.get_type(),
+                    DisAllowedEdge::SelfLoop,
+                    self.schema.name(),
+                ));
+                continue;
+            }
+
+            // The quantities already include the edge itself
+            let outgoing_quantity = quantities.get(&edge.source).map_or(0, |(outgoing, _)| {
+                outgoing.get(&weight_type, &target_node.get_type())
+            });
+            let incoming_quantity = quantities.get(&edge.target).map_or(0, |(_, incoming)| {
+                incoming.get(&weight_type, &source_node.get_type())
+            });
+
+            let allowed = self.schema.allow_edge_full(
+                outgoing_quantity,
+                incoming_quantity,
+                &edge.weight,
+                source_node,
+                target_node,
+            );
+            if let Err(e) = allowed {
+                errors.push(TypedError::InvalidEdgeType(
+                    weight_type,
+                    source_node.get_type(),
+                    target_node.get_type(),
+                    e,
+                    self.schema.name(),
+                ));
+            }
+        }
+
+        for node in self.nodes.values() {
+            if let Err(e) = self.check_lower_bound(node) {
+                errors.push(e);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check that the node has all the edges required by the schema
+    fn check_lower_bound(
+        &self,
+        node: &SchemaNodeMetadata<NK, EK, S>,
+    ) -> SchemaResult<(), NK, EK, S> {
+        let edge_types = |edges: &IndexSet<EdgeKey>| -> Vec<_> {
+            edges
+                .iter()
+                .filter_map(|edge_key| self.edges.get(*edge_key))
+                .map(|edge| edge.weight.get_type())
+                .collect()
+        };
+
+        let outgoing = edge_types(&node.outgoing_edges);
+        let incoming = edge_types(&node.incoming_edges);
+
+        self.schema
+            .validate_node(node.weight.get_type(), &outgoing, &incoming)
+            .map_err(|edge_ty| {
+                TypedError::InvalidLowerBound(
+                    node.weight.get_id(),
+                    node.weight.get_type(),
+                    edge_ty.to_string(),
+                )
+            })
+    }
+
+    /// Convert the graph from one schema to another using two mapping functions
+    /// The mapping functions are not allowed to change the id of any of the nodes only their data.
+    /// Use update_schema_remap to change the ids aswell
+    ///
+    ///
+    /// Returning None from a mapping function will delete the node from the resulting graph
+    ///
+    ///
+    /// When mapping to a schema with lower bounds on the number of edges allowed from a node.
+    /// Edges higher in the outgoing edge order will be removed
+    pub fn update_schema<NS, NF, EF>(
+        self,
+        schema: NS,
+        node_map: NF,
+        edge_map: EF,
+    ) -> SchemaResult<TypedGraph<NK, EK, NS, H>, NK, EK, NS>
+    where
+        NS: SchemaExt<NK, EK>,
+        NF: Fn(&S, &NS, S::N) -> Option<NS::N>,
+        EF: Fn(&S, &NS, S::E) -> Option<NS::E>,
+        H: Default,
+    {
+        self.update_schema_with_report(schema, node_map, edge_map)
+            .map(|(new_graph, _)| new_graph)
+    }
+
+    /// Same as update_schema but also returns a report of every node and edge that was dropped along the way
+    pub fn update_schema_with_report<NS, NF, EF>(
+        self,
+        schema: NS,
+        node_map: NF,
+        edge_map: EF,
+    ) -> SchemaResult<ReportedSchemaUpdate<NK, EK, S, NS, H>, NK, EK, NS>
+    where
+        NS: SchemaExt<NK, EK>,
+        NF: Fn(&S, &NS, S::N) -> Option<NS::N>,
+        EF: Fn(&S, &NS, S::E) -> Option<NS::E>,
+        H: Default,
+    {
+        self.update_schema_with_policy(schema, node_map, edge_map, DisAllowedPolicy::Reject, false)
+    }
+
+    /// Same as update_schema but the mapping functions are allowed to change the ids of the nodes and edges
+    ///
+    /// The source and target of every edge is updated to use the new node ids.
+    /// If two nodes or two edges ends up with the same id the update fails with an id collision
+    pub fn update_schema_remap<NS, NF, EF>(
+        self,
+        schema: NS,
+        node_map: NF,
+        edge_map: EF,
+    ) -> SchemaResult<ReportedSchemaUpdate<NK, EK, S, NS, H>, NK, EK, NS>
+    where
+        NS: SchemaExt<NK, EK>,
+        NF: Fn(&S, &NS, S::N) -> Option<NS::N>,
+        EF: Fn(&S, &NS, S::E) -> Option<NS::E>,
+        H: Default,
+    {
+        self.update_schema_with_policy(schema, node_map, edge_map, DisAllowedPolicy::Reject, true)
+    }
+
+    /// Move the graph to a schema using the same node and edge weights
+    ///
+    /// This makes it possible to tighten or loosen the rules of a schema without writing any mapping functions.
+    /// Nodes and edges which are not allowed by the new schema are either rejected or dropped depending on the policy
+    pub fn update_schema_rules<NS>(
+        self,
+        schema: NS,
+        policy: DisAllowedPolicy,
+    ) -> SchemaResult<ReportedSchemaUpdate<NK, EK, S, NS, H>, NK, EK, NS>
+    where
+        NS: SchemaExt<NK, EK, N = S::N, E = S::E>,
+        H: Default,
+    {
+        self.update_schema_with_policy(schema, |_, _, n| Some(n), |_, _, e| Some(e), policy, false)
+    }
+
+    /// Move the graph to a schema using the same node and edge weights without rebuilding it
+    ///
+    /// The existing nodes and edges are checked against the rules of the new schema, but they are moved across as is.
+    /// If the new schema changes the direction or symmetry of the edges, the graph has to be rebuilt
+    /// and this falls back to update_schema_rules
+    pub fn reinterpret_schema<NS>(
+        mut self,
+        schema: NS,
+    ) -> SchemaResult<TypedGraph<NK, EK, NS, H>, NK, EK, NS>
+    where
+        NS: SchemaExt<NK, EK, N = S::N, E = S::E>,
+        H: Default,
+    {
+        self.reindex();
+        let same_layout = S::DIRECTED == NS::DIRECTED
+            && self.edges.values().all(|edge| {
+                schema.symmetric_edge(edge.get_type()).is_some() == edge.mirror.is_some()
+            });
+        if !same_layout {
+            return self
+                .update_schema_rules(schema, DisAllowedPolicy::Reject)
+                .map(|(graph, _)| graph);
+        }
+
+        let graph = TypedGraph {
+            node_lut: self.node_lut,
+            edge_lut: self.edge_lut,
+            nodes: self.nodes,
+            edges: self.edges,
+            node_types: self.node_types,
+            edge_types: self.edge_types,
+            schema,
+        };
+
+        for node in graph.nodes.values() {
+            let node_type = node.get_type();
+            if let Err(e) = graph.schema.allow_node(node_type.clone()) {
+                return Err(TypedError::InvalidNodeType(
+                    node_type,
+                    e,
+                    graph.schema.name(),
+                ));
+            }
+        }
+
+        // The cached quantities are still valid since the types are the same
+        let edge_keys: Vec<_> = graph.edges.keys().collect();
+        graph.check_existing_edges(&edge_keys)?;
+
+        Ok(graph)
+    }
+
+    fn update_schema_with_policy<NS, NF, EF>(
+        mut self,
+        schema: NS,
+        node_map: NF,
+        edge_map: EF,
+        policy: DisAllowedPolicy,
+        allow_id_changes: bool,
+    ) -> SchemaResult<ReportedSchemaUpdate<NK, EK, S, NS, H>, NK, EK, NS>
+    where
+        NS: SchemaExt<NK, EK>,
+        NF: Fn(&S, &NS, S::N) -> Option<NS::N>,
+        EF: Fn(&S, &NS, S::E) -> Option<NS::E>,
+        H: Default,
+    {
+        // Create a list of all the edges that stores them in outgoing order
+        let edge_keys: Vec<_> = self
+            .edges_in_outgoing_order(self.nodes.keys())
+            .map(|(edge_key, _)| edge_key)
+            .collect();
+        let mut edges = Vec::new();
+        for e in edge_keys {
+            edges.push(
+                self.edges
+                    .remove(e)
+                    .ok_or_else(|| TypedError::InvalidInternalState)?,
+            );
+        }
+
+        let old_schema = self.schema;
+        let mut new_graph = TypedGraph::with_default_hasher(schema, 0, 0);
+
+        let mut node_id_lut = HashMap::new();
+        let mut report = MigrationReport::default();
+
+        for (nk, node) in self.nodes {
+            let old_id = node.get_id();
+            let old_type = node.weight.get_type();
+
+            // Remove the node if it is not part of the new schema
+            // Removed nodes are stored as None so edges to them can be removed aswell
+            let mut new_id = None;
+            if let Some(n) = node_map(&old_schema, new_graph.get_schema(), node.weight) {
+                let id = n.get_id();
+
+                // Check that the mapping function is not changing the id
+                if !allow_id_changes && id != old_id {
+                    return Err(TypedError::InconsistentNodeIds(old_id, id));
+                }
+
+                // Adding a node with an existing id would replace it
+                if new_graph.has_node(id.clone()) {
+                    return Err(TypedError::NodeIdCollision(id));
+                }
+
+                match new_graph.add_node(n) {
+                    Err(TypedError::InvalidNodeType(..)) if policy == DisAllowedPolicy::Drop => {
+                        report.dropped_nodes.push((old_id, old_type))
+                    }
+                    Err(e) => Err(e)?,
+                    Ok(_) => new_id = Some(id),
+                }
+            } else {
+                report.dropped_nodes.push((old_id, old_type));
+            }
+            node_id_lut.insert(nk, new_id);
+        }
+
+        // Update the edges in outgoing order
+        let mut edge_ids = HashSet::new();
+        for edge in edges {
+            let old_id = edge.weight.get_id();
+            let old_type = edge.weight.get_type();
+
+            // Remove the edge if it is not part of the new schema
+            if let Some(e) = edge_map(&old_schema, new_graph.get_schema(), edge.weight) {
+                let id = e.get_id();
+
+                // Check that the mapping function is not changing the id
+                if !allow_id_changes && id != old_id {
+                    return Err(TypedError::InconsistentEdgeIds(old_id, id));
+                }
+
+                // Adding an edge with an existing id would replace it
+                // Mirrored edges of symmetric edges are not counted since they are replaced by their original
+                if !edge_ids.insert(id.clone()) {
+                    return Err(TypedError::EdgeIdCollision(id));
+                }
+
+                let source_id = node_id_lut
+                    .get(&edge.source)
+                    .cloned()
+                    .ok_or_else(|| TypedError::InvalidInternalState)?;
+                let target_id = node_id_lut
+                    .get(&edge.target)
+                    .cloned()
+                    .ok_or_else(|| TypedError::InvalidInternalState)?;
+
+                // Don't include the edge if the source or target has been removed
+                if let (Some(source_id), Some(target_id)) = (source_id, target_id) {
+                    let e = new_graph.add_edge(source_id, target_id, e);
+
+                    match e {
+                        // Any excess edges are removed
+                        // Since edges are updated in outgoing order this will remove the last edges in the outgoing order
+                        Err(TypedError::InvalidEdgeType(_, _, _, DisAllowedEdge::ToManyOutgoing, _))
+                        | Err(TypedError::InvalidEdgeType(_, _, _, DisAllowedEdge::ToManyIncoming, _)) => {
+                            report.excess_edges.push((old_id, old_type))
+                        }
+                        Err(TypedError::InvalidEdgeType(..))
+                            if policy == DisAllowedPolicy::Drop =>
+                        {
+                            report.dropped_edges.push((old_id, old_type))
+                        }
+                        Err(e) => Err(e)?,
+                        Ok(_) => (),
+                    }
+                } else {
+                    report.dropped_edges.push((old_id, old_type));
+                }
+            } else {
+                report.dropped_edges.push((old_id, old_type));
+            }
+        }
+
+        Ok((new_graph, report))
+    }
+}
+
+// Migrations are defined on graphs using the default hasher
+impl<NK, EK, S> TypedGraph<NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    /// Apply a Migration to the current graph
+    pub fn migrate<NS>(
+        self,
+        new_schema: NS,
+        handler: &S::Handler,
+    ) -> GenericTypedResult<TypedGraph<NK, EK, NS>, NK, EK>
+    where
+        S: Migration<NK, EK, NS>,
+        NS: SchemaExt<NK, EK> + Clone,
+    {
+        Migration::migrate(self, handler, new_schema)
+    }
+
+    /// Apply a Migration to the current graph and report what was dropped during the migration
+    pub fn migrate_with_report<NS>(
+        self,
+        new_schema: NS,
+        handler: &S::Handler,
+    ) -> GenericTypedResult<ReportedMigration<NK, EK, NS>, NK, EK>
+    where
+        S: Migration<NK, EK, NS>,
+        NS: SchemaExt<NK, EK> + Clone,
+    {
+        Migration::migrate_with_report(self, handler, new_schema)
+    }
+
+    /// Run a Migration on a copy of the graph and report what would be dropped without changing the current graph
+    ///
+    /// Returns the error the migration would have failed with if it is not possible
+    pub fn can_migrate<NS>(
+        &self,
+        new_schema: &NS,
+        handler: &S::Handler,
+    ) -> GenericTypedResult<GenericMigrationReport<NK, EK>, NK, EK>
+    where
+        S: Migration<NK, EK, NS>,
+        NS: SchemaExt<NK, EK> + Clone,
+    {
+        let (_, report) = self
+            .clone()
+            .migrate_with_report(new_schema.clone(), handler)?;
+        Ok(report)
+    }
+
+    /// Migrate directly from one version to another
+    pub fn migrate_direct<NS>(self) -> GenericTypedResult<TypedGraph<NK, EK, NS>, NK, EK>
+    where
+        S: DirectMigration<NK, EK, NS>,
+        NS: SchemaExt<NK, EK>,
+    {
+        DirectMigration::migrate(self)
+    }
+}
+
+impl<NK, EK, S, H> Default for TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK> + Default,
+    H: Default,
+{
+    fn default() -> Self {
+        TypedGraph {
+            node_lut: Default::default(),
+            edge_lut: Default::default(),
+            nodes: HopSlotMap::with_key(),
+            edges: HopSlotMap::with_key(),
+            node_types: Default::default(),
+            edge_types: Default::default(),
+            schema: S::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+use serde::de::Error;
+#[cfg(feature = "serde")]
+use serde::de::*;
+#[cfg(feature = "serde")]
+use serde::ser::*;
+
+/// Error returned when serializing a graph whose edges refer to missing nodes or edges
+///
+/// The ids are not required to implement Display, so the message is taken from an error without any ids
+#[cfg(feature = "serde")]
+fn invalid_internal_state<E: serde::ser::Error>() -> E {
+    E::custom(TypedError::<String, String, String, String>::InvalidInternalState)
+}
+
+/// A reference to an edge with its source and target id
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct EdgeWriteDTO<'a, NK, E> {
+    weight: &'a E,
+    source: NK,
+    target: NK,
+}
+
+/// The outgoing edge order of a node
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct EdgeOrderDTO<NK, EK> {
+    node: NK,
+    edges: Vec<EK>,
+}
+
+// This is what #[derive(Serialize)] would generate.
+#[cfg(feature = "serde")]
+impl<NK, EK, N, E, S, H> Serialize for TypedGraph<NK, EK, S, H>
+where
+    NK: Key + Serialize,
+    EK: Key + Serialize,
+    N: Serialize + NodeExt<NK>,
+    E: Serialize + EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E> + Serialize,
+    H: BuildHasher,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        // Serialize the nodes as is
+        let nodes: Vec<_> = self.nodes.iter().collect();
+        self.serialize_nodes(nodes, false, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<NK, EK, N, E, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key + Serialize,
+    EK: Key + Serialize,
+    N: Serialize + NodeExt<NK>,
+    E: Serialize + EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E> + Serialize,
+    H: BuildHasher,
+{
+    /// Serialize the graph with the nodes in the given order
+    ///
+    /// The edges are serialized grouped by their source node following the same order.
+    /// If with_order is set the outgoing order of every node is also written explicitly
+    fn serialize_nodes<Ser>(
+        &self,
+        nodes: Vec<(NodeKey, &SchemaNodeMetadata<NK, EK, S>)>,
+        with_order: bool,
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let order: Vec<_> = if with_order {
+            nodes
+                .iter()
+                .filter(|(_, n)| !n.outgoing_edges.is_empty())
+                .map(|(_, n)| {
+                    let edges = n
+                        .outgoing_edges
+                        .iter()
+                        .map(|edge_key| self.edges.get(*edge_key).map(|e| e.get_id()))
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid_internal_state::<Ser::Error>)?;
+                    Ok(EdgeOrderDTO {
+                        node: n.get_id(),
+                        edges,
+                    })
+                })
+                .collect::<Result<_, _>>()?
+        } else {
+            Vec::new()
+        };
+        let (nodes, edges) = self
+            .write_dtos(nodes)
+            .map_err(|_| invalid_internal_state::<Ser::Error>())?;
+
+        // Serialize the graph as a struct with 3 fields, or 4 if the order is included
+        // Self describing formats will see this as a map while others will see it as a sequence
+        let mut s = serializer.serialize_struct("TypedGraph", if with_order { 4 } else { 3 })?;
+        s.serialize_field("schema", &self.schema)?;
+        s.serialize_field("nodes", &nodes)?;
+        s.serialize_field("edges", &edges)?;
+        if with_order {
+            s.serialize_field("order", &order)?;
+        }
+        s.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Get the weights of the given nodes and the edges going out of them in outgoing order
+    ///
+    /// Exporting the edges in outgoing order ensures that they will be imported in the correct order
+    #[allow(clippy::type_complexity)]
+    fn write_dtos<'a>(
+        &'a self,
+        nodes: Vec<(NodeKey, &'a SchemaNodeMetadata<NK, EK, S>)>,
+    ) -> SchemaResult<(Vec<&'a S::N>, Vec<EdgeWriteDTO<'a, NK, S::E>>), NK, EK, S> {
+        let (node_keys, nodes): (Vec<_>, Vec<_>) = nodes
+            .into_iter()
+            .map(|(node_key, n)| (node_key, &n.weight))
+            .unzip();
+
+        // Edges pointing to removed nodes can not be written
+        let endpoint = |node_key| {
+            self.nodes
+                .get(node_key)
+                .map(|n| n.get_id())
+                .ok_or(TypedError::InvalidInternalState)
+        };
+        let edges = self
+            .edges_in_outgoing_order(node_keys)
+            .map(|(_, e)| {
+                Ok(EdgeWriteDTO {
+                    weight: &e.weight,
+                    source: endpoint(e.source)?,
+                    target: endpoint(e.target)?,
+                })
+            })
+            .collect::<SchemaResult<_, NK, EK, S>>()?;
+
+        Ok((nodes, edges))
+    }
+
+    /// Serialize only the nodes and edges of the graph without the schema
+    ///
+    /// The output can be deserialized using deserialize_data together with the schema
+    pub fn serialize_data(&self) -> SerializeData<'_, NK, EK, S, H> {
+        SerializeData { g: self }
+    }
+}
+
+// Reading a graph creates it with the default hasher, so the type of the graph can be inferred
+#[cfg(feature = "serde")]
+impl<NK, EK, S> TypedGraph<NK, EK, S>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    /// Deserialize the nodes and edges written by serialize_data into a graph using the given schema
+    ///
+    /// The data is checked against the schema the same way as when deserializing a normal TypedGraph
+    pub fn deserialize_data<'de, D>(schema: S, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        NK: Display + Deserialize<'de>,
+        EK: Display + Deserialize<'de>,
+        S::N: Deserialize<'de>,
+        S::E: Deserialize<'de>,
+    {
+        deserializer.deserialize_struct(
+            "GraphData",
+            GRAPH_DATA_FIELDS,
+            GraphDataVisitor {
+                schema,
+                nk: PhantomData,
+                ek: PhantomData,
+                h: PhantomData,
+            },
+        )
+    }
+
+    /// Deserialize a graph written by serialize_compact
+    ///
+    /// The node indices of the edges are turned back into node ids before the edges are added
+    pub fn deserialize_compact<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+        NK: Display + Deserialize<'de>,
+        EK: Display + Deserialize<'de>,
+        S: Deserialize<'de>,
+        S::N: Deserialize<'de>,
+        S::E: Deserialize<'de>,
+    {
+        deserializer.deserialize_struct(
+            "CompactTypedGraph",
+            TYPED_GRAPH_FIELDS,
+            CompactGraphVisitor {
+                nk: PhantomData,
+                ek: PhantomData,
+                s: PhantomData,
+            },
+        )
+    }
+}
+
+/// Serializes only the nodes and edges of a graph
+///
+/// Created using TypedGraph::serialize_data
+#[cfg(feature = "serde")]
+pub struct SerializeData<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    g: &'a TypedGraph<NK, EK, S, H>,
+}
+
+#[cfg(feature = "serde")]
+impl<'a, NK, EK, N, E, S, H> Serialize for SerializeData<'a, NK, EK, S, H>
+where
+    NK: Key + Serialize,
+    EK: Key + Serialize,
+    N: Serialize + NodeExt<NK>,
+    E: Serialize + EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E>,
+    H: BuildHasher,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let (nodes, edges) = self
+            .g
+            .write_dtos(self.g.nodes.iter().collect())
+            .map_err(|_| invalid_internal_state::<Ser::Error>())?;
+
+        let mut s = serializer.serialize_struct("GraphData", 2)?;
+        s.serialize_field("nodes", &nodes)?;
+        s.serialize_field("edges", &edges)?;
+        s.end()
+    }
+}
+
+/// Serializes a graph with the nodes sorted by their id
+///
+/// This produces the same output for graphs with the same content regardless of the order
+/// the nodes and edges were inserted and removed in.
+///
+/// The output can be deserialized as a normal TypedGraph
+#[cfg(feature = "serde")]
+pub struct SerializeSorted<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    g: &'a TypedGraph<NK, EK, S, H>,
+}
+
+#[cfg(feature = "serde")]
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key + Ord,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Serialize the graph with nodes sorted by id
+    ///
+    /// Edges are serialized in the order of their source node and then by the outgoing edge order
+    pub fn serialize_sorted(&self) -> SerializeSorted<'_, NK, EK, S, H> {
+        SerializeSorted { g: self }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a, NK, EK, N, E, S, H> Serialize for SerializeSorted<'a, NK, EK, S, H>
+where
+    NK: Key + Ord + Serialize,
+    EK: Key + Serialize,
+    N: Serialize + NodeExt<NK>,
+    E: Serialize + EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E> + Serialize,
+    H: BuildHasher,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let mut nodes: Vec<_> = self.g.nodes.iter().collect();
+        nodes.sort_by_key(|(_, n)| n.get_id());
+        self.g.serialize_nodes(nodes, false, serializer)
+    }
+}
+
+/// Serializes a graph together with the explicit outgoing edge order of every node
+///
+/// Normally the order is only given by the sequence of the edges,
+/// which is lost if the edges are reordered before the graph is deserialized.
+/// The explicit order is used instead when present.
+///
+/// The order is written as a fourth field which is only read by self describing formats such as JSON
+#[cfg(feature = "serde")]
+pub struct SerializeWithOrder<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    g: &'a TypedGraph<NK, EK, S, H>,
+}
+
+#[cfg(feature = "serde")]
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Serialize the graph with an explicit outgoing edge order for every node
+    ///
+    /// The output can be deserialized as a normal TypedGraph
+    pub fn serialize_with_order(&self) -> SerializeWithOrder<'_, NK, EK, S, H> {
+        SerializeWithOrder { g: self }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a, NK, EK, N, E, S, H> Serialize for SerializeWithOrder<'a, NK, EK, S, H>
+where
+    NK: Key + Serialize,
+    EK: Key + Serialize,
+    N: Serialize + NodeExt<NK>,
+    E: Serialize + EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E> + Serialize,
+    H: BuildHasher,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let nodes: Vec<_> = self.g.nodes.iter().collect();
+        self.g.serialize_nodes(nodes, true, serializer)
+    }
+}
+
+/// Serializes a graph where the edges refer to their endpoints by the index of the node instead of its id
+///
+/// The edges are written as (source index, target index, weight) triples,
+/// which is a lot smaller than repeating the ids when they are large such as UUIDs.
+///
+/// The output has to be deserialized using deserialize_compact
+#[cfg(feature = "serde")]
+pub struct SerializeCompact<'a, NK, EK, S, H = RandomState>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+{
+    g: &'a TypedGraph<NK, EK, S, H>,
+}
+
+#[cfg(feature = "serde")]
+impl<NK, EK, S, H> TypedGraph<NK, EK, S, H>
+where
+    NK: Key,
+    EK: Key,
+    S: SchemaExt<NK, EK>,
+    H: BuildHasher,
+{
+    /// Serialize the graph using node indices for the endpoints of the edges
+    ///
+    /// The edges are written grouped by their source node in outgoing order, so the order is kept
+    pub fn serialize_compact(&self) -> SerializeCompact<'_, NK, EK, S, H> {
+        SerializeCompact { g: self }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a, NK, EK, N, E, S, H> Serialize for SerializeCompact<'a, NK, EK, S, H>
+where
+    NK: Key + Serialize,
+    EK: Key + Serialize,
+    N: Serialize + NodeExt<NK>,
+    E: Serialize + EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E> + Serialize,
+    H: BuildHasher,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        let g = self.g;
+        let indices: HashMap<NodeKey, usize> = g
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(idx, node_key)| (node_key, idx))
+            .collect();
+
+        let nodes: Vec<_> = g.nodes.values().map(|n| &n.weight).collect();
+        let edges: Vec<_> = g
+            .edges_in_outgoing_order(g.nodes.keys())
+            .map(|(_, e)| CompactEdgeWriteDTO(indices[&e.source], indices[&e.target], &e.weight))
+            .collect();
+
+        let mut s = serializer.serialize_struct("CompactTypedGraph", 3)?;
+        s.serialize_field("schema", &g.schema)?;
+        s.serialize_field("nodes", &nodes)?;
+        s.serialize_field("edges", &edges)?;
+        s.end()
+    }
+}
+
+/// An edge referring to its source and target by the index of the nodes
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct CompactEdgeWriteDTO<'a, E>(usize, usize, &'a E);
+
+/// An owned edge referring to its source and target by the index of the nodes
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct CompactEdgeReadDTO<E>(usize, usize, E);
+
+/// An owned reference to aedge with its source and target id
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct EdgeReadDTO<NK, E> {
+    weight: E,
+    source: NK,
+    target: NK,
+}
+
+/// Name of the fields in the serialized TypedGraph
+///
+/// Formats which are not self describing expect the fields in this order
+#[cfg(feature = "serde")]
+const TYPED_GRAPH_FIELDS: &[&str] = &["schema", "nodes", "edges"];
+
+/// The top level fields of a serialized graph
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum GraphField {
+    Schema,
+    Nodes,
+    Edges,
+    Order,
+}
+
+/// A deserialize visitor that can generate a TypedGraph
+///
+/// this contains all the generics used by the TypeGraph since they would otherwise be seen as not used
+#[cfg(feature = "serde")]
+#[derive(Default)]
+struct TypedGraphVisitor<NK, EK, N, E, S, H>
+where
+    NK: Key,
+    EK: Key,
+    N: NodeExt<NK>,
+    E: EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E>,
+{
+    nk: PhantomData<NK>,
+    ek: PhantomData<EK>,
+    n: PhantomData<N>,
+    e: PhantomData<E>,
+    s: PhantomData<S>,
+    h: PhantomData<H>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, NK, EK, N, E, S, H> Visitor<'de> for TypedGraphVisitor<NK, EK, N, E, S, H>
+where
+    NK: Key + Display + Deserialize<'de>,
+    EK: Key + Display + Deserialize<'de>,
+    N: NodeExt<NK> + Deserialize<'de>,
+    E: EdgeExt<EK> + Deserialize<'de>,
+    S: SchemaExt<NK, EK, N = N, E = E> + Deserialize<'de>,
+    H: BuildHasher + Default,
+{
+    /// Produce a typed graph
+    type Value = TypedGraph<NK, EK, S, H>;
+
+    /// Message in case it all goes wrong
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("TypedGraph")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        // The fields may come in any order, so they are all read before any nodes or edges are added
+        let mut schema: Option<S> = None;
+        let mut nodes: Option<Vec<N>> = None;
+        let mut edges: Option<Vec<EdgeReadDTO<NK, E>>> = None;
+        let mut order: Option<Vec<EdgeOrderDTO<NK, EK>>> = None;
+
+        while let Some(field) = access.next_key()? {
+            match field {
+                GraphField::Schema if schema.is_some() => {
+                    return Err(M::Error::duplicate_field("schema"))
+                }
+                GraphField::Nodes if nodes.is_some() => {
+                    return Err(M::Error::duplicate_field("nodes"))
+                }
+                GraphField::Edges if edges.is_some() => {
+                    return Err(M::Error::duplicate_field("edges"))
+                }
+                GraphField::Order if order.is_some() => {
+                    return Err(M::Error::duplicate_field("order"))
+                }
+                GraphField::Schema => schema = Some(access.next_value()?),
+                GraphField::Nodes => nodes = Some(access.next_value()?),
+                GraphField::Edges => edges = Some(access.next_value()?),
+                GraphField::Order => order = Some(access.next_value()?),
+            }
+        }
+
+        let schema = schema.ok_or_else(|| M::Error::missing_field("schema"))?;
+        let nodes = nodes.ok_or_else(|| M::Error::missing_field("nodes"))?;
+        let edges = edges.ok_or_else(|| M::Error::missing_field("edges"))?;
+
+        // Without an explicit order the edges are kept in the order they were read
+        build_typed_graph(schema, nodes, edges, order.unwrap_or_default())
+    }
+
+    /// Formats which are not self describing stores the fields as a sequence in the same order as they are serialized
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let schema: S = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let nodes: Vec<N> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        let edges: Vec<EdgeReadDTO<NK, E>> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(2, &self))?;
+
+        build_typed_graph(schema, nodes, edges, Vec::new())
+    }
+}
+
+/// Name of the fields in the serialized graph data
+///
+/// Formats which are not self describing expect the fields in this order
+#[cfg(feature = "serde")]
+const GRAPH_DATA_FIELDS: &[&str] = &["nodes", "edges"];
+
+/// A deserialize visitor that reads the nodes and edges of a graph using an existing schema
+#[cfg(feature = "serde")]
+struct GraphDataVisitor<NK, EK, S, H> {
+    schema: S,
+    nk: PhantomData<NK>,
+    ek: PhantomData<EK>,
+    h: PhantomData<H>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, NK, EK, S, H> Visitor<'de> for GraphDataVisitor<NK, EK, S, H>
+where
+    NK: Key + Display + Deserialize<'de>,
+    EK: Key + Display + Deserialize<'de>,
+    S: SchemaExt<NK, EK>,
+    S::N: Deserialize<'de>,
+    S::E: Deserialize<'de>,
+    H: BuildHasher + Default,
+{
+    type Value = TypedGraph<NK, EK, S, H>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("GraphData")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut nodes: Option<Vec<S::N>> = None;
+        let mut edges: Option<Vec<EdgeReadDTO<NK, S::E>>> = None;
+
+        while let Some(field) = access.next_key()? {
+            match field {
+                GraphField::Schema => {
+                    return Err(M::Error::unknown_field("schema", GRAPH_DATA_FIELDS))
+                }
+                GraphField::Order => {
+                    return Err(M::Error::unknown_field("order", GRAPH_DATA_FIELDS))
+                }
+                GraphField::Nodes if nodes.is_some() => {
+                    return Err(M::Error::duplicate_field("nodes"))
+                }
+                GraphField::Edges if edges.is_some() => {
+                    return Err(M::Error::duplicate_field("edges"))
+                }
+                GraphField::Nodes => nodes = Some(access.next_value()?),
+                GraphField::Edges => edges = Some(access.next_value()?),
+            }
+        }
+
+        let nodes = nodes.ok_or_else(|| M::Error::missing_field("nodes"))?;
+        let edges = edges.ok_or_else(|| M::Error::missing_field("edges"))?;
+
+        build_typed_graph(self.schema, nodes, edges, Vec::new())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let nodes: Vec<S::N> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &"GraphData"))?;
+        let edges: Vec<EdgeReadDTO<NK, S::E>> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(1, &"GraphData"))?;
+
+        build_typed_graph(self.schema, nodes, edges, Vec::new())
+    }
+}
+
+/// A deserialize visitor that reads a graph written by serialize_compact
+#[cfg(feature = "serde")]
+struct CompactGraphVisitor<NK, EK, S> {
+    nk: PhantomData<NK>,
+    ek: PhantomData<EK>,
+    s: PhantomData<S>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, NK, EK, S> Visitor<'de> for CompactGraphVisitor<NK, EK, S>
+where
+    NK: Key + Display + Deserialize<'de>,
+    EK: Key + Display + Deserialize<'de>,
+    S: SchemaExt<NK, EK> + Deserialize<'de>,
+    S::N: Deserialize<'de>,
+    S::E: Deserialize<'de>,
+{
+    type Value = TypedGraph<NK, EK, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("CompactTypedGraph")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut schema: Option<S> = None;
+        let mut nodes: Option<Vec<S::N>> = None;
+        let mut edges: Option<Vec<CompactEdgeReadDTO<S::E>>> = None;
+
+        while let Some(field) = access.next_key()? {
+            match field {
+                GraphField::Order => {
+                    return Err(M::Error::unknown_field("order", TYPED_GRAPH_FIELDS))
+                }
+                GraphField::Schema if schema.is_some() => {
+                    return Err(M::Error::duplicate_field("schema"))
+                }
+                GraphField::Nodes if nodes.is_some() => {
+                    return Err(M::Error::duplicate_field("nodes"))
+                }
+                GraphField::Edges if edges.is_some() => {
+                    return Err(M::Error::duplicate_field("edges"))
+                }
+                GraphField::Schema => schema = Some(access.next_value()?),
+                GraphField::Nodes => nodes = Some(access.next_value()?),
+                GraphField::Edges => edges = Some(access.next_value()?),
+            }
+        }
+
+        let schema = schema.ok_or_else(|| M::Error::missing_field("schema"))?;
+        let nodes = nodes.ok_or_else(|| M::Error::missing_field("nodes"))?;
+        let edges = edges.ok_or_else(|| M::Error::missing_field("edges"))?;
+
+        build_compact_graph(schema, nodes, edges)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let schema: S = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let nodes: Vec<S::N> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        let edges: Vec<CompactEdgeReadDTO<S::E>> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(2, &self))?;
+
+        build_compact_graph(schema, nodes, edges)
+    }
+}
+
+/// Replace the node indices of the edges with the ids of the nodes and insert everything into a new graph
+#[cfg(feature = "serde")]
+fn build_compact_graph<NK, EK, S, Err>(
+    schema: S,
+    nodes: Vec<S::N>,
+    edges: Vec<CompactEdgeReadDTO<S::E>>,
+) -> Result<TypedGraph<NK, EK, S>, Err>
+where
+    NK: Key + Display,
+    EK: Key + Display,
+    S: SchemaExt<NK, EK>,
+    Err: Error,
+{
+    let node_id = |idx: usize| {
+        nodes.get(idx).map(|n| n.get_id()).ok_or_else(|| {
+            Err::custom(format!(
+                "edge refers to node index {} but there are only {} nodes",
+                idx,
+                nodes.len()
+            ))
+        })
+    };
+
+    let edges = edges
+        .into_iter()
+        .map(|CompactEdgeReadDTO(source, target, weight)| {
+            Ok(EdgeReadDTO {
+                weight,
+                source: node_id(source)?,
+                target: node_id(target)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Err>>()?;
+
+    build_typed_graph(schema, nodes, edges, Vec::new())
+}
+
+/// Insert the deserialized nodes and edges into a new graph
+///
+/// This ensures that the schema is upheld by the deserialized data.
+/// The outgoing edges of the nodes in the order list are then rearranged to match it
+#[cfg(feature = "serde")]
+fn build_typed_graph<NK, EK, N, E, S, H, Err>(
+    schema: S,
+    nodes: Vec<N>,
+    edges: Vec<EdgeReadDTO<NK, E>>,
+    order: Vec<EdgeOrderDTO<NK, EK>>,
+) -> Result<TypedGraph<NK, EK, S, H>, Err>
+where
+    NK: Key + Display,
+    EK: Key + Display,
+    N: NodeExt<NK>,
+    E: EdgeExt<EK>,
+    S: SchemaExt<NK, EK, N = N, E = E>,
+    H: BuildHasher + Default,
+    Err: Error,
+{
+    let mut g = TypedGraph::with_default_hasher(schema, 0, 0);
+
+    // Check for id collisions and propper node types
+    for n in nodes {
+        g.add_node(n).map_err(|e| Err::custom(e))?;
+    }
+
+    // Check for id collisions and propper edge types
+    for e in edges {
+        g.add_edge(e.source, e.target, e.weight)
+            .map_err(|e| Err::custom(e))?;
+    }
+
+    for o in order {
+        g.set_outgoing_order(o.node, &o.edges)
+            .map_err(|e| Err::custom(e))?;
+    }
+
+    Ok(g)
+}
+
+/// Use the visitor to deserialize the TypedGraph
+#[cfg(feature = "serde")]
+impl<'de, NK, EK, N, E, S, H> Deserialize<'de> for TypedGraph<NK, EK, S, H>
+where
+    NK: Key + Display + Deserialize<'de>,
+    EK: Key + Display + Deserialize<'de>,
+    N: NodeExt<NK> + Deserialize<'de>,
+    E: EdgeExt<EK> + Deserialize<'de>,
+    S: SchemaExt<NK, EK, N = N, E = E> + Deserialize<'de>,
+    H: BuildHasher + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            "TypedGraph",
+            TYPED_GRAPH_FIELDS,
+            TypedGraphVisitor {
+                nk: PhantomData,
+                ek: PhantomData,
+                n: PhantomData,
+                e: PhantomData,
+                s: PhantomData,
+                h: PhantomData,
+            },
+        )
+    }
+}
+
+#[test]
+fn adjacency_matrix_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    let a = g.add_node((0, 0))?;
+    let b = g.add_node((1, 0))?;
+    let c = g.add_node((2, 0))?;
+
+    g.add_edge(a, b, (0, 0))?;
+    g.add_edge(a, b, (1, 1))?;
+    g.add_edge(b, c, (2, 0))?;
+    g.add_edge(c, c, (3, 0))?;
+
+    let (ids, matrix) = g.adjacency_matrix();
+    let idx = |id| ids.iter().position(|i| *i == id).unwrap();
+
+    assert_eq!(ids.len(), 3);
+    assert_eq!(matrix[idx(a)][idx(b)], 2);
+    assert_eq!(matrix[idx(b)][idx(a)], 0);
+    assert_eq!(matrix[idx(b)][idx(c)], 1);
+    assert_eq!(matrix[idx(c)][idx(c)], 1);
+    assert_eq!(matrix.iter().flatten().sum::<usize>(), g.edge_count());
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graph_compose_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use fake::Dummy;
+
+    let config = CompleteGraph {
+        nodes: 100,
+        node_types: 5,
+        edge_types: 5,
+    };
+
+    let g = TestGraph::dummy(&config);
+    let s = serde_json::to_string_pretty(&g)?;
+    let ng: TestGraph = serde_json::from_str(&s)?;
+    g.assert_eq(&ng)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graph_compose_bincode_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use fake::Dummy;
+
+    let config = CompleteGraph {
+        nodes: 100,
+        node_types: 5,
+        edge_types: 5,
+    };
+
+    let g = TestGraph::dummy(&config);
+    let bytes = bincode::serialize(&g).unwrap();
+    let ng: TestGraph = bincode::deserialize(&bytes).unwrap();
+    g.assert_eq(&ng)?;
+
+    let bytes = postcard::to_allocvec(&g).unwrap();
+    let ng: TestGraph = postcard::from_bytes(&bytes).unwrap();
+    g.assert_eq(&ng)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graph_serialize_data_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use bincode::Options;
+    use fake::Dummy;
+
+    let config = CompleteGraph {
+        nodes: 20,
+        node_types: 3,
+        edge_types: 3,
+    };
+
+    let g = TestGraph::dummy(&config);
+    let s = serde_json::to_string(&g.serialize_data())?;
+    assert!(!s.contains("schema"));
+
+    let mut deserializer = serde_json::Deserializer::from_str(&s);
+    let ng = TestGraph::deserialize_data(g.get_schema().clone(), &mut deserializer)?;
+    g.assert_eq(&ng)?;
+
+    let bytes = bincode::serialize(&g.serialize_data()).unwrap();
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes();
+    let mut deserializer = bincode::Deserializer::from_slice(&bytes, options);
+    let ng = TestGraph::deserialize_data(g.get_schema().clone(), &mut deserializer).unwrap();
+    g.assert_eq(&ng)?;
+
+    // The data is checked against the given schema
+    let schema = TestSchema::new().node_whitelist(Some(vec![]));
+    let mut deserializer = serde_json::Deserializer::from_str(&s);
+    assert!(TestGraph::deserialize_data(schema, &mut deserializer).is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graph_serialize_sorted_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g1 = TestGraph::default();
+    let mut g2 = TestGraph::default();
+
+    // Build the same graph using two different insertion histories
+    for i in 0..10 {
+        g1.add_node((i, i % 3))?;
+    }
+    for i in (0..10).rev() {
+        g2.add_node((i, i % 3))?;
+    }
+    g2.add_node((10, 0))?;
+    g2.remove_node(10)?;
+
+    for i in 0..9 {
+        g1.add_edge(i, i + 1, (i, 0))?;
+    }
+    for i in (0..9).rev() {
+        g2.add_edge(i, i + 1, (i, 0))?;
+    }
+
+    // The order of outgoing edges is kept even though the nodes are sorted
+    for g in [&mut g1, &mut g2] {
+        g.add_edge(5, 2, (10, 1))?;
+        g.add_edge(5, 3, (11, 1))?;
+        g.move_edge_order(11, 5, InsertPosition::Before)?;
+    }
+
+    let s1 = serde_json::to_string(&g1.serialize_sorted())?;
+    let s2 = serde_json::to_string(&g2.serialize_sorted())?;
+    assert_eq!(s1, s2);
+
+    let ng: TestGraph = serde_json::from_str(&s1)?;
+    g1.assert_eq(&ng)?;
+
+    Ok(())
+}
+
+#[test]
+fn graph_quantity_test_outgoing() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    const NODE_TYPE: usize = 0;
+    const NODE_TYPE1: usize = 1;
+    const NODE_TYPE2: usize = 2;
+    const EDGE_TYPE1: usize = 0;
+    const EDGE_TYPE2: usize = 2;
+
+    let s = TestSchema::new()
+        .endpoint_outgoing_max_quantity(Some(
+            vec![
+                // Set a max capacity of 2 edges of type EDGE_TYPE1 from NODE_TYPE to NODE_TYPE1
+                ((NODE_TYPE, EDGE_TYPE1), 2),
+            ]
+            .into_iter()
+            .collect(),
+        ))
+        .endpoint_incoming_max_quantity(Some(
+            vec![
+                // Set a max capacity of 2 edges of type EDGE_TYPE1 from NODE_TYPE to NODE_TYPE1
+                ((NODE_TYPE2, EDGE_TYPE1), 3),
+            ]
+            .into_iter()
+            .collect(),
+        ));
+    let mut g = TestGraph::new(s);
+
+    let source_id = g.add_node((0, NODE_TYPE))?;
+    let target_id = g.add_node((1, NODE_TYPE1))?;
+
+    // First outgoing edge
+    g.add_edge(source_id, target_id, (0, EDGE_TYPE1))?;
+    // Second outgoing edge
+    g.add_edge(source_id, target_id, (1, EDGE_TYPE1))?;
+    // Third outgoing edge
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Added third edge");
+
+    // Try to add third edge by updating an existing edge of another type
+    g.add_edge(source_id, target_id, (2, EDGE_TYPE2))?;
+
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Updating edge");
+    g.remove_edge(2)?;
+
+    // Try to add third edge by updating an existing edge of another type going in the opposit direction
+    g.add_edge(target_id, source_id, (2, EDGE_TYPE2))?;
+
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Updating edge reversed");
+    g.remove_edge(2)?;
+
+    // Try to add third edge by updating an existing edge of another type coming from different nodes
+    let source_id2 = g.add_node((2, NODE_TYPE2))?;
+    let target_id2 = g.add_node((3, NODE_TYPE2))?;
+    g.add_edge(target_id2, source_id2, (2, EDGE_TYPE2))?;
+
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Updating completly different edge");
+    g.remove_edge(2)?;
+
+    // Make sure the limit only works in one direction
+    // First incoming edge
+    g.add_edge(target_id, source_id, (10, EDGE_TYPE1))?;
+    // Second incoming edge
+    g.add_edge(target_id, source_id, (11, EDGE_TYPE1))?;
+    // Third incoming edge
+    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
+    // Fourth incoming edge
+    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
+
+    Ok(())
+}
+
+#[test]
+fn graph_quantity_test_incoming() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    const NODE_TYPE: usize = 0;
+    const NODE_TYPE1: usize = 1;
+    const NODE_TYPE2: usize = 2;
+    const EDGE_TYPE1: usize = 0;
+    const EDGE_TYPE2: usize = 2;
+
+    let s = TestSchema::new()
+        .endpoint_incoming_max_quantity(Some(
+            vec![
+                // Set a max capacity of 2 edges of type EDGE_TYPE1 from NODE_TYPE to NODE_TYPE1
+                ((NODE_TYPE, EDGE_TYPE1), 2),
+            ]
+            .into_iter()
+            .collect(),
+        ));
+    let mut g = TestGraph::new(s);
+
+    let source_id = g.add_node((0, NODE_TYPE1))?;
+    let target_id = g.add_node((1, NODE_TYPE))?;
+
+    // First incoming edge
+    g.add_edge(source_id, target_id, (0, EDGE_TYPE1))?;
+    // Second incoming edge
+    g.add_edge(source_id, target_id, (1, EDGE_TYPE1))?;
+    // Third incoming edge
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Added third edge");
+
+    // Try to add third edge by updating an existing edge of another type
+    g.add_edge(source_id, target_id, (2, EDGE_TYPE2))?;
+
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Updating edge");
+    g.remove_edge(2)?;
+
+    // Try to add third edge by updating an existing edge of another type going in the opposit direction
+    g.add_edge(target_id, source_id, (2, EDGE_TYPE2))?;
+
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Updating edge reversed");
+    g.remove_edge(2)?;
+
+    // Try to add third edge by updating an existing edge of another type coming from different nodes
+    let source_id2 = g.add_node((2, NODE_TYPE2))?;
+    let target_id2 = g.add_node((3, NODE_TYPE2))?;
+    g.add_edge(target_id2, source_id2, (2, EDGE_TYPE2))?;
+
+    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
+    assert!(e.is_err(), "Updating completly different edge");
+    g.remove_edge(2)?;
+
+    // Make sure the limit only works in one direction
+    // First incoming edge
+    g.add_edge(target_id, source_id, (10, EDGE_TYPE1))?;
+    // Second incoming edge
+    g.add_edge(target_id, source_id, (11, EDGE_TYPE1))?;
+    // Third incoming edge
+    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
+    // Fourth incoming edge
+    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
+
+    Ok(())
+}
+
+#[test]
+fn edge_order() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let s = TestSchema::new();
+    let mut g = TestGraph::new(s);
+
+    let a = g.add_node((0, 0))?;
+    let b = g.add_node((1, 0))?;
+
+    for i in 0..5 {
+        g.add_edge(a, b, (i, i))?;
+    }
+
+    let ids: Vec<usize> = g.get_outgoing(a)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+
+    // Swap before when after
+    let mut ng = g.clone();
+    ng.move_edge_order(3, 1, InsertPosition::Before)?;
+    let ids: Vec<usize> = ng.get_outgoing(0)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 3, 1, 2, 4]);
+
+    // Swap after when after
+    let mut ng = g.clone();
+    ng.move_edge_order(3, 1, InsertPosition::After)?;
+    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 1, 3, 2, 4]);
+
+    // Swap before start
+    let mut ng = g.clone();
+    ng.move_edge_order(3, 0, InsertPosition::Before)?;
+    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[3, 0, 1, 2, 4]);
+
+    // Swap after end
+    let mut ng = g.clone();
+    ng.move_edge_order(3, 4, InsertPosition::After)?;
+    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 1, 2, 4, 3]);
+
+    // Swap before self is identity
+    let mut ng = g.clone();
+    ng.move_edge_order(2, 2, InsertPosition::Before)?;
+    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+
+    // Swap after self is identity
+    let mut ng = g.clone();
+    ng.move_edge_order(2, 2, InsertPosition::After)?;
+    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+
+    // Swap before when before
+    let mut ng = g.clone();
+    ng.move_edge_order(1, 2, InsertPosition::Before)?;
+    let ids: Vec<usize> = ng.get_outgoing(0)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+
+    // Swap after when before
+    let mut ng = g.clone();
+    ng.move_edge_order(1, 2, InsertPosition::After)?;
+    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
+    assert_eq!(ids, &[0, 2, 1, 3, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn subgraph_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let s = TestSchema::new();
+    let mut g = TestGraph::new(s);
+
+    let a = g.add_node((0, 0))?;
+    let b = g.add_node((1, 1))?;
+    let c = g.add_node((2, 0))?;
+
+    g.add_edge(a, b, (0, 0))?;
+    g.add_edge(a, c, (1, 0))?;
+    g.add_edge(a, b, (2, 1))?;
+    g.add_edge(b, c, (3, 0))?;
+
+    let sg = g.subgraph([a, b])?;
+    assert_eq!(sg.node_count(), 2);
+    assert_eq!(sg.edge_count(), 2);
+    assert!(!sg.has_node(c));
+    assert!(!sg.has_edge(1));
+
+    // The outgoing order of the remaining edges is preserved
+    let ids: Vec<usize> = sg.get_outgoing(a)?.map(|e| e.get_id()).collect();
+    assert_eq!(ids, &[0, 2]);
+
+    // Unknown nodes are reported
+    let e = g.subgraph([a, 10]);
+    assert!(matches!(e, Err(TypedError::MissingNode(10))));
+
+    Ok(())
+}
+
+#[test]
+fn graph_validate_min_quantity() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::HashMap;
+
+    // Every node of type 0 must have at least one outgoing edge of type 1
+    // and every node of type 1 must have at least two incoming edges of type 1
+    let schema = TestSchema::new()
+        .endpoint_outgoing_min_quantity(Some(HashMap::from([((0, 1), 1)])))
+        .endpoint_incoming_min_quantity(Some(HashMap::from([((1, 1), 2)])));
+    let mut g = TestGraph::new(schema);
+
+    g.add_node((0, 0))?;
+    g.add_node((1, 0))?;
+    g.add_node((2, 1))?;
+
+    // None of the nodes has any edges yet
+    let errors = g.validate().unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e, TypedError::InvalidLowerBound(..))));
+
+    g.add_edge(0, 2, (0, 1))?;
+    assert_eq!(g.validate().unwrap_err().len(), 2);
+
+    // Edges of other types does not count towards the lower bound
+    g.add_edge(1, 2, (1, 0))?;
+    assert_eq!(g.validate().unwrap_err().len(), 2);
+
+    g.add_edge(1, 2, (2, 1))?;
+    assert!(g.validate().is_ok());
+
+    g.remove_edge(0)?;
+    assert_eq!(g.validate().unwrap_err().len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn graph_validate_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::HashMap;
+
+    let schema = TestSchema::new()
+        .node_whitelist(Some(vec![0, 1]))
+        .endpoint_whitelist(Some(vec![(0, 1, 0), (0, 1, 1)]))
+        .endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 0), 1)])));
+    let mut g = TestGraph::new(schema);
+
+    g.add_node((0, 0))?;
+    g.add_node((1, 1))?;
+    g.add_node((2, 1))?;
+    g.add_node((3, 1))?;
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(0, 2, (1, 1))?;
+    assert!(g.validate().is_ok());
+
+    // Change the types without going through add_node and add_edge
+    g.get_node_safe_mut(3).unwrap().1 = 2;
+    g.get_edge_safe_mut(1).unwrap().1 = 0;
+
+    let errors = g.validate().unwrap_err();
+    assert_eq!(errors.len(), 3);
+
+    // The node type is no longer allowed
+    assert!(matches!(
+        errors[0],
+        TypedError::InvalidNodeType(2, DisAllowedNode::InvalidType, _)
+    ));
+
+    // Both edges are now violating the quantity limit
+    assert!(errors[1..].iter().all(|e| matches!(
+        e,
+        TypedError::InvalidEdgeType(0, 0, 1, DisAllowedEdge::ToManyOutgoing, _)
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn graph_edge_update_endpoints() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..3 {
+        g.add_node((i, 0))?;
+    }
+
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(0, 2, (1, 0))?;
+
+    // Move the first edge to go from 2 to 1
+    g.add_edge(2, 1, (0, 0))?;
+
+    let edge = g.get_edge_full(0)?;
+    assert_eq!(edge.get_source(), 2);
+    assert_eq!(edge.get_target(), 1);
+
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![1]);
+    let outgoing: Vec<usize> = g.get_outgoing(2)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![0]);
+    let incoming: Vec<usize> = g.get_incoming(1)?.map(|e| e.get_id()).collect();
+    assert_eq!(incoming, vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_undirected_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = UndirectedTestGraph::default();
+    for i in 0..3 {
+        g.add_node((i, 0))?;
+    }
+
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(1, 2, (1, 0))?;
+    // Edge loop
+    g.add_edge(2, 2, (2, 0))?;
+
+    // The edges are seen from the perspective of the node
+    let outgoing: Vec<_> = g
+        .get_outgoing(1)?
+        .map(|e| (e.get_id(), e.get_source(), e.get_target()))
+        .collect();
+    assert_eq!(outgoing, vec![(0, 1, 0), (1, 1, 2)]);
+
+    let incoming: Vec<_> = g
+        .get_incoming(1)?
+        .map(|e| (e.get_id(), e.get_source(), e.get_target()))
+        .collect();
+    assert_eq!(incoming, vec![(0, 0, 1), (1, 2, 1)]);
+
+    // Edges are not repeated when looking in both directions
+    assert_eq!(g.get_incoming_and_outgoing(0)?.count(), 1);
+
+    let (_, matrix) = g.adjacency_matrix();
+    assert_eq!(matrix, vec![vec![0, 1, 0], vec![1, 0, 1], vec![0, 1, 1]]);
+
+    // Removing either endpoint removes the shared edge
+    let mut ng = g.clone();
+    ng.remove_node(0)?;
+    assert_eq!(ng.edge_count(), 2);
+    assert_eq!(ng.get_outgoing(1)?.count(), 1);
+    assert_eq!(ng.get_incoming(1)?.count(), 1);
+
+    let mut ng = g.clone();
+    ng.remove_node(1)?;
+    assert_eq!(ng.edge_count(), 1);
+    assert_eq!(ng.get_outgoing(0)?.count(), 0);
+    assert_eq!(ng.get_incoming(0)?.count(), 0);
+    assert_eq!(ng.get_outgoing(2)?.count(), 1);
+
+    ng.remove_node(2)?;
+    assert_eq!(ng.edge_count(), 0);
+
+    g.remove_edge(1)?;
+    assert_eq!(g.get_outgoing(1)?.count(), 1);
+    assert_eq!(g.get_incoming(2)?.count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn graph_symmetric_edge_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = SymmetricTestGraph::default();
+    for i in 0..3 {
+        g.add_node((i, 0))?;
+    }
+
+    // Adding a symmetric edge also adds the mirrored edge
+    g.add_edge(0, 1, (0, 1))?;
+    g.add_edge(0, 2, (1, 0))?;
+    assert_eq!(g.edge_count(), 3);
+
+    let mirror = g.get_edge_full(!0)?;
+    assert_eq!(mirror.get_source(), 1);
+    assert_eq!(mirror.get_target(), 0);
+
+    // Moving the edge also moves the mirror
+    g.add_edge(0, 2, (0, 1))?;
+    assert_eq!(g.edge_count(), 3);
+    let mirror = g.get_edge_full(!0)?;
+    assert_eq!(mirror.get_source(), 2);
+    assert_eq!(mirror.get_target(), 0);
+
+    // Removing either edge removes both of them
+    g.remove_edge(!0)?;
+    assert_eq!(g.edge_count(), 1);
+    assert!(!g.has_edge(0));
+    g.debug_assert_consistent();
+
+    g.add_edge(0, 1, (0, 1))?;
+    g.remove_node(1)?;
+    assert_eq!(g.edge_count(), 1);
+    g.debug_assert_consistent();
+
+    // Changing the type removes the mirror
+    g.add_edge(0, 2, (2, 1))?;
+    assert!(g.has_edge(!2));
+    g.add_edge(0, 2, (2, 0))?;
+    assert!(!g.has_edge(!2));
+    assert_eq!(g.edge_count(), 2);
+    g.debug_assert_consistent();
+
+    // The mirror is not allowed to replace another edge
+    g.add_edge(2, 0, (!3, 0))?;
+    assert!(matches!(
+        g.add_edge(0, 2, (3, 1)),
+        Err(TypedError::EdgeIdCollision(_))
+    ));
+    assert!(!g.has_edge(3));
+
+    Ok(())
+}
+
+#[test]
+fn graph_self_loop_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    // Self loops are allowed by default
+    let mut g = TestGraph::default();
+    g.add_node((0, 0))?;
+    g.add_edge(0, 0, (0, 0))?;
+
+    let mut g = TestGraph::new(TestSchema::new().allow_self_loops(false));
+    g.add_node((0, 0))?;
+    g.add_node((1, 0))?;
+    g.add_edge(0, 1, (0, 0))?;
+
+    let res = g.add_edge(1, 1, (1, 0));
+    assert!(matches!(
+        res,
+        Err(TypedError::InvalidEdgeType(.., DisAllowedEdge::SelfLoop, _))
+    ));
+
+    // Moving an existing edge onto a single node is also rejected
+    let res = g.add_edge(0, 0, (0, 0));
+    assert!(matches!(
+        res,
+        Err(TypedError::InvalidEdgeType(.., DisAllowedEdge::SelfLoop, _))
+    ));
+    assert_eq!(g.edge_count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn graph_allow_edge_full_test() -> crate::test::TestResult<()> {
+    use crate::generic_graph::{GenericEdge, GenericNode};
+    use crate::test::*;
+
+    /// Edges with an id of 100 or more are only allowed to go to nodes of type 1
+    #[derive(Default)]
+    struct WeightSchema(TestSchema);
+
+    impl SchemaExt<usize, usize> for WeightSchema {
+        type N = GenericNode<usize, usize>;
+        type E = GenericEdge<usize, usize>;
+
+        fn name(&self) -> String {
+            "WeightSchema".to_string()
+        }
+
+        fn allow_node(&self, node_ty: usize) -> Result<(), DisAllowedNode> {
+            SchemaExt::<usize, usize>::allow_node(&self.0, node_ty)
+        }
+
+        fn allow_edge(
+            &self,
+            outgoing_edge_count: usize,
+            incoming_edge_count: usize,
+            edge_ty: usize,
+            source: usize,
+            target: usize,
+        ) -> Result<(), DisAllowedEdge> {
+            SchemaExt::<usize, usize>::allow_edge(
+                &self.0,
+                outgoing_edge_count,
+                incoming_edge_count,
+                edge_ty,
+                source,
+                target,
+            )
+        }
+
+        fn allow_edge_full(
+            &self,
+            outgoing_edge_count: usize,
+            incoming_edge_count: usize,
+            weight: &Self::E,
+            source: &Self::N,
+            target: &Self::N,
+        ) -> Result<(), DisAllowedEdge> {
+            if weight.0 >= 100 && target.1 != 1 {
+                return Err(DisAllowedEdge::InvalidType);
+            }
+
+            self.allow_edge(
+                outgoing_edge_count,
+                incoming_edge_count,
+                weight.get_type(),
+                source.get_type(),
+                target.get_type(),
+            )
+        }
+    }
+
+    let mut g = TypedGraph::<usize, usize, WeightSchema>::default();
+    g.add_node((0, 0))?;
+    g.add_node((1, 1))?;
+
+    g.add_edge(0, 0, (0, 0))?;
+    g.add_edge(0, 1, (100, 0))?;
+    assert!(g.add_edge(1, 0, (101, 0)).is_err());
+
+    // Changing the type of the target is checked against the weights as well
+    assert!(g.add_node((1, 0)).is_err());
+    assert!(g.validate().is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn graph_capacity_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::with_capacity(TestSchema::default(), 10, 20);
+    assert!(g.nodes.capacity() >= 10);
+    assert!(g.edges.capacity() >= 20);
+    assert!(g.node_lut.capacity() >= 10);
+    assert!(g.edge_lut.capacity() >= 20);
+
+    g.add_node((0, 0))?;
+    g.reserve(100, 200);
+    assert!(g.nodes.capacity() >= 101);
+    assert!(g.edges.capacity() >= 200);
+    assert!(g.node_lut.capacity() >= 101);
+    assert!(g.edge_lut.capacity() >= 200);
+
+    Ok(())
+}
+
+#[test]
+fn graph_shrink_to_fit_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..1000 {
+        g.add_node((i, 0))?;
+    }
+    for i in 0..999 {
+        g.add_edge(i, i + 1, (i, 0))?;
+    }
+    g.add_edge(5, 0, (1000, 0))?;
+
+    for i in 10..1000 {
+        g.remove_node(i)?;
+    }
+
+    let expected = g.clone();
+    let node_capacity = g.nodes.capacity();
+    let edge_capacity = g.edges.capacity();
+    let node_lut_capacity = g.node_lut.capacity();
+    let edge_lut_capacity = g.edge_lut.capacity();
+
+    g.shrink_to_fit();
+
+    assert!(g.nodes.capacity() < node_capacity);
+    assert!(g.edges.capacity() < edge_capacity);
+    assert!(g.node_lut.capacity() < node_lut_capacity);
+    assert!(g.edge_lut.capacity() < edge_lut_capacity);
+
+    // The graph should still be intact
+    g.assert_eq(&expected)?;
+    let outgoing: Vec<usize> = g.get_outgoing(5)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![5, 1000]);
+    let incoming: Vec<usize> = g.get_incoming(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(incoming, vec![1000]);
+
+    g.remove_edge(1000)?;
+    g.add_node((10, 0))?;
+    g.add_edge(9, 10, (9, 0))?;
+    assert_eq!(g.node_count(), 11);
+    assert_eq!(g.edge_count(), 10);
+
+    Ok(())
+}
+
+#[test]
+fn graph_bulk_add_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let schema = TestSchema::new()
+        .node_blacklist(Some(vec![9]))
+        .allow_self_loops(false);
+    let mut g = TestGraph::new(schema);
+
+    let ids = g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    assert_eq!(ids, vec![0, 1, 2, 3]);
+    let ids = g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0)), (0, 3, (2, 0))], true)?;
+    assert_eq!(ids, vec![0, 1, 2]);
+
+    let expected = g.clone();
+
+    // Atomic calls leave the graph untouched when failing
+    let res = g.add_nodes([(4, 0), (0, 1), (9, 9)], true);
+    assert!(matches!(res, Err(TypedError::InvalidNodeType(9, _, _))));
+    g.assert_eq(&expected)?;
+
+    let res = g.add_edges([(1, 2, (3, 0)), (2, 3, (0, 1)), (3, 3, (4, 0))], true);
+    assert!(matches!(
+        res,
+        Err(TypedError::InvalidEdgeType(.., DisAllowedEdge::SelfLoop, _))
+    ));
+    g.assert_eq(&expected)?;
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![0, 1, 2]);
+
+    // Non atomic calls keep everything before the failure
+    let res = g.add_nodes([(4, 0), (0, 1), (9, 9)], false);
+    assert!(res.is_err());
+    assert_eq!(g.node_count(), 5);
+    assert_eq!(g.get_node(0)?.get_type(), 1);
+
+    let res = g.add_edges([(1, 2, (3, 0)), (2, 3, (0, 1)), (3, 3, (4, 0))], false);
+    assert!(res.is_err());
+    assert_eq!(g.edge_count(), 4);
+    assert_eq!(g.get_edge_full(0)?.get_source(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn graph_quantity_index_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use fake::Dummy;
+
+    /// Compare the maintained quantities with the ones counted from the edges
+    fn check_quantities<S: SchemaExt<usize, usize>>(
+        g: &TypedGraph<usize, usize, S>,
+    ) -> SchemaResult<(), usize, usize, S>
+    where
+        S::N: Typed<Type = usize>,
+        S::E: Typed<Type = usize>,
+    {
+        for node_id in g.node_ids() {
+            let node_key = g.get_node_key(&node_id)?;
+            for edge_type in 0..3 {
+                for node_type in 0..3 {
+                    for dir in [Direction::Outgoing, Direction::Incoming] {
+                        assert_eq!(
+                            g.get_quantity(node_key, dir, &edge_type, &node_type)?,
+                            g.count_quantity(node_id, dir, node_type, edge_type)?
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let config = CompleteGraph {
+        nodes: 10,
+        node_types: 3,
+        edge_types: 3,
+    };
+    let mut g = TestGraph::dummy(&config);
+    check_quantities(&g)?;
+
+    // Change the type of a node, move an edge and change the type of an edge
+    g.add_node((0, 2))?;
+    g.add_edge(3, 3, (0, 1))?;
+    g.add_edge(4, 5, (1, 2))?;
+    g.remove_node(6)?;
+    g.remove_edge(2)?;
+    check_quantities(&g)?;
+
+    g.shrink_to_fit();
+    check_quantities(&g)?;
+
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, i % 3)), true)?;
+    let edges = [
+        (0, 1, (0, 0)),
+        (1, 2, (1, 1)),
+        (2, 2, (2, 2)),
+        (3, 0, (3, 0)),
+    ];
+    g.add_edges(edges, true)?;
+    check_quantities(&g)?;
+
+    g.add_node((0, 2))?;
+    g.add_edge(2, 1, (1, 0))?;
+    g.remove_node(3)?;
+    check_quantities(&g)?;
+
+    Ok(())
+}
+
+#[test]
+fn graph_nodes_of_type_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::new(TestSchema::new().node_blacklist(Some(vec![9])));
+    g.add_nodes((0..6).map(|i| (i, i % 3)), true)?;
+    g.add_edge(0, 3, (0, 0))?;
+
+    let sorted_nodes_of_type = |g: &TestGraph, ty| {
+        let mut ids: Vec<usize> = g.nodes_of_type(ty).collect();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(sorted_nodes_of_type(&g, 0), vec![0, 3]);
+    assert_eq!(sorted_nodes_of_type(&g, 1), vec![1, 4]);
+    assert_eq!(g.count_nodes_of_type(2), 2);
+    assert_eq!(g.count_nodes_of_type(3), 0);
+    assert_eq!(g.nodes_of_type(3).count(), 0);
+
+    // Changing the type moves the node to the new type
+    g.add_node((0, 1))?;
+    assert_eq!(sorted_nodes_of_type(&g, 0), vec![3]);
+    assert_eq!(sorted_nodes_of_type(&g, 1), vec![0, 1, 4]);
+
+    g.remove_node(3)?;
+    assert_eq!(g.count_nodes_of_type(0), 0);
+
+    // Failed atomic inserts does not leave anything behind
+    let res = g.add_nodes([(6, 0), (1, 0), (7, 9)], true);
+    assert!(res.is_err());
+    assert_eq!(g.count_nodes_of_type(0), 0);
+    assert_eq!(sorted_nodes_of_type(&g, 1), vec![0, 1, 4]);
+
+    g.add_nodes([(6, 0), (7, 0), (1, 0)], true)?;
+    g.remove_node(6)?;
+    assert_eq!(sorted_nodes_of_type(&g, 0), vec![1, 7]);
+
+    g.shrink_to_fit();
+    assert_eq!(sorted_nodes_of_type(&g, 0), vec![1, 7]);
+    assert_eq!(sorted_nodes_of_type(&g, 1), vec![0, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_broken_state_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.add_edge(0, 1, (0, 0))?;
+
+    // Remove the node without removing its edges
+    let node_key = g.get_node_key(&1)?;
+    g.nodes.remove(node_key);
+
+    // The broken edge is skipped by the plain iterators and reported by the try_* variants
+    assert_eq!(g.get_outgoing(0)?.count(), 0);
+    assert!(matches!(
+        g.try_get_outgoing(0)?.next(),
+        Some(Err(TypedError::MissingNodeKey(_)))
+    ));
+    assert!(matches!(
+        g.get_edge_full(0),
+        Err(TypedError::MissingNodeKey(_))
+    ));
+    assert_eq!(g.edges_full().count(), 0);
+    assert!(matches!(
+        g.try_edges_full().next(),
+        Some(Err(TypedError::MissingNodeKey(_)))
+    ));
+    #[cfg(feature = "serde")]
+    assert!(serde_json::to_string(&g).is_err());
+
+    // Remove the edge without removing it from the node
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.add_edge(0, 1, (0, 0))?;
+    let edge_key = g.get_edge_key(&0)?;
+    g.edges.remove(edge_key);
+
+    assert_eq!(g.get_outgoing(0)?.count(), 0);
+    assert!(matches!(
+        g.try_get_outgoing(0)?.next(),
+        Some(Err(TypedError::MissingEdgeKey(_)))
+    ));
+    assert!(matches!(
+        g.try_get_incoming(1)?.next(),
+        Some(Err(TypedError::MissingEdgeKey(_)))
+    ));
+    #[cfg(feature = "serde")]
+    assert!(serde_json::to_string(&g.serialize_with_order()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graph_reverse_edge_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    // Edges of type 0 may only go from type 0 to type 1
+    let schema = TestSchema::new().endpoint_whitelist(Some(vec![
+        (0, 1, 0),
+        (0, 0, 1),
+        (0, 1, 1),
+        (1, 0, 1),
+    ]));
+    let mut g = TestGraph::new(schema);
+    g.add_nodes([(0, 0), (1, 1), (2, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 1)), (1, 0, (2, 1))], true)?;
+
+    let expected = g.clone();
+    let res = g.reverse_edge(0);
+    assert!(matches!(
+        res,
+        Err(TypedError::InvalidEdgeType(
+            0,
+            1,
+            0,
+            DisAllowedEdge::InvalidType,
+            _
+        ))
+    ));
+    g.assert_eq(&expected)?;
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![0, 1]);
+
+    g.reverse_edge(1)?;
+    let edge = g.get_edge_full(1)?;
+    assert_eq!((edge.get_source(), edge.get_target()), (2, 0));
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![0]);
+    let incoming: Vec<usize> = g.get_incoming(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(incoming, vec![2, 1]);
+
+    g.reverse_edge(2)?;
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![0, 2]);
+
+    assert!(matches!(g.reverse_edge(3), Err(TypedError::MissingEdge(3))));
+
+    Ok(())
+}
+
+#[test]
+fn graph_sort_outgoing_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..5).map(|i| (i, 0)), true)?;
+    g.add_edges(
+        [
+            (0, 3, (0, 1)),
+            (0, 1, (1, 0)),
+            (0, 4, (2, 1)),
+            (0, 2, (3, 0)),
+        ],
+        true,
+    )?;
+
+    // Sort by target
+    g.sort_outgoing_by(0, |a, b| a.get_target().cmp(&b.get_target()))?;
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![1, 3, 0, 2]);
+
+    // Sorting is stable
+    g.sort_outgoing_by(0, |a, b| a.get_type().cmp(&b.get_type()))?;
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![1, 3, 0, 2]);
+
+    g.sort_outgoing_by(0, |a, b| b.get_type().cmp(&a.get_type()))?;
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![0, 2, 1, 3]);
+
+    // The incoming edges are unaffected
+    let incoming: Vec<usize> = g.get_incoming(3)?.map(|e| e.get_id()).collect();
+    assert_eq!(incoming, vec![0]);
+
+    assert!(matches!(
+        g.sort_outgoing_by(5, |_, _| Ordering::Equal),
+        Err(TypedError::MissingNode(5))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn graph_incoming_order_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = OrderedIncomingTestGraph::default();
+    g.add_nodes((0..6).map(|i| (i, 0)), true)?;
+    g.add_edges((1..6).map(|i| (i, 0, (i, 0))), true)?;
+
+    let incoming = |g: &OrderedIncomingTestGraph| -> TestResult<Vec<usize>> {
+        Ok(g.get_incoming(0)?.map(|e| e.get_id()).collect())
+    };
+    assert_eq!(incoming(&g)?, vec![1, 2, 3, 4, 5]);
+
+    g.move_incoming_edge_order(5, 1, InsertPosition::Before)?;
+    assert_eq!(incoming(&g)?, vec![5, 1, 2, 3, 4]);
+    g.move_incoming_edge_order(1, 3, InsertPosition::After)?;
+    assert_eq!(incoming(&g)?, vec![5, 2, 3, 1, 4]);
+
+    // Removing edges keeps the order of the remaining ones
+    g.remove_edge(5)?;
+    assert_eq!(incoming(&g)?, vec![2, 3, 1, 4]);
+    g.remove_node(3)?;
+    assert_eq!(incoming(&g)?, vec![2, 1, 4]);
+
+    // Moving an edge to another target keeps the order of the remaining ones
+    g.add_edge(2, 1, (2, 0))?;
+    assert_eq!(incoming(&g)?, vec![1, 4]);
+
+    // The edges must have the same target
+    g.add_edge(1, 2, (6, 0))?;
+    assert!(matches!(
+        g.move_incoming_edge_order(6, 1, InsertPosition::Before),
+        Err(TypedError::InvalidEdgeMove(6, 1))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn graph_edges_of_type_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    assert!(g.is_empty());
+
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    assert!(!g.is_empty());
+    g.add_edges((0..6).map(|i| (i % 4, (i + 1) % 4, (i, i % 2))), true)?;
+
+    let sorted_edges_of_type = |g: &TestGraph, ty| {
+        let mut ids: Vec<usize> = g.edges_of_type(ty).collect();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(sorted_edges_of_type(&g, 0), vec![0, 2, 4]);
+    assert_eq!(sorted_edges_of_type(&g, 1), vec![1, 3, 5]);
+    assert_eq!(g.count_edges_of_type(2), 0);
+
+    // Changing the type of an edge
+    g.add_edge(0, 1, (0, 2))?;
+    assert_eq!(sorted_edges_of_type(&g, 0), vec![2, 4]);
+    assert_eq!(sorted_edges_of_type(&g, 2), vec![0]);
+
+    // Removing edges directly and through their nodes
+    g.remove_edge(2)?;
+    g.remove_node(1)?;
+    assert_eq!(g.count_edges_of_type(0), 0);
+    assert_eq!(sorted_edges_of_type(&g, 1), vec![3]);
+    assert_eq!(g.count_edges_of_type(2), 0);
+
+    g.shrink_to_fit();
+    assert_eq!(sorted_edges_of_type(&g, 1), vec![3]);
+
+    for i in [0, 2, 3] {
+        g.remove_node(i)?;
+    }
+    assert!(g.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn graph_update_schema_report_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0), (3, 1)], true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 2, (1, 0)),
+            (1, 3, (2, 0)),
+            (2, 1, (3, 1)),
+        ],
+        true,
+    )?;
+
+    // Only allow a single outgoing edge of type 0 from nodes of type 0
+    let new_schema =
+        TestSchema::new().endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 0), 1)])));
+    let (new_g, report) = g.update_schema_with_report(
+        new_schema,
+        |_, _, n| (n.get_type() == 0).then_some(n),
+        |_, _, e| (e.get_type() == 0).then_some(e),
+    )?;
+
+    assert_eq!(new_g.node_count(), 3);
+    assert_eq!(new_g.edge_count(), 1);
+    assert_eq!(
+        report,
+        MigrationReport {
+            dropped_nodes: vec![(3, 1)],
+            dropped_edges: vec![(2, 0), (3, 1)],
+            excess_edges: vec![(1, 0)],
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn graph_can_migrate_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 1)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (0, 1, (2, 1))], true)?;
+
+    let report = g
+        .can_migrate(&TestSchema::new(), &DefaultMigrationHandler)
+        .unwrap();
+    assert_eq!(
+        report.dropped_nodes,
+        vec![(2, "UndirectedTestSchema::1".to_string())]
+    );
+    let mut dropped_edges = report.dropped_edges.clone();
+    dropped_edges.sort();
+    assert_eq!(
+        dropped_edges,
+        vec![
+            (1, "UndirectedTestSchema::0".to_string()),
+            (2, "UndirectedTestSchema::1".to_string())
+        ]
+    );
+    assert!(report.excess_edges.is_empty());
+
+    // The graph is left untouched
+    assert_eq!(g.node_count(), 3);
+    assert_eq!(g.edge_count(), 3);
+
+    // And the preview matches the actual migration
+    let (new_g, migration_report) = g
+        .migrate_with_report(TestSchema::new(), &DefaultMigrationHandler)
+        .unwrap();
+    assert_eq!(report, migration_report);
+    assert_eq!(new_g.node_count(), 2);
+    assert_eq!(new_g.edge_count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn graph_update_schema_rules_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 1)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (1, 0, (2, 1))], true)?;
+
+    let stricter_schema = || {
+        TestSchema::new()
+            .node_blacklist(Some(vec![1]))
+            .edge_blacklist(Some(vec![1]))
+    };
+
+    // Rejecting the disallowed data fails the update
+    let result = g
+        .clone()
+        .update_schema_rules(stricter_schema(), DisAllowedPolicy::Reject);
+    assert!(matches!(result, Err(TypedError::InvalidNodeType(1, _, _))));
+
+    // Dropping the disallowed data removes it from the graph
+    let (new_g, report) = g.update_schema_rules(stricter_schema(), DisAllowedPolicy::Drop)?;
+    assert_eq!(new_g.node_count(), 2);
+    assert_eq!(new_g.edge_count(), 1);
+    assert_eq!(
+        report,
+        MigrationReport {
+            dropped_nodes: vec![(2, 1)],
+            dropped_edges: vec![(1, 0), (2, 1)],
+            excess_edges: vec![],
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn graph_update_schema_remap_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (2, 0, (2, 1))], true)?;
+
+    let map_node = |n: TestNode, f: fn(usize) -> usize| {
+        let id: usize = n.get_id();
+        TestNode::from((f(id), n.get_type()))
+    };
+    let map_edge = |e: TestEdge, f: fn(usize) -> usize| {
+        let id: usize = e.get_id();
+        TestEdge::from((f(id), e.get_type()))
+    };
+
+    // The ids are not allowed to change without remapping
+    let result = g.clone().update_schema(
+        TestSchema::new(),
+        |_, _, n| Some(map_node(n, |id| id + 10)),
+        |_, _, e| Some(e),
+    );
+    assert!(matches!(
+        result,
+        Err(TypedError::InconsistentNodeIds(0, 10))
+    ));
+
+    let (new_g, report) = g.clone().update_schema_remap(
+        TestSchema::new(),
+        |_, _, n| Some(map_node(n, |id| id + 10)),
+        |_, _, e| (e.get_type() != 1).then(|| map_edge(e, |id| id + 100)),
+    )?;
+    assert!(new_g.has_node(10) && new_g.has_node(11) && new_g.has_node(12));
+    assert!(!new_g.has_node(0));
+    assert_eq!(new_g.get_edge_full(100)?.get_source(), 10);
+    assert_eq!(new_g.get_edge_full(100)?.get_target(), 11);
+    assert_eq!(new_g.get_edge_full(101)?.get_source(), 11);
+    assert_eq!(new_g.edge_count(), 2);
+    assert_eq!(report.dropped_edges, vec![(2, 1)]);
+
+    // Two nodes ending up with the same id is a collision
+    let result = g.clone().update_schema_remap(
+        TestSchema::new(),
+        |_, _, n| Some(map_node(n, |id| id / 2)),
+        |_, _, e| Some(e),
+    );
+    assert!(matches!(result, Err(TypedError::NodeIdCollision(0))));
+
+    let result = g.update_schema_remap(
+        TestSchema::new(),
+        |_, _, n| Some(n),
+        |_, _, e| Some(map_edge(e, |id| id / 2)),
+    );
+    assert!(matches!(result, Err(TypedError::EdgeIdCollision(0))));
+
+    Ok(())
+}
+
+#[test]
+fn graph_quantity_error_direction_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let s = TestSchema::new()
+        .endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 0), 1)])))
+        .endpoint_incoming_max_quantity(Some(HashMap::from([((1, 1), 1)])));
+    let mut g = TestGraph::new(s);
+    g.add_nodes([(0, 0), (1, 1), (2, 1), (3, 1)], true)?;
+
+    g.add_edge(0, 1, (0, 0))?;
+    let Err(TypedError::InvalidEdgeType(_, _, _, e, _)) = g.add_edge(0, 2, (1, 0)) else {
+        panic!("Expected the outgoing limit to be exceeded");
+    };
+    assert!(matches!(e.quantity_direction(), Some(Direction::Outgoing)));
+
+    g.add_edge(2, 1, (2, 1))?;
+    let err = g.add_edge(3, 1, (3, 1)).unwrap_err();
+    let TypedError::InvalidEdgeType(_, _, _, e, _) = &err else {
+        panic!("Expected the incoming limit to be exceeded");
+    };
+    assert!(matches!(e.quantity_direction(), Some(Direction::Incoming)));
+    assert_eq!(
+        err.to_string(),
+        "Invalid edge type 1 from 1 to 1 due to too many incoming edges of the type into the target in schema GenericSchema"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn graph_quantity_replace_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let s = TestSchema::new()
+        .endpoint_incoming_max_quantity(Some(HashMap::from([((0, 0), 1)])))
+        .endpoint_outgoing_max_quantity(Some(HashMap::from([((2, 0), 1)])));
+    let mut g = TestGraph::new(s);
+    g.add_nodes([(0, 1), (1, 0), (2, 0), (3, 1)], true)?;
+
+    // Updating an edge at the limit does not count the edge twice
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(0, 1, (0, 0))?;
+    assert!(g.add_edge(3, 1, (1, 0)).is_err());
+
+    // Node 0 can not become type 2 while it has two outgoing edges of type 0
+    g.add_edge(0, 2, (1, 0))?;
+    assert!(g.add_node((0, 2)).is_err());
+    assert_eq!(g.get_node(0)?.get_type(), 1);
+
+    // The quantities are restored after the failed replacement
+    assert!(g.add_edge(0, 1, (2, 0)).is_err());
+    g.remove_edge(1)?;
+    g.add_node((0, 2))?;
+    assert_eq!(g.get_node(0)?.get_type(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn graph_nodes_with_outgoing_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..3).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0)), (2, 0, (2, 0))], true)?;
+    g.move_edge_order(1, 0, InsertPosition::Before)?;
+
+    let mut node_count = 0;
+    for (node, outgoing) in g.nodes_with_outgoing() {
+        let node_id: usize = node.get_id();
+        let edge_ids: Vec<usize> = outgoing
+            .map(|e| e.map(|e| e.get_id()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected_ids: Vec<usize> = g.get_outgoing(node_id)?.map(|e| e.get_id()).collect();
+        assert_eq!(edge_ids, expected_ids);
+        node_count += 1;
+    }
+    assert_eq!(node_count, 3);
+
+    // Remove a node without removing its edges
+    let node_key = g.get_node_key(&1)?;
+    g.nodes.remove(node_key);
+
+    // Only the broken edge is reported while the other nodes and edges are still visited
+    let results: Vec<Vec<bool>> = g
+        .nodes_with_outgoing()
+        .map(|(_, outgoing)| outgoing.map(|e| e.is_ok()).collect())
+        .collect();
+    assert_eq!(results, vec![vec![true, false], vec![true]]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_edge_endpoints_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..3).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (2, 0, (1, 0))], true)?;
+
+    assert_eq!(g.edge_endpoints(0)?, (0, 1));
+    assert_eq!(g.edge_endpoints(1)?, (2, 0));
+    assert!(matches!(
+        g.edge_endpoints(2),
+        Err(TypedError::MissingEdge(2))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn graph_entry_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = VariantTestGraph::default();
+    let increment = |n: &mut VariantNode| {
+        if let VariantNode::Number(n) = n {
+            n.value += 1;
+        }
+    };
+
+    // A vacant entry is inserted and and_modify is skipped
+    g.node_entry(0)
+        .unwrap()
+        .and_modify(increment)
+        .or_insert_with(|| VariantNode::Number(NumberNode { id: 0, value: 1 }))
+        .unwrap();
+    assert!(matches!(
+        g.get_node(0).unwrap(),
+        VariantNode::Number(NumberNode { value: 1, .. })
+    ));
+
+    // An occupied entry is modified in place and the function is not called
+    g.node_entry(0)
+        .unwrap()
+        .and_modify(increment)
+        .or_insert_with(|| -> VariantNode { unreachable!() })
+        .unwrap();
+    assert!(matches!(
+        g.get_node(0).unwrap(),
+        VariantNode::Number(NumberNode { value: 2, .. })
+    ));
+    assert_eq!(g.node_count(), 1);
+
+    // The inserted node must use the id of the entry
+    let res = g
+        .node_entry(1)
+        .unwrap()
+        .or_insert_with(|| VariantNode::Number(NumberNode { id: 2, value: 0 }));
+    assert!(matches!(res, Err(TypedError::InconsistentNodeIds(1, 2))));
+    assert!(!g.has_node(1));
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..2).map(|i| (i, 0)), true)?;
+
+    match g.edge_entry(0)? {
+        EdgeEntry::Vacant(entry) => {
+            assert_eq!(entry.id(), 0);
+            entry.insert(0, 1, (0, 0))?;
+        }
+        EdgeEntry::Occupied(_) => panic!("Edge 0 should not exist"),
+    }
+    assert_eq!(g.edge_endpoints(0)?, (0, 1));
+
+    let edge = g.edge_entry(0)?.or_insert_with(1, 0, || (0, 1))?;
+    assert_eq!(edge.get_type(), 0);
+    assert_eq!(g.edge_count(), 1);
+
+    // Inserting still goes through the schema checks
+    assert!(g.edge_entry(1)?.or_insert_with(0, 2, || (1, 0)).is_err());
+    assert!(!g.has_edge(1));
+
+    // Retyping through the reference of an inserted entry is picked up by the type index
+    g.node_entry(2)?.or_insert_with(|| (2, 0))?.1 = 1;
+    g.debug_assert_consistent();
+    assert_eq!(g.nodes_of_type(1).collect::<Vec<_>>(), vec![2]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_try_filter_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 2, (1, 1)),
+            (0, 3, (2, 0)),
+            (3, 0, (3, 0)),
+        ],
+        true,
+    )?;
+
+    // Type 1 edges can not be handled by the filter
+    let filter = |e: &TestEdge| match e.get_type() {
+        1 => Err(TypedError::MissingEdge(e.get_id())),
+        ty => Ok(ty == 0),
+    };
+
+    let outgoing: Result<Vec<_>, _> = g.try_get_outgoing_filter(0, filter)?.collect();
+    assert!(matches!(outgoing, Err(TypedError::MissingEdge(1))));
+
+    // Only edges rejected by the filter are skipped
+    let outgoing: Vec<_> = g.try_get_outgoing_filter(0, filter)?.collect();
+    assert_eq!(outgoing.len(), 3);
+    assert!(outgoing[1].is_err());
+
+    let incoming: Vec<usize> = g
+        .try_get_incoming_filter(0, filter)?
+        .map(|e| e.map(|e| e.get_id()))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(incoming, vec![3]);
+
+    let all = g.try_get_incoming_and_outgoing_filter(3, filter)?.count();
+    assert_eq!(all, 2);
+
+    assert!(g.try_get_outgoing_filter(4, filter).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graph_get_many_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..3).map(|i| (i, i % 2)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 1))], true)?;
+
+    let [a, b, c] = g.get_nodes_many([2, 0, 1])?;
+    let a_id: usize = a.get_id();
+    let b_id: usize = b.get_id();
+    assert_eq!((a_id, b_id), (2, 0));
+    assert_eq!(c.get_type(), 1);
+
+    let [e1, e0] = g.get_edges_many([1, 0])?;
+    assert_eq!(e1.get_type(), 1);
+    assert_eq!(e0.get_type(), 0);
+
+    // The first missing id is reported
+    assert!(matches!(
+        g.get_nodes_many([0, 5, 6]),
+        Err(TypedError::MissingNode(5))
+    ));
+    assert!(matches!(
+        g.get_edges_many([3, 0]),
+        Err(TypedError::MissingEdge(3))
+    ));
+
+    let none: [&TestNode; 0] = g.get_nodes_many([])?;
+    assert!(none.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn graph_get_two_nodes_mut_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = VariantTestGraph::default();
+    g.add_node(VariantNode::Number(NumberNode { id: 0, value: 5 }))?;
+    g.add_node(VariantNode::Number(NumberNode { id: 1, value: 1 }))?;
+
+    // Move some of the value from one node to the other
+    match g.get_two_nodes_mut(0, 1).unwrap() {
+        (VariantNode::Number(a), VariantNode::Number(b)) => {
+            a.value -= 2;
+            b.value += 2;
+        }
+        _ => panic!("Both nodes should be numbers"),
+    }
+
+    let [a, b] = g.get_nodes_many([0, 1]).unwrap();
+    assert!(matches!(
+        a,
+        VariantNode::Number(NumberNode { value: 3, .. })
+    ));
+    assert!(matches!(
+        b,
+        VariantNode::Number(NumberNode { value: 3, .. })
+    ));
+
+    assert!(matches!(
+        g.get_two_nodes_mut(0, 0),
+        Err(TypedError::AliasedNode(0))
+    ));
+    assert!(matches!(
+        g.get_two_nodes_mut(0, 2),
+        Err(TypedError::MissingNode(2))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn graph_try_get_outgoing_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..3).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0))], true)?;
+
+    let outgoing: Vec<usize> = g
+        .try_get_outgoing(0)?
+        .map(|e| e.map(|e| e.get_id()))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(outgoing, vec![0, 1]);
+    assert_eq!(g.try_get_incoming_and_outgoing(2)?.count(), 1);
+    assert!(matches!(
+        g.try_get_outgoing(3).map(|_| ()),
+        Err(TypedError::NodeIdMissing(3))
+    ));
+
+    // Remove a node without removing its edges
+    let node_key = g.get_node_key(&1)?;
+    g.nodes.remove(node_key);
+
+    // The broken edge is reported on its own while the rest can still be used
+    let outgoing: Vec<_> = g.try_get_outgoing(0)?.collect();
+    assert!(matches!(outgoing[0], Err(TypedError::MissingNodeKey(_))));
+    assert!(matches!(&outgoing[1], Ok(e) if e.get_target() == 2));
+
+    // The same goes for the filtered traversal
+    let filtered: Vec<_> = g.try_get_outgoing_filter(0, |_| Ok(true))?.collect();
+    assert_eq!(filtered.len(), 2);
+    assert!(filtered[0].is_err());
+
+    // The plain traversal skips the broken edge
+    let outgoing: Vec<usize> = g.get_outgoing(0)?.map(|e| e.get_id()).collect();
+    assert_eq!(outgoing, vec![1]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_type_histogram_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    assert_eq!(g.type_histogram(), (vec![], vec![]));
+
+    g.add_nodes((0..5).map(|i| (i, i % 2)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (2, 3, (2, 1))], true)?;
+
+    let (mut nodes, mut edges) = g.type_histogram();
+    nodes.sort();
+    edges.sort();
+    assert_eq!(nodes, vec![(0, 3), (1, 2)]);
+    assert_eq!(edges, vec![(0, 2), (1, 1)]);
+
+    // Types which are no longer used are left out
+    g.remove_edge(2)?;
+    let (_, edges) = g.type_histogram();
+    assert_eq!(edges, vec![(0, 2)]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_outgoing_edge_order_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 2, (1, 0)),
+            (0, 3, (2, 0)),
+            (1, 0, (3, 0)),
+        ],
+        true,
+    )?;
+
+    assert_eq!(g.outgoing_edge_order(0)?, vec![0, 1, 2]);
+    assert!(g.outgoing_edge_order(3)?.is_empty());
+
+    g.move_edge_order(2, 0, InsertPosition::Before)?;
+    assert_eq!(g.outgoing_edge_order(0)?, vec![2, 0, 1]);
+
+    assert!(matches!(
+        g.outgoing_edge_order(4),
+        Err(TypedError::MissingNode(4))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn graph_set_outgoing_order_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 2, (1, 0)),
+            (0, 3, (2, 0)),
+            (1, 0, (3, 0)),
+        ],
+        true,
+    )?;
+
+    g.set_outgoing_order(0, &[1, 2, 0])?;
+    assert_eq!(g.outgoing_edge_order(0)?, vec![1, 2, 0]);
+
+    // Missing, extra, foreign and duplicate edges are all rejected
+    for order in [
+        &[1, 2][..],
+        &[1, 2, 0, 4],
+        &[1, 2, 3],
+        &[1, 2, 2],
+        &[1, 1, 2, 0],
+    ] {
+        assert!(matches!(
+            g.set_outgoing_order(0, order),
+            Err(TypedError::InvalidEdgeOrder(0))
+        ));
+    }
+
+    // A failed reorder leaves the order untouched
+    assert_eq!(g.outgoing_edge_order(0)?, vec![1, 2, 0]);
+
+    g.set_outgoing_order(3, &[])?;
+    assert!(matches!(
+        g.set_outgoing_order(4, &[]),
+        Err(TypedError::MissingNode(4))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn graph_ordered_iteration_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(3, 0), (1, 0), (4, 0), (0, 0)], true)?;
+    g.add_edges([(0, 1, (5, 0)), (1, 3, (2, 0)), (3, 4, (7, 0))], true)?;
+
+    // Reuse the slots of removed nodes and edges
+    g.remove_node(1)?;
+    g.add_node((2, 0))?;
+    g.add_edge(2, 0, (1, 0))?;
+
+    let node_ids: Vec<usize> = g.nodes_ordered().map(|n| n.get_id()).collect();
+    assert_eq!(node_ids, vec![0, 2, 3, 4]);
+
+    let edge_ids: Vec<usize> = g.edges_ordered().map(|e| e.get_id()).collect();
+    assert_eq!(edge_ids, vec![1, 7]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_reinterpret_schema_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::HashMap;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 1)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 1)), (1, 2, (2, 0))], true)?;
+    g.move_edge_order(1, 0, InsertPosition::Before)?;
+
+    // The data and the edge order is kept when the new rules allow it
+    let schema = TestSchema::new().node_whitelist(Some(vec![0, 1]));
+    let g = g.reinterpret_schema(schema)?;
+    assert_eq!(g.node_count(), 3);
+    assert_eq!(g.outgoing_edge_order(0)?, vec![1, 0]);
+    assert_eq!(g.count_edges_of_type(0), 2);
+
+    // Adding edges still respects the existing quantities
+    let schema = TestSchema::new()
+        .node_whitelist(Some(vec![0, 1]))
+        .endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 0), 1)])));
+    let mut g = g.reinterpret_schema(schema)?;
+    assert!(g.add_edge(0, 1, (3, 0)).is_err());
+
+    // Existing data which breaks the new rules is rejected
+    let schema = TestSchema::new().node_blacklist(Some(vec![1]));
+    assert!(matches!(
+        g.clone().reinterpret_schema(schema),
+        Err(TypedError::InvalidNodeType(1, _, _))
+    ));
+
+    let schema = TestSchema::new()
+        .allow_self_loops(true)
+        .edge_whitelist(Some(vec![0]));
+    assert!(matches!(
+        g.clone().reinterpret_schema(schema),
+        Err(TypedError::InvalidEdgeType(1, 0, 1, _, _))
+    ));
+
+    // Changing the direction of the edges rebuilds the graph
+    let g = g.reinterpret_schema(UndirectedTestSchema::default())?;
+    assert_eq!(g.edge_count(), 3);
+    assert_eq!(g.try_get_incoming_and_outgoing(2)?.count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn graph_node_handle_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..3).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0)), (2, 0, (2, 0))], true)?;
+
+    let handle = g.node_handle(0).unwrap();
+    assert!(g.node_handle(3).is_none());
+
+    let node_id: usize = g.get_node_by_handle(handle)?.get_id();
+    assert_eq!(node_id, 0);
+    assert_eq!(g.out_degree_by_handle(handle)?, 2);
+    assert_eq!(g.in_degree_by_handle(handle)?, 1);
+
+    let outgoing = g
+        .get_outgoing_by_handle(handle)?
+        .map(|e| e.map(|e| e.get_target()))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(outgoing, vec![1, 2]);
+    assert_eq!(g.get_incoming_by_handle(handle)?.count(), 1);
+
+    // A handle to a removed node can not be used even if the id is reused
+    g.remove_node(0)?;
+    g.add_node((0, 0))?;
+    assert!(matches!(
+        g.get_node_by_handle(handle),
+        Err(TypedError::MissingNodeKey(_))
+    ));
+    assert!(g.out_degree_by_handle(handle).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graph_from_edges_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let g = TestGraph::from_edges(
+        TestSchema::new(),
+        [
+            ((0, 0), (1, 0), (0, 0)),
+            ((1, 0), (2, 1), (1, 1)),
+            ((2, 1), (0, 0), (2, 0)),
+        ],
+    )?;
+    assert_eq!(g.node_count(), 3);
+    assert_eq!(g.edge_count(), 3);
+    assert_eq!(g.edge_endpoints(1)?, (1, 2));
+    assert_eq!(g.outgoing_edge_order(0)?, vec![0]);
+
+    // The last weight of a node is kept
+    let g = TestGraph::from_edges(
+        TestSchema::new(),
+        [((0, 0), (1, 0), (0, 0)), ((0, 1), (1, 0), (1, 0))],
+    )?;
+    assert_eq!(g.get_node(0)?.get_type(), 1);
+
+    // The schema is checked for both the nodes and edges
+    let schema = TestSchema::new().node_blacklist(Some(vec![1]));
+    let g = TestGraph::from_edges(schema, [((0, 0), (1, 1), (0, 0))]);
+    assert!(matches!(g, Err(TypedError::InvalidNodeType(1, _, _))));
+
+    let schema = TestSchema::new().edge_blacklist(Some(vec![1]));
+    let g = TestGraph::from_edges(schema, [((0, 0), (1, 0), (0, 1))]);
+    assert!(matches!(g, Err(TypedError::InvalidEdgeType(1, 0, 0, _, _))));
+
+    Ok(())
+}
+
+#[test]
+fn graph_contract_edge_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (1, 2, (1, 0)),
+            (2, 1, (2, 0)),
+            (3, 1, (3, 0)),
+            (0, 3, (4, 0)),
+        ],
+        true,
+    )?;
+
+    assert_eq!(g.contract_edge(0)?, 0);
+    assert!(!g.has_node(1));
+    assert!(!g.has_edge(0));
+    assert_eq!(g.edge_endpoints(1)?, (0, 2));
+    assert_eq!(g.edge_endpoints(2)?, (2, 0));
+    assert_eq!(g.edge_endpoints(3)?, (3, 0));
+    assert_eq!(g.outgoing_edge_order(0)?, vec![4, 1]);
+
+    // Contracting a self loop only removes the edge
+    g.add_edge(0, 0, (5, 0))?;
+    assert_eq!(g.contract_edge(5)?, 0);
+    assert_eq!(g.node_count(), 3);
+
+    // Edges which are not allowed after the contraction leaves the graph unchanged
+    let schema = TestSchema::new().allow_self_loops(false);
+    let mut g = TestGraph::new(schema);
+    g.add_nodes((0..3).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0)), (1, 0, (2, 0))], true)?;
+
+    assert!(matches!(
+        g.contract_edge(0),
+        Err(TypedError::InvalidEdgeType(
+            _,
+            _,
+            _,
+            DisAllowedEdge::SelfLoop,
+            _
+        ))
+    ));
+    assert_eq!(g.node_count(), 3);
+    assert_eq!(g.edge_count(), 3);
+    assert_eq!(g.edge_endpoints(0)?, (0, 1));
+    assert_eq!(g.edge_endpoints(1)?, (1, 2));
+    assert_eq!(g.outgoing_edge_order(1)?, vec![1, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_split_node_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::HashMap;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..4).map(|i| (i, 0)), true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 2, (1, 0)),
+            (3, 0, (2, 0)),
+            (0, 0, (3, 0)),
+        ],
+        true,
+    )?;
+
+    // Move the edges with odd ids to the new node
+    assert_eq!(g.split_node(0, (10, 0), |e| e.get_id() % 2 == 0)?, 10);
+    assert_eq!(g.edge_endpoints(0)?, (0, 1));
+    assert_eq!(g.edge_endpoints(1)?, (10, 2));
+    assert_eq!(g.edge_endpoints(2)?, (3, 0));
+    assert_eq!(g.edge_endpoints(3)?, (10, 10));
+
+    assert!(matches!(
+        g.split_node(0, (10, 0), |_| true),
+        Err(TypedError::NodeIdCollision(10))
+    ));
+    assert!(matches!(
+        g.split_node(11, (12, 0), |_| true),
+        Err(TypedError::MissingNode(11))
+    ));
+
+    // Every node of type 0 needs an outgoing edge of type 0
+    let schema =
+        TestSchema::new().endpoint_outgoing_min_quantity(Some(HashMap::from([((0, 0), 1)])));
+    let mut g = TestGraph::new(schema);
+    g.add_nodes((0..3).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 2, (1, 0))], true)?;
+
+    // Moving all the edges leaves the original node without any
+    assert!(matches!(
+        g.split_node(0, (10, 0), |_| false),
+        Err(TypedError::InvalidLowerBound(0, 0, _))
+    ));
+    assert!(!g.has_node(10));
+    assert_eq!(g.outgoing_edge_order(0)?, vec![0, 1]);
+    assert_eq!(g.edge_endpoints(1)?, (0, 2));
+
+    g.split_node(0, (10, 0), |e| e.get_id() == 0)?;
+    assert_eq!(g.edge_endpoints(1)?, (10, 2));
+
+    Ok(())
+}
+
+#[test]
+fn graph_get_all_edges_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes((0..3).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (2, 0, (1, 0)), (0, 0, (2, 0))], true)?;
+
+    // The self loop is returned both as incoming and outgoing
+    assert_eq!(g.get_incoming_and_outgoing(0)?.count(), 4);
+
+    let edges: Vec<_> = g
+        .get_all_edges(0)?
+        .map(|e| (e.get_id(), e.get_direction()))
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            (0, Direction::Outgoing),
+            (2, Direction::Outgoing),
+            (1, Direction::Incoming)
+        ]
+    );
+
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes((0..2).map(|i| (i, 0)), true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 1, (1, 0))], true)?;
+    assert_eq!(g.get_all_edges(1)?.count(), 2);
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graph_deserialize_field_order_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use fake::Dummy;
+
+    let config = CompleteGraph {
+        nodes: 20,
+        node_types: 3,
+        edge_types: 3,
+    };
+
+    let g = TestGraph::dummy(&config);
+
+    // serde_json::Value sorts the keys so the edges comes before the nodes and schema
+    let value = serde_json::to_value(&g)?;
+    let s = value.to_string();
+    assert!(s.find("\"edges\"") < s.find("\"schema\""));
+    let ng: TestGraph = serde_json::from_str(&s)?;
+    g.assert_eq(&ng)?;
+
+    let value = serde_json::to_value(g.serialize_data())?;
+    let ng = TestGraph::deserialize_data(g.get_schema().clone(), &value)?;
+    g.assert_eq(&ng)?;
+
+    // Every field is still required and no other fields are allowed
+    let missing: serde_json::Result<TestGraph> =
+        serde_json::from_str(r#"{"nodes": [], "schema": {}}"#);
+    assert!(missing.is_err());
+    let unknown: serde_json::Result<TestGraph> =
+        serde_json::from_str(r#"{"edges": [], "nodes": [], "other": []}"#);
+    assert!(unknown.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graph_error_schema_name_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let schema = TestSchema::new()
+        .node_blacklist(Some(vec![1]))
+        .edge_blacklist(Some(vec![1]));
+    let mut g = TestGraph::new(schema);
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+
+    // The errors tell which schema rejected the node or edge
+    let err = g.add_node((2, 1)).unwrap_err();
+    assert!(matches!(&err, TypedError::InvalidNodeType(1, _, name) if name == "GenericSchema"));
+    assert!(err.to_string().ends_with("in schema GenericSchema"));
+
+    let err = g.add_edge(0, 1, (0, 1)).unwrap_err();
+    assert!(matches!(&err, TypedError::InvalidEdgeType(1, .., name) if name == "GenericSchema"));
+
+    let mut g = UndirectedTestGraph::new(UndirectedTestSchema(
+        TestSchema::new().edge_blacklist(Some(vec![1])),
+    ));
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    let err = g.add_edge(0, 1, (0, 1)).unwrap_err();
+    assert!(err.to_string().ends_with("in schema UndirectedTestSchema"));
+
+    Ok(())
+}
+
+#[test]
+fn graph_reversed_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = OrderedIncomingTestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 1)], true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (2, 1, (1, 0)),
+            (1, 1, (2, 0)),
+            (0, 2, (3, 1)),
+        ],
+        true,
+    )?;
+
+    let r = g.reversed()?;
+    assert_eq!(r.node_count(), 3);
+    assert_eq!(r.edge_endpoints(0)?, (1, 0));
+    assert_eq!(r.edge_endpoints(2)?, (1, 1));
+    assert_eq!(r.edge_endpoints(3)?, (2, 0));
+
+    // The outgoing order of the reversed graph follows the incoming order of the original graph
+    assert_eq!(r.outgoing_edge_order(1)?, vec![0, 1, 2]);
+    assert_eq!(r.reversed()?.outgoing_edge_order(0)?, vec![0, 3]);
+
+    // Symmetric edges are reversed together with their mirror
+    let mut g = SymmetricTestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (0, 1, (1, 1))], true)?;
+    let r = g.reversed()?;
+    assert_eq!(r.edge_count(), 3);
+    assert_eq!(r.edge_endpoints(1)?, (1, 0));
+    assert_eq!(r.edge_endpoints(!1)?, (0, 1));
+
+    // The schema may not allow the edges to be flipped
+    let schema = TestSchema::new().endpoint_whitelist(Some(vec![(0, 1, 0), (0, 0, 0)]));
+    let mut g = TestGraph::new(schema);
+    g.add_nodes([(0, 0), (1, 0), (2, 1)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0))], true)?;
+    assert!(matches!(
+        g.reversed(),
+        Err(TypedError::ReversedEdgeNotAllowed(
+            1,
+            DisAllowedEdge::InvalidType
+        ))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn graph_map_weights_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = VariantTestGraph::default();
+    g.add_node(VariantNode::Number(NumberNode { id: 0, value: 1 }))?;
+    g.add_node(VariantNode::Name(NameNode {
+        id: 1,
+        name: " a ".to_string(),
+    }))?;
+    g.add_edge(0, 1, VariantEdge::Weight(WeightEdge { id: 0, weight: 0 }))?;
+
+    g.map_nodes(|n| match n {
+        VariantNode::Number(n) => n.value *= 10,
+        VariantNode::Name(n) => n.name = n.name.trim().to_string(),
+    });
+    assert!(matches!(
+        g.get_node(0)?,
+        VariantNode::Number(NumberNode { value: 10, .. })
+    ));
+    assert!(matches!(g.get_node(1)?, VariantNode::Name(n) if n.name == "a"));
+
+    let mut visited = 0;
+    g.map_edges(|_| visited += 1);
+    assert_eq!(visited, 1);
+    assert_eq!(g.edge_endpoints(0)?, (0, 1));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "map_nodes changed the id of a node")]
+#[cfg(debug_assertions)]
+fn graph_map_nodes_id_change_test() {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_node((0, 0)).unwrap();
+    g.map_nodes(|n| n.set_id(1));
+}
+
+#[test]
+fn graph_capacity_metrics_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::with_capacity(TestSchema::new(), 100, 200);
+    g.add_nodes((0..10).map(|i| (i, 0)), true)?;
+    g.add_edges((0..9).map(|i| (i, i + 1, (i, 0))), true)?;
+
+    let capacity = g.capacity();
+    assert!(capacity.nodes >= 100 && capacity.node_lut >= 100);
+    assert!(capacity.edges >= 200 && capacity.edge_lut >= 200);
+    assert_eq!(capacity.outgoing_edges, 9);
+    assert_eq!(capacity.incoming_edges, 9);
+
+    // Shrinking releases the unused capacity but keeps the edges
+    g.shrink_to_fit();
+    let shrunk = g.capacity();
+    assert!(shrunk.nodes < capacity.nodes && shrunk.nodes >= 10);
+    assert!(shrunk.edges < capacity.edges && shrunk.edges >= 9);
+    assert_eq!(shrunk.outgoing_edges, 9);
+    assert_eq!(shrunk.incoming_edges, 9);
+
+    Ok(())
+}
+
+#[test]
+fn graph_neighborhood_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..6 {
+        g.add_node((i, 0))?;
+    }
+
+    // 0 -> 1 -> 2 -> 3 and 4 -> 1, with 5 disconnected
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (1, 2, (1, 0)),
+            (2, 3, (2, 0)),
+            (4, 1, (3, 0)),
+        ],
+        true,
+    )?;
+
+    let outgoing = g.neighborhood(1, 1, Some(Direction::Outgoing))?;
+    assert_eq!(outgoing, HashSet::from([1, 2]));
+
+    let incoming = g.neighborhood(1, 5, Some(Direction::Incoming))?;
+    assert_eq!(incoming, HashSet::from([1, 0, 4]));
+
+    let both = g.neighborhood(2, 2, None)?;
+    assert_eq!(both, HashSet::from([2, 1, 3, 0, 4]));
+
+    // The center is at distance 0
+    assert_eq!(g.neighborhood(5, 3, None)?, HashSet::from([5]));
+    assert_eq!(g.neighborhood(1, 0, None)?, HashSet::from([1]));
+    assert!(g.neighborhood(9, 1, None).is_err());
+
+    // The neighborhood can be extracted as its own graph
+    let region = g.subgraph(g.neighborhood(0, 2, Some(Direction::Outgoing))?)?;
+    assert_eq!(region.node_count(), 3);
+    assert_eq!(region.edge_count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn graph_duplicate_edges_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 1, (1, 0)),
+            (0, 1, (2, 1)),
+            (1, 0, (3, 0)),
+            (1, 2, (4, 2)),
+            (1, 2, (5, 2)),
+            (1, 2, (6, 2)),
+            (2, 2, (7, 0)),
+        ],
+        true,
+    )?;
+
+    // Edges in opposite directions are not parallel in a directed graph
+    assert_eq!(
+        g.duplicate_edges(),
+        vec![(0, 1, 0, vec![0, 1]), (1, 2, 2, vec![4, 5, 6])]
+    );
+
+    // Type 2 edges are allowed to appear twice between the same nodes
+    let duplicates = g.duplicate_edges_by(|edge_type| if *edge_type == 2 { 2 } else { 1 });
+    assert_eq!(
+        duplicates,
+        vec![(0, 1, 0, vec![0, 1]), (1, 2, 2, vec![4, 5, 6])]
+    );
+    let duplicates = g.duplicate_edges_by(|edge_type| if *edge_type == 2 { 3 } else { 1 });
+    assert_eq!(duplicates, vec![(0, 1, 0, vec![0, 1])]);
+
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 0, (1, 0)), (1, 1, (2, 0))], true)?;
+    assert_eq!(g.duplicate_edges(), vec![(0, 1, 0, vec![0, 1])]);
+
+    assert!(TestGraph::default().duplicate_edges().is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graph_serialize_with_order_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (0, 2, (1, 0)),
+            (0, 0, (2, 0)),
+            (1, 2, (3, 0)),
+        ],
+        true,
+    )?;
+    g.set_outgoing_order(0, &[2, 0, 1])?;
+
+    // Reverse the edges the way an intermediary might reorder them
+    let reverse_edges = |mut value: serde_json::Value| {
+        value["edges"].as_array_mut().unwrap().reverse();
+        value
+    };
+
+    let value = reverse_edges(serde_json::to_value(&g)?);
+    let ng: TestGraph = serde_json::from_value(value)?;
+    assert_eq!(ng.outgoing_edge_order(0)?, vec![1, 0, 2]);
+
+    let value = reverse_edges(serde_json::to_value(g.serialize_with_order())?);
+    assert!(value.get("order").is_some());
+    let ng: TestGraph = serde_json::from_value(value)?;
+    assert_eq!(ng.outgoing_edge_order(0)?, vec![2, 0, 1]);
+    g.assert_eq(&ng)?;
+
+    // The order must match the edges of the node
+    let mut value = serde_json::to_value(g.serialize_with_order())?;
+    value["order"][0]["edges"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<TestGraph>(value).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graph_component_stats_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    for i in 0..6 {
+        g.add_node((i, 0))?;
+    }
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (2, 1, (1, 0)),
+            (0, 2, (2, 0)),
+            (3, 4, (3, 0)),
+            (4, 4, (4, 0)),
+        ],
+        true,
+    )?;
+
+    let stats = g.component_stats();
+    assert_eq!(
+        stats,
+        vec![
+            ComponentStat {
+                representative: 0,
+                node_count: 3,
+                edge_count: 3
+            },
+            ComponentStat {
+                representative: 3,
+                node_count: 2,
+                edge_count: 2
+            },
+            ComponentStat {
+                representative: 5,
+                node_count: 1,
+                edge_count: 0
+            },
+        ]
+    );
+    assert_eq!(
+        stats.iter().map(|s| s.edge_count).sum::<usize>(),
+        g.edge_count()
+    );
+
+    // Joining two components merges their counts
+    g.add_edge(1, 3, (5, 0))?;
+    let stats = g.component_stats();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].node_count, 5);
+    assert_eq!(stats[0].edge_count, 6);
+
+    assert!(TestGraph::default().component_stats().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn graph_for_each_outgoing_mut_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = VariantTestGraph::default();
+    for id in 0..3 {
+        g.add_node(VariantNode::Number(NumberNode { id, value: 0 }))?;
+    }
+    g.add_edge(0, 1, VariantEdge::Weight(WeightEdge { id: 0, weight: 1 }))?;
+    g.add_edge(0, 2, VariantEdge::Weight(WeightEdge { id: 1, weight: 2 }))?;
+    g.add_edge(
+        0,
+        2,
+        VariantEdge::Label(LabelEdge {
+            id: 2,
+            label: "a".to_string(),
+        }),
+    )?;
+    g.add_edge(1, 2, VariantEdge::Weight(WeightEdge { id: 3, weight: 3 }))?;
+
+    // Only the edges of the requested type are visited
+    let mut visited = Vec::new();
+    g.for_each_outgoing_mut(0, |edge_id, edge: &mut WeightEdge| {
+        visited.push(edge_id);
+        edge.weight *= 10;
+    })?;
+    visited.sort();
+    assert_eq!(visited, vec![0, 1]);
+
+    assert_eq!(g.get_edge_downcast::<&WeightEdge>(0)?.weight, 10);
+    assert_eq!(g.get_edge_downcast::<&WeightEdge>(1)?.weight, 20);
+    assert_eq!(g.get_edge_downcast::<&WeightEdge>(3)?.weight, 3);
+
+    g.for_each_outgoing_mut(0, |_, edge: &mut LabelEdge| edge.label.push('b'))?;
+    assert_eq!(g.get_edge_downcast::<&LabelEdge>(2)?.label, "ab");
+
+    assert!(g
+        .for_each_outgoing_mut(9, |_, _: &mut WeightEdge| {})
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graph_string_key_test() -> crate::generic_graph::GenericResult<(), String, String, usize, usize>
+{
+    use crate::generic_graph::*;
+
+    let mut g: GenericGraph<String, String, usize, usize> = GenericGraph::default();
+    for id in ["a", "b", "c"] {
+        g.add_node((id.to_string(), 0))?;
+    }
+    g.add_edge("a".to_string(), "b".to_string(), ("ab".to_string(), 0))?;
+    g.add_edge("b".to_string(), "c".to_string(), ("bc".to_string(), 0))?;
+    g.add_edge("a".to_string(), "c".to_string(), ("ac".to_string(), 0))?;
+
+    assert_eq!(
+        g.outgoing_edge_order("a".to_string())?,
+        vec!["ab".to_string(), "ac".to_string()]
+    );
+    assert_eq!(
+        g.edge_endpoints("bc".to_string())?,
+        ("b".to_string(), "c".to_string())
+    );
+
+    // Errors carry the missing key
+    assert!(matches!(
+        g.get_node("d".to_string()),
+        Err(TypedError::MissingNode(id)) if id == "d"
+    ));
+
+    g.remove_node("b".to_string())?;
+    assert_eq!(g.node_count(), 2);
+    assert_eq!(g.edge_ids().collect::<Vec<_>>(), vec!["ac".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn graph_add_edge_default_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+
+    assert_eq!(g.add_edge_default(0, 1, 0, 2)?, 0);
+    assert_eq!(g.get_edge(0)?, &TestEdge::from((0, 2)));
+    assert_eq!(g.edge_endpoints(0)?, (0, 1));
+
+    // Existing edges are updated like with add_edge
+    g.add_edge_default(1, 0, 0, 3)?;
+    assert_eq!(g.get_edge(0)?.get_type(), 3);
+    assert_eq!(g.edge_endpoints(0)?, (1, 0));
+    assert_eq!(g.edge_count(), 1);
+
+    assert!(g.add_edge_default(0, 9, 1, 0).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graph_add_node_strict_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_node_strict((0, 0))?;
+    g.add_node_strict((1, 1))?;
+    g.add_edge(0, 1, (0, 0))?;
+
+    // Updating a node with the same type is allowed
+    g.add_node_strict((1, 1))?;
+    assert_eq!(g.node_count(), 2);
+
+    // add_node would replace the node since the edge still allows the new type
+    assert!(matches!(
+        g.add_node_strict((1, 2)),
+        Err(TypedError::NodeTypeChanged(1, 1, 2))
+    ));
+    assert_eq!(g.get_node(1)?.get_type(), 1);
+
+    g.add_node((1, 2))?;
+    assert_eq!(g.get_node(1)?.get_type(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn graph_upsert_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    assert_eq!(g.upsert_node((0, 0))?, Upsert::Inserted(0));
+    assert_eq!(g.upsert_node((1, 0))?, Upsert::Inserted(1));
+    assert_eq!(g.upsert_node((1, 1))?, Upsert::Updated(1));
+    assert_eq!(g.get_node(1)?.get_type(), 1);
+
+    assert_eq!(g.upsert_edge(0, 1, (0, 0))?, Upsert::Inserted(0));
+    let moved = g.upsert_edge(1, 0, (0, 0))?;
+    assert!(!moved.is_inserted());
+    assert_eq!(moved.into_id(), 0);
+    assert_eq!(g.edge_endpoints(0)?, (1, 0));
+
+    // Failing to add the edge is still an error
+    assert!(g.upsert_edge(0, 9, (1, 0)).is_err());
+    assert!(!g.has_edge(1));
+
+    Ok(())
+}
+
+#[test]
+fn graph_remove_node_with_edges_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges(
+        [
+            (0, 1, (0, 0)),
+            (1, 0, (1, 0)),
+            (0, 0, (2, 1)),
+            (0, 2, (3, 0)),
+            (1, 2, (4, 0)),
+        ],
+        true,
+    )?;
+
+    // The self loop is only returned once
+    let (node, edges) = g.remove_node_with_edges(0)?;
+    assert_eq!(node, TestNode::from((0, 0)));
+    let edge_ids: Vec<usize> = edges.iter().map(|e| e.get_id()).collect();
+    assert_eq!(edge_ids, vec![0, 2, 3, 1]);
+    assert_eq!(edges[1].get_type(), 1);
+
+    assert_eq!(g.edge_ids().collect::<Vec<_>>(), vec![4]);
+    assert!(g.remove_node_with_edges(0).is_err());
+
+    // The removed node can be moved to another graph together with its edges
+    let mut other = TestGraph::default();
+    other.add_nodes([(1, 0), (2, 0)], true)?;
+    let (node, edges) = g.remove_node_with_edges(2)?;
+    other.add_node(node)?;
+    for edge in edges {
+        other.add_edge(1, 2, edge)?;
+    }
+    assert_eq!(other.edge_endpoints(4)?, (1, 2));
+
+    Ok(())
+}
+
+#[test]
+fn graph_with_hasher_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
+
+    type FixedHasher = BuildHasherDefault<DefaultHasher>;
+    type FixedGraph = TypedGraph<usize, usize, TestSchema, FixedHasher>;
+
+    let mut g = FixedGraph::with_hasher(TestSchema::new(), FixedHasher::default());
+    g.add_nodes([(0, 0), (1, 0), (2, 1)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0))], true)?;
+    assert_eq!(g.get_node(2)?.get_type(), 1);
+    assert_eq!(g.edge_endpoints(1)?, (1, 2));
+
+    let path: Vec<usize> = g
+        .walker_from_many([0], false)?
+        .progress(|n, g| Ok(g.get_outgoing(n.get_id())?.map(|e| ((), e.get_outer()))))
+        .many::<Vec<_>>()?
+        .into_iter()
+        .map(|n| n.get_id())
+        .collect();
+    assert_eq!(path, vec![1]);
+
+    g.remove_node(1)?;
+    assert_eq!(g.edge_count(), 0);
+
+    let g = FixedGraph::with_capacity_and_hasher(TestSchema::new(), 10, 10, FixedHasher::default());
+    assert!(g.capacity().nodes >= 10);
+
+    Ok(())
+}
 
-        // Step 2: Deserialize the nodes
-        let (nodes_field, nodes): (&'de str, Vec<N>) = access
-            .next_entry()?
-            .ok_or_else(|| M::Error::missing_field("nodes"))?;
-        if nodes_field != "nodes" {
-            return Err(M::Error::unknown_field(nodes_field, &["nodes"]));
-        }
+#[test]
+fn graph_with_seeded_hasher_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
 
-        // Check for id collisions and propper node types
-        for n in nodes {
-            g.add_node(n).map_err(|e| M::Error::custom(e))?;
-        }
+    // A hasher which can only be created from a seed, so there is no Default to fall back on
+    #[derive(Clone)]
+    struct SeededHasher(u64);
 
-        // Step 3: Deserialize the edges
-        let (edges_field, edges): (&'de str, Vec<EdgeReadDTO<NK, E>>) = access
-            .next_entry()?
-            .ok_or_else(|| M::Error::missing_field("edges"))?;
-        if edges_field != "edges" {
-            return Err(M::Error::unknown_field(edges_field, &["edges"]));
-        }
+    impl BuildHasher for SeededHasher {
+        type Hasher = DefaultHasher;
 
-        // Check for id collisions and propper edge types
-        for e in edges {
-            g.add_edge(e.source, e.target, e.weight)
-                .map_err(|e| M::Error::custom(e))?;
+        fn build_hasher(&self) -> DefaultHasher {
+            let mut hasher = DefaultHasher::new();
+            hasher.write_u64(self.0);
+            hasher
         }
-
-        Ok(g)
     }
-}
 
-/// Use the visitor to deserialize the TypedGraph
-impl<'de, NK, EK, N, E, S> Deserialize<'de> for TypedGraph<NK, EK, S>
-where
-    NK: Key + Display + Deserialize<'de>,
-    EK: Key + Display + Deserialize<'de>,
-    N: NodeExt<NK> + Deserialize<'de>,
-    E: EdgeExt<EK> + Deserialize<'de>,
-    S: SchemaExt<NK, EK, N = N, E = E> + Deserialize<'de>,
-{
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_map(TypedGraphVisitor {
-            nk: PhantomData,
-            ek: PhantomData,
-            n: PhantomData,
-            e: PhantomData,
-            s: PhantomData,
-        })
-    }
+    let mut g =
+        TypedGraph::<usize, usize, TestSchema, _>::with_hasher(TestSchema::new(), SeededHasher(7));
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.node_entry(2)?.or_insert_with(|| (2, 1))?;
+    g.edge_entry(0)?.or_insert_with(0, 1, || (0, 0))?;
+    g.add_edge(1, 2, (1, 0))?;
+
+    let path: Vec<usize> = g
+        .walker_from_many([0], false)?
+        .progress(|n, g| Ok(g.get_outgoing(n.get_id())?.map(|e| ((), e.get_outer()))))
+        .many::<Vec<_>>()?
+        .into_iter()
+        .map(|n| n.get_id())
+        .collect();
+    assert_eq!(path, vec![1]);
+    assert_eq!(g.shortest_path_edges(0, 2, |_| 1)?, Some((2, vec![0, 1])));
+    assert!(ComponentIndex::new(&g)?.same_component(&g, 0, 2)?);
+
+    Ok(())
 }
 
+#[cfg(feature = "serde")]
 #[test]
-fn graph_compose_test() -> crate::test::TestResult<()> {
+fn graph_with_hasher_serde_test() -> crate::test::TestResult<()> {
     use crate::test::*;
-    use fake::Dummy;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::BuildHasherDefault;
 
-    let config = CompleteGraph {
-        nodes: 100,
-        node_types: 5,
-        edge_types: 5,
-    };
+    type FixedGraph = TypedGraph<usize, usize, TestSchema, BuildHasherDefault<DefaultHasher>>;
 
-    let g = TestGraph::dummy(&config);
-    let s = serde_json::to_string_pretty(&g)?;
+    let mut g = FixedGraph::default();
+    g.add_nodes([(0, 0), (1, 1)], true)?;
+    g.add_edge(0, 1, (0, 0))?;
+
+    // The graph is serialized the same way regardless of the hasher
+    let s = serde_json::to_string(&g)?;
     let ng: TestGraph = serde_json::from_str(&s)?;
-    g.assert_eq(&ng)?;
+    assert_eq!(serde_json::to_string(&ng)?, s);
+    let ng: FixedGraph = serde_json::from_str(&s)?;
+    assert_eq!(ng.edge_endpoints(0)?, (0, 1));
 
     Ok(())
 }
 
 #[test]
-fn graph_quantity_test_outgoing() -> crate::test::TestResult<()> {
+fn graph_into_parts_test() -> crate::test::TestResult<()> {
     use crate::test::*;
 
-    const NODE_TYPE: usize = 0;
-    const NODE_TYPE1: usize = 1;
-    const NODE_TYPE2: usize = 2;
-    const EDGE_TYPE1: usize = 0;
-    const EDGE_TYPE2: usize = 2;
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 1), (2, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 1))], true)?;
+    g.remove_node(2)?;
 
-    let s = TestSchema::new()
-        .endpoint_outgoing_max_quantity(Some(
-            vec![
-                // Set a max capacity of 2 edges of type EDGE_TYPE1 from NODE_TYPE to NODE_TYPE1
-                ((NODE_TYPE, EDGE_TYPE1), 2),
-            ]
-            .into_iter()
-            .collect(),
-        ))
-        .endpoint_incoming_max_quantity(Some(
-            vec![
-                // Set a max capacity of 2 edges of type EDGE_TYPE1 from NODE_TYPE to NODE_TYPE1
-                ((NODE_TYPE2, EDGE_TYPE1), 3),
-            ]
-            .into_iter()
-            .collect(),
-        ));
-    let mut g = TestGraph::new(s);
+    let nodes: Vec<_> = g.nodes().cloned().collect();
+    let edges: Vec<_> = g.edges().cloned().collect();
+    assert_eq!(g.clone().into_nodes().collect::<Vec<_>>(), nodes);
+    assert_eq!(g.clone().into_edges().collect::<Vec<_>>(), edges);
 
-    let source_id = g.add_node((0, NODE_TYPE))?;
-    let target_id = g.add_node((1, NODE_TYPE1))?;
+    let (schema, owned_nodes, owned_edges) = g.into_parts();
+    assert_eq!(owned_nodes.collect::<Vec<_>>(), nodes);
+    assert_eq!(owned_edges.collect::<Vec<_>>(), edges);
 
-    // First outgoing edge
-    g.add_edge(source_id, target_id, (0, EDGE_TYPE1))?;
-    // Second outgoing edge
-    g.add_edge(source_id, target_id, (1, EDGE_TYPE1))?;
-    // Third outgoing edge
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Added third edge");
+    // The parts can be used to build the graph again
+    let mut g = TestGraph::new(schema);
+    g.add_nodes(nodes, true)?;
+    g.add_edge(0, 1, edges[0])?;
+    assert_eq!(g.edge_endpoints(0)?, (0, 1));
 
-    // Try to add third edge by updating an existing edge of another type
-    g.add_edge(source_id, target_id, (2, EDGE_TYPE2))?;
+    Ok(())
+}
 
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Updating edge");
-    g.remove_edge(2)?;
+#[test]
+fn graph_debug_assert_consistent_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
 
-    // Try to add third edge by updating an existing edge of another type going in the opposit direction
-    g.add_edge(target_id, source_id, (2, EDGE_TYPE2))?;
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 0)], true)?;
+    g.add_edges([(0, 0, (0, 0)), (0, 1, (1, 0)), (1, 0, (2, 0))], true)?;
+    g.debug_assert_consistent();
+
+    // Self loops are listed as both incoming and outgoing edges of the same node
+    g.remove_node(0)?;
+    g.debug_assert_consistent();
+
+    g.add_node((0, 0))?;
+    g.add_edge(0, 0, (0, 0))?;
+    g.add_edge(2, 1, (1, 0))?;
+    g.add_node((1, 1))?;
+    g.debug_assert_consistent();
+
+    g.remove_edge(0)?;
+    g.shrink_to_fit();
+    g.debug_assert_consistent();
+
+    // Undirected edges are both incoming and outgoing edges of both endpoints
+    let mut g = UndirectedTestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 1, (1, 0))], true)?;
+    g.debug_assert_consistent();
+    g.remove_node(1)?;
+    g.debug_assert_consistent();
 
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Updating edge reversed");
-    g.remove_edge(2)?;
+    Ok(())
+}
 
-    // Try to add third edge by updating an existing edge of another type coming from different nodes
-    let source_id2 = g.add_node((2, NODE_TYPE2))?;
-    let target_id2 = g.add_node((3, NODE_TYPE2))?;
-    g.add_edge(target_id2, source_id2, (2, EDGE_TYPE2))?;
+#[test]
+#[should_panic(expected = "edge 0 is not an incoming edge of its target")]
+#[cfg(debug_assertions)]
+fn graph_debug_assert_consistent_broken_test() {
+    use crate::test::*;
 
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Updating completly different edge");
-    g.remove_edge(2)?;
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0)], true).unwrap();
+    g.add_edge(0, 1, (0, 0)).unwrap();
 
-    // Make sure the limit only works in one direction
-    // First incoming edge
-    g.add_edge(target_id, source_id, (10, EDGE_TYPE1))?;
-    // Second incoming edge
-    g.add_edge(target_id, source_id, (11, EDGE_TYPE1))?;
-    // Third incoming edge
-    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
-    // Fourth incoming edge
-    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
+    let target = g.get_node_key(&1).unwrap();
+    g.nodes[target].incoming_edges.clear();
+    g.debug_assert_consistent();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn graph_serialize_compact_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use bincode::Options;
+
+    // Large ids are only written once for every node
+    let offset = 1 << 60;
+    let mut g = TestGraph::default();
+    g.add_nodes((0..10).map(|i| (offset + i, i % 2)), true)?;
+    g.add_edges((0..9).map(|i| (offset + i, offset + i + 1, (i, 0))), true)?;
+    g.add_edge(offset + 3, offset, (9, 1))?;
+    g.set_outgoing_order(offset + 3, &[9, 3])?;
+
+    let s = serde_json::to_string(&g.serialize_compact())?;
+    assert!(s.len() < serde_json::to_string(&g)?.len());
+
+    let mut deserializer = serde_json::Deserializer::from_str(&s);
+    let ng = TestGraph::deserialize_compact(&mut deserializer)?;
+    g.assert_eq(&ng)?;
+    assert_eq!(ng.outgoing_edge_order(offset + 3)?, vec![9, 3]);
+
+    let bytes = bincode::serialize(&g.serialize_compact()).unwrap();
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes();
+    let mut deserializer = bincode::Deserializer::from_slice(&bytes, options);
+    let ng = TestGraph::deserialize_compact(&mut deserializer).unwrap();
+    g.assert_eq(&ng)?;
+
+    // Edges may only refer to existing nodes
+    let s = s.replacen("[0,1,", "[0,10,", 1);
+    let mut deserializer = serde_json::Deserializer::from_str(&s);
+    let err = TestGraph::deserialize_compact(&mut deserializer).unwrap_err();
+    assert!(err.to_string().contains("node index 10"));
 
     Ok(())
 }
 
 #[test]
-fn graph_quantity_test_incoming() -> crate::test::TestResult<()> {
+fn graph_get_or_add_node_test() -> crate::test::TestResult<()> {
     use crate::test::*;
 
-    const NODE_TYPE: usize = 0;
-    const NODE_TYPE1: usize = 1;
-    const NODE_TYPE2: usize = 2;
-    const EDGE_TYPE1: usize = 0;
-    const EDGE_TYPE2: usize = 2;
+    let mut g = TestGraph::new(TestSchema::new().node_whitelist(Some(vec![0, 1])));
 
-    let s = TestSchema::new()
-        .endpoint_incoming_max_quantity(Some(
-            vec![
-                // Set a max capacity of 2 edges of type EDGE_TYPE1 from NODE_TYPE to NODE_TYPE1
-                ((NODE_TYPE, EDGE_TYPE1), 2),
-            ]
-            .into_iter()
-            .collect(),
-        ));
-    let mut g = TestGraph::new(s);
+    let node = g.get_or_add_node((0, 0))?;
+    assert_eq!(node, &TestNode::from((0, 0)));
+    assert_eq!(g.node_count(), 1);
 
-    let source_id = g.add_node((0, NODE_TYPE1))?;
-    let target_id = g.add_node((1, NODE_TYPE))?;
+    // The existing node is returned without changing its type
+    let node = g.get_or_add_node((0, 1))?;
+    assert_eq!(node.get_type(), 0);
+    assert_eq!(g.get_node(0)?.get_type(), 0);
+    assert_eq!(g.node_count(), 1);
 
-    // First incoming edge
-    g.add_edge(source_id, target_id, (0, EDGE_TYPE1))?;
-    // Second incoming edge
-    g.add_edge(source_id, target_id, (1, EDGE_TYPE1))?;
-    // Third incoming edge
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Added third edge");
+    // New nodes are checked against the schema
+    assert!(g.get_or_add_node((1, 2)).is_err());
+    assert!(!g.has_node(1));
 
-    // Try to add third edge by updating an existing edge of another type
-    g.add_edge(source_id, target_id, (2, EDGE_TYPE2))?;
+    g.get_or_add_node((1, 1))?;
+    g.add_edge(0, 1, (0, 0))?;
+    assert_eq!(g.nodes_of_type(1).collect::<Vec<_>>(), vec![1]);
+    g.debug_assert_consistent();
 
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Updating edge");
-    g.remove_edge(2)?;
+    Ok(())
+}
 
-    // Try to add third edge by updating an existing edge of another type going in the opposit direction
-    g.add_edge(target_id, source_id, (2, EDGE_TYPE2))?;
+#[test]
+fn graph_quantity_retype_through_mut_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::HashMap;
 
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Updating edge reversed");
-    g.remove_edge(2)?;
+    // At most one edge of type 0 from a node of type 0 to nodes of the same type
+    let schema =
+        TestSchema::new().endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 0), 1)])));
+    let mut g = TestGraph::new(schema);
 
-    // Try to add third edge by updating an existing edge of another type coming from different nodes
-    let source_id2 = g.add_node((2, NODE_TYPE2))?;
-    let target_id2 = g.add_node((3, NODE_TYPE2))?;
-    g.add_edge(target_id2, source_id2, (2, EDGE_TYPE2))?;
+    g.add_nodes([(0, 0), (1, 1), (2, 2)], true)?;
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(0, 2, (1, 1))?;
 
-    let e = g.add_edge(source_id, target_id, (2, EDGE_TYPE1));
-    assert!(e.is_err(), "Updating completly different edge");
+    // Retyping the edge gives node 0 two edges of type 0 to nodes of type 1 and 2
+    g.get_edge_mut(1)?.1 = 0;
+    g.debug_assert_consistent();
+
+    // Removing the other edge must not leave a count behind for the type the edge had before
+    g.remove_edge(0)?;
+    g.debug_assert_consistent();
+    g.add_edge(0, 1, (2, 0))?;
+    g.debug_assert_consistent();
+
+    // Retyping the node gives node 0 two edges of type 0 to nodes of type 1
+    g.get_node_mut(2)?.1 = 1;
     g.remove_edge(2)?;
+    g.debug_assert_consistent();
+    assert!(matches!(
+        g.add_edge(0, 1, (3, 0)),
+        Err(TypedError::InvalidEdgeType(
+            0,
+            0,
+            1,
+            DisAllowedEdge::ToManyOutgoing,
+            _
+        ))
+    ));
 
-    // Make sure the limit only works in one direction
-    // First incoming edge
-    g.add_edge(target_id, source_id, (10, EDGE_TYPE1))?;
-    // Second incoming edge
-    g.add_edge(target_id, source_id, (11, EDGE_TYPE1))?;
-    // Third incoming edge
-    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
-    // Fourth incoming edge
-    g.add_edge(target_id, source_id, (12, EDGE_TYPE1))?;
+    // The same goes for the other ways of getting a mutable reference
+    g.map_nodes(|node| {
+        if node.0 == 2 {
+            node.1 = 2;
+        }
+    });
+    g.add_edge(0, 1, (3, 0))?;
+    g.debug_assert_consistent();
+
+    g.node_entry(1)?.and_modify(|node| node.1 = 2);
+    g.remove_edge(1)?;
+    g.debug_assert_consistent();
+    assert!(g.add_edge(0, 2, (4, 0)).is_err());
 
     Ok(())
 }
 
 #[test]
-fn edge_order() -> crate::test::TestResult<()> {
+fn graph_type_index_retype_through_mut_test() -> crate::test::TestResult<()> {
     use crate::test::*;
 
-    let s = TestSchema::new();
-    let mut g = TestGraph::new(s);
+    let mut g = TestGraph::default();
+    g.add_nodes([(0, 0), (1, 0), (2, 1)], true)?;
+    g.add_edges([(0, 1, (0, 0)), (1, 2, (1, 0))], true)?;
+
+    // The new types are seen right away, before the graph is changed again
+    g.get_node_mut(1)?.1 = 1;
+    g.get_edge_mut(1)?.1 = 2;
+    g.debug_assert_consistent();
+
+    let mut nodes: Vec<usize> = g.nodes_of_type(1).collect();
+    nodes.sort();
+    assert_eq!(nodes, vec![1, 2]);
+    assert_eq!(g.nodes_of_type(0).collect::<Vec<_>>(), vec![0]);
+    assert_eq!((g.count_nodes_of_type(0), g.count_nodes_of_type(1)), (1, 2));
+    assert_eq!(g.edges_of_type(2).collect::<Vec<_>>(), vec![1]);
+    assert_eq!((g.count_edges_of_type(0), g.count_edges_of_type(2)), (1, 1));
+
+    let (mut node_counts, mut edge_counts) = g.type_histogram();
+    node_counts.sort();
+    edge_counts.sort();
+    assert_eq!(node_counts, vec![(0, 1), (1, 2)]);
+    assert_eq!(edge_counts, vec![(0, 1), (2, 1)]);
+
+    // Changing the type back and forth leaves the node where it was
+    g.get_node_mut(0)?.1 = 1;
+    g.get_node_mut(0)?.1 = 0;
+    assert_eq!(g.count_nodes_of_type(0), 1);
+
+    // The index is repaired once the graph is changed
+    g.add_node((3, 0))?;
+    g.debug_assert_consistent();
+    let mut nodes: Vec<usize> = g.nodes_of_type(0).collect();
+    nodes.sort();
+    assert_eq!(nodes, vec![0, 3]);
+    assert_eq!(g.count_nodes_of_type(1), 2);
+
+    // Removing a retyped node removes it from the type it was changed to
+    g.get_node_mut(2)?.1 = 0;
+    g.remove_node(2)?;
+    g.debug_assert_consistent();
+    assert_eq!((g.count_nodes_of_type(0), g.count_nodes_of_type(1)), (2, 1));
 
-    let a = g.add_node((0, 0))?;
-    let b = g.add_node((1, 0))?;
+    Ok(())
+}
 
-    for i in 0..5 {
-        g.add_edge(a, b, (i, i))?;
+#[test]
+fn graph_symmetric_edge_update_atomic_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+    use std::collections::HashMap;
+
+    // Every node can have at most one outgoing edge of type 1
+    let schema =
+        TestSchema::new().endpoint_outgoing_max_quantity(Some(HashMap::from([((0, 1), 1)])));
+    let mut g = SymmetricTestGraph::new(SymmetricTestSchema(schema));
+    g.add_nodes([(0, 0), (1, 0), (2, 0), (3, 0)], true)?;
+    g.add_edge(0, 1, (0, 0))?;
+    g.add_edge(0, 1, (1, 1))?;
+    g.add_edge(2, 3, (2, 1))?;
+
+    let edges = |g: &SymmetricTestGraph| -> crate::test::TestResult<Vec<_>> {
+        let mut edges: Vec<_> = g
+            .edges_full()
+            .map(|e| (e.get_id(), e.get_source(), e.get_target(), e.get_type()))
+            .collect();
+        edges.sort();
+        Ok(edges)
+    };
+    let before = edges(&g)?;
+
+    // Moving the edge is allowed but its mirror would be the second outgoing edge of node 2
+    assert!(g.add_edge(0, 2, (1, 1)).is_err());
+    g.debug_assert_consistent();
+    assert_eq!(edges(&g)?, before);
+    assert_eq!(g.outgoing_edge_order(0)?, vec![0, 1]);
+    assert_eq!(g.edge_endpoints(1)?, (0, 1));
+    assert_eq!(g.edge_endpoints(!1)?, (1, 0));
+
+    // The same goes for an edge which was not symmetric before
+    g.add_node((4, 0))?;
+    g.add_edge(4, 1, (4, 0))?;
+    let before = edges(&g)?;
+    assert!(g.add_edge(4, 1, (4, 1)).is_err());
+    g.debug_assert_consistent();
+    assert_eq!(edges(&g)?, before);
+    assert_eq!(g.get_edge(4)?.1, 0);
+
+    Ok(())
+}
+
+#[test]
+fn graph_replace_node_same_type_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
+
+    /// Edges are only allowed to go to number nodes with a value of at least 10
+    #[derive(Default)]
+    struct ValueSchema(VariantTestSchema);
+
+    impl SchemaExt<usize, usize> for ValueSchema {
+        type N = VariantNode;
+        type E = VariantEdge;
+
+        fn name(&self) -> String {
+            "ValueSchema".to_string()
+        }
+
+        fn allow_node(&self, node_ty: usize) -> Result<(), DisAllowedNode> {
+            self.0.allow_node(node_ty)
+        }
+
+        fn allow_edge(
+            &self,
+            outgoing_edge_count: usize,
+            incoming_edge_count: usize,
+            edge_ty: usize,
+            source: usize,
+            target: usize,
+        ) -> Result<(), DisAllowedEdge> {
+            self.0.allow_edge(
+                outgoing_edge_count,
+                incoming_edge_count,
+                edge_ty,
+                source,
+                target,
+            )
+        }
+
+        fn allow_edge_full(
+            &self,
+            outgoing_edge_count: usize,
+            incoming_edge_count: usize,
+            weight: &Self::E,
+            source: &Self::N,
+            target: &Self::N,
+        ) -> Result<(), DisAllowedEdge> {
+            if let VariantNode::Number(NumberNode { value, .. }) = target {
+                if *value < 10 {
+                    return Err(DisAllowedEdge::InvalidType);
+                }
+            }
+
+            self.allow_edge(
+                outgoing_edge_count,
+                incoming_edge_count,
+                weight.get_type(),
+                source.get_type(),
+                target.get_type(),
+            )
+        }
     }
 
-    let ids: Vec<usize> = g.get_outgoing(a)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+    let number = |id, value| VariantNode::Number(NumberNode { id, value });
 
-    // Swap before when after
-    let mut ng = g.clone();
-    ng.move_edge_order(3, 1, InsertPosition::Before)?;
-    let ids: Vec<usize> = ng.get_outgoing(0)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 3, 1, 2, 4]);
+    let mut g = TypedGraph::<usize, usize, ValueSchema>::default();
+    g.add_node(number(0, 10)).unwrap();
+    g.add_node(number(1, 10)).unwrap();
+    g.add_edge(0, 1, VariantEdge::Weight(WeightEdge { id: 0, weight: 0 }))
+        .unwrap();
 
-    // Swap after when after
-    let mut ng = g.clone();
-    ng.move_edge_order(3, 1, InsertPosition::After)?;
-    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 1, 3, 2, 4]);
+    // Replacing the node with one of the same type is allowed as long as the edges still are
+    g.add_node(number(1, 20)).unwrap();
+    assert_eq!(g.get_node(1).unwrap(), &number(1, 20));
 
-    // Swap before start
-    let mut ng = g.clone();
-    ng.move_edge_order(3, 0, InsertPosition::Before)?;
-    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[3, 0, 1, 2, 4]);
+    // The existing edge does not allow the new weight, so the old one is kept
+    assert!(g.add_node(number(1, 5)).is_err());
+    assert_eq!(g.get_node(1).unwrap(), &number(1, 20));
+    assert!(g.validate().is_ok());
 
-    // Swap after end
-    let mut ng = g.clone();
-    ng.move_edge_order(3, 4, InsertPosition::After)?;
-    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 1, 2, 4, 3]);
+    // Nodes without incoming edges can still take any value
+    g.add_node(number(0, 5)).unwrap();
+    assert_eq!(g.get_node(0).unwrap(), &number(0, 5));
 
-    // Swap before self is identity
-    let mut ng = g.clone();
-    ng.move_edge_order(2, 2, InsertPosition::Before)?;
-    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+    Ok(())
+}
 
-    // Swap after self is identity
-    let mut ng = g.clone();
-    ng.move_edge_order(2, 2, InsertPosition::After)?;
-    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+#[cfg(feature = "serde")]
+#[test]
+fn graph_serialize_sorted_quantities_test() -> crate::test::TestResult<()> {
+    use crate::test::*;
 
-    // Swap before when before
-    let mut ng = g.clone();
-    ng.move_edge_order(1, 2, InsertPosition::Before)?;
-    let ids: Vec<usize> = ng.get_outgoing(0)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 1, 2, 3, 4]);
+    // Every HashMap gets its own random order, so enough entries are added for the orders to differ
+    let quantities =
+        || -> HashMap<(usize, usize), usize> { (0..20).map(|i| ((i, i % 3), i + 1)).collect() };
+    let schema = || {
+        TestSchema::new()
+            .endpoint_outgoing_max_quantity(Some(quantities()))
+            .endpoint_incoming_max_quantity(Some(quantities()))
+            .endpoint_outgoing_min_quantity(Some(HashMap::new()))
+            .endpoint_incoming_min_quantity(Some(HashMap::new()))
+    };
 
-    // Swap after when before
-    let mut ng = g.clone();
-    ng.move_edge_order(1, 2, InsertPosition::After)?;
-    let ids: Vec<usize> = ng.get_outgoing(a)?.map(|e| e.get_type()).collect();
-    assert_eq!(ids, &[0, 2, 1, 3, 4]);
+    let mut g1 = TestGraph::new(schema());
+    let mut g2 = TestGraph::new(schema());
+    for g in [&mut g1, &mut g2] {
+        g.add_node((0, 0))?;
+        g.add_node((1, 1))?;
+        g.add_edge(0, 1, (0, 0))?;
+    }
+
+    let s1 = bincode::serialize(&g1.serialize_sorted()).unwrap();
+    let s2 = bincode::serialize(&g2.serialize_sorted()).unwrap();
+    assert_eq!(s1, s2);
+
+    // The deserialized graph has new HashMaps, but is still serialized the same way
+    let ng: TestGraph = bincode::deserialize(&s1).unwrap();
+    let s3 = bincode::serialize(&ng.serialize_sorted()).unwrap();
+    assert_eq!(s1, s3);
+    g1.assert_eq(&ng)?;
 
     Ok(())
-}
\ No newline at end of file
+}
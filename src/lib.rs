@@ -8,3 +8,5 @@ mod either;
 pub use either::*;
 pub use graph::*;
 pub use typed_error::*;
+#[cfg(feature = "derive")]
+pub use typed_graph_derive::{schema, GraphEdge, GraphNode};
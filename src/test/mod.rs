@@ -1,5 +1,6 @@
 mod test_dummy;
 mod test_graph;
+#[cfg(feature = "serde")]
 mod test_py;
 
 pub use test_dummy::*;
@@ -1,5 +1,377 @@
-use crate::generic_graph::{GenericGraph, GenericResult, GenericSchema};
+use crate::generic_graph::{GenericEdge, GenericGraph, GenericNode, GenericResult, GenericSchema};
+use crate::{
+    DefaultMigrationHandler, DisAllowedEdge, DisAllowedNode, Downcast, DowncastMut, EdgeExt, Id,
+    MigrateSchema, Migration, NodeExt, ReverseSpec, SchemaExt, SchemaResult, Typed, TypedError,
+    TypedGraph,
+};
 
 pub type TestSchema = GenericSchema<usize, usize>;
 pub type TestGraph = GenericGraph<usize, usize, usize, usize>;
 pub type TestResult<T> = GenericResult<T, usize, usize, usize, usize>;
+
+pub type TestNode = GenericNode<usize, usize>;
+pub type TestEdge = GenericEdge<usize, usize>;
+
+pub type UndirectedTestGraph = TypedGraph<usize, usize, UndirectedTestSchema>;
+
+/// Same as the TestSchema but the edges has no direction
+#[derive(Default, Clone, Debug)]
+pub struct UndirectedTestSchema(pub TestSchema);
+
+impl SchemaExt<usize, usize> for UndirectedTestSchema {
+    type N = GenericNode<usize, usize>;
+    type E = GenericEdge<usize, usize>;
+
+    const DIRECTED: bool = false;
+
+    fn name(&self) -> String {
+        "UndirectedTestSchema".to_string()
+    }
+
+    fn allow_node(&self, node_ty: usize) -> Result<(), DisAllowedNode> {
+        SchemaExt::<usize, usize>::allow_node(&self.0, node_ty)
+    }
+
+    fn allow_edge(
+        &self,
+        outgoing_edge_count: usize,
+        incoming_edge_count: usize,
+        edge_ty: usize,
+        source: usize,
+        target: usize,
+    ) -> Result<(), DisAllowedEdge> {
+        SchemaExt::<usize, usize>::allow_edge(
+            &self.0,
+            outgoing_edge_count,
+            incoming_edge_count,
+            edge_ty,
+            source,
+            target,
+        )
+    }
+}
+
+/// Migrating to the TestSchema gives the edges a direction and removes everything of type 1
+impl MigrateSchema<usize, usize, TestSchema> for UndirectedTestSchema {
+    fn update_node(&self, _new_schema: &TestSchema, node: TestNode) -> Option<TestNode> {
+        (node.get_type() != 1).then_some(node)
+    }
+
+    fn update_edge(
+        &self,
+        _new_schema: &TestSchema,
+        edge: GenericEdge<usize, usize>,
+    ) -> Option<GenericEdge<usize, usize>> {
+        (edge.get_type() != 1).then_some(edge)
+    }
+
+    fn update_node_type(&self, _new_schema: &TestSchema, node_type: usize) -> Option<usize> {
+        (node_type != 1).then_some(node_type)
+    }
+
+    fn update_edge_type(&self, _new_schema: &TestSchema, edge_type: usize) -> Option<usize> {
+        (edge_type != 1).then_some(edge_type)
+    }
+}
+
+impl Migration<usize, usize, TestSchema> for UndirectedTestSchema {
+    type Handler = DefaultMigrationHandler;
+}
+
+/// Walker step following all outgoing edges of a node
+///
+/// The type of the traversed edge is returned as the state addition
+pub fn move_forward<'a>(
+    n: &'a TestNode,
+    g: &'a TestGraph,
+) -> TestResult<impl Iterator<Item = (usize, &'a TestNode)>> {
+    Ok(g.get_outgoing(n.get_id())?
+        .map(|e| (e.get_type(), g.get_node(e.get_target()).unwrap())))
+}
+
+pub type SymmetricTestGraph = TypedGraph<usize, usize, SymmetricTestSchema>;
+
+/// Same as the TestSchema but edges of type 1 are mirrored
+///
+/// The mirrored edge is given the bitwise negated id of the original edge
+#[derive(Default, Clone, Debug)]
+pub struct SymmetricTestSchema(pub TestSchema);
+
+impl SchemaExt<usize, usize> for SymmetricTestSchema {
+    type N = GenericNode<usize, usize>;
+    type E = GenericEdge<usize, usize>;
+
+    fn name(&self) -> String {
+        "SymmetricTestSchema".to_string()
+    }
+
+    fn allow_node(&self, node_ty: usize) -> Result<(), DisAllowedNode> {
+        SchemaExt::<usize, usize>::allow_node(&self.0, node_ty)
+    }
+
+    fn allow_edge(
+        &self,
+        outgoing_edge_count: usize,
+        incoming_edge_count: usize,
+        edge_ty: usize,
+        source: usize,
+        target: usize,
+    ) -> Result<(), DisAllowedEdge> {
+        SchemaExt::<usize, usize>::allow_edge(
+            &self.0,
+            outgoing_edge_count,
+            incoming_edge_count,
+            edge_ty,
+            source,
+            target,
+        )
+    }
+
+    fn symmetric_edge(&self, edge_ty: usize) -> Option<ReverseSpec<Self::E>> {
+        (edge_ty == 1).then(|| ReverseSpec {
+            reverse_edge: Box::new(|e: &Self::E| {
+                let id: usize = e.get_id();
+                GenericEdge::from((!id, e.get_type()))
+            }),
+        })
+    }
+}
+
+pub type OrderedIncomingTestGraph = TypedGraph<usize, usize, OrderedIncomingTestSchema>;
+
+/// Same as the TestSchema but the order of the incoming edges is kept
+#[derive(Default, Clone, Debug)]
+pub struct OrderedIncomingTestSchema(pub TestSchema);
+
+impl SchemaExt<usize, usize> for OrderedIncomingTestSchema {
+    type N = GenericNode<usize, usize>;
+    type E = GenericEdge<usize, usize>;
+
+    const ORDERED_INCOMING: bool = true;
+
+    fn name(&self) -> String {
+        "OrderedIncomingTestSchema".to_string()
+    }
+
+    fn allow_node(&self, node_ty: usize) -> Result<(), DisAllowedNode> {
+        SchemaExt::<usize, usize>::allow_node(&self.0, node_ty)
+    }
+
+    fn allow_edge(
+        &self,
+        outgoing_edge_count: usize,
+        incoming_edge_count: usize,
+        edge_ty: usize,
+        source: usize,
+        target: usize,
+    ) -> Result<(), DisAllowedEdge> {
+        SchemaExt::<usize, usize>::allow_edge(
+            &self.0,
+            outgoing_edge_count,
+            incoming_edge_count,
+            edge_ty,
+            source,
+            target,
+        )
+    }
+}
+
+pub type VariantTestGraph = TypedGraph<usize, usize, VariantTestSchema>;
+
+/// Schema where the nodes and edges can be downcast into one of two concrete types
+#[derive(Default, Clone, Debug)]
+pub struct VariantTestSchema;
+
+impl SchemaExt<usize, usize> for VariantTestSchema {
+    type N = VariantNode;
+    type E = VariantEdge;
+
+    fn name(&self) -> String {
+        "VariantTestSchema".to_string()
+    }
+
+    fn allow_node(&self, _node_ty: usize) -> Result<(), DisAllowedNode> {
+        Ok(())
+    }
+
+    fn allow_edge(
+        &self,
+        _outgoing_edge_count: usize,
+        _incoming_edge_count: usize,
+        _edge_ty: usize,
+        _source: usize,
+        _target: usize,
+    ) -> Result<(), DisAllowedEdge> {
+        Ok(())
+    }
+}
+
+/// Node holding a number, with type 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberNode {
+    pub id: usize,
+    pub value: usize,
+}
+
+/// Node holding a name, with type 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameNode {
+    pub id: usize,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariantNode {
+    Number(NumberNode),
+    Name(NameNode),
+}
+
+impl NodeExt<usize> for VariantNode {}
+
+impl Id<usize> for VariantNode {
+    fn get_id(&self) -> usize {
+        match self {
+            VariantNode::Number(n) => n.id,
+            VariantNode::Name(n) => n.id,
+        }
+    }
+
+    fn set_id(&mut self, id: usize) {
+        match self {
+            VariantNode::Number(n) => n.id = id,
+            VariantNode::Name(n) => n.id = id,
+        }
+    }
+}
+
+impl PartialEq<usize> for VariantNode {
+    fn eq(&self, other: &usize) -> bool {
+        &self.get_type() == other
+    }
+}
+
+impl Typed for VariantNode {
+    type Type = usize;
+
+    fn get_type(&self) -> usize {
+        match self {
+            VariantNode::Number(_) => 0,
+            VariantNode::Name(_) => 1,
+        }
+    }
+}
+
+/// Edge holding a weight, with type 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightEdge {
+    pub id: usize,
+    pub weight: usize,
+}
+
+/// Edge holding a label, with type 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelEdge {
+    pub id: usize,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariantEdge {
+    Weight(WeightEdge),
+    Label(LabelEdge),
+}
+
+impl EdgeExt<usize> for VariantEdge {}
+
+impl Id<usize> for VariantEdge {
+    fn get_id(&self) -> usize {
+        match self {
+            VariantEdge::Weight(e) => e.id,
+            VariantEdge::Label(e) => e.id,
+        }
+    }
+
+    fn set_id(&mut self, id: usize) {
+        match self {
+            VariantEdge::Weight(e) => e.id = id,
+            VariantEdge::Label(e) => e.id = id,
+        }
+    }
+}
+
+impl PartialEq<usize> for VariantEdge {
+    fn eq(&self, other: &usize) -> bool {
+        &self.get_type() == other
+    }
+}
+
+impl Typed for VariantEdge {
+    type Type = usize;
+
+    fn get_type(&self) -> usize {
+        match self {
+            VariantEdge::Weight(_) => 0,
+            VariantEdge::Label(_) => 1,
+        }
+    }
+}
+
+macro_rules! variant_impl {
+    ($container:ident: $($variant:ident($node:ident) = $ty:literal;)*) => {$(
+        impl PartialEq<usize> for $node {
+            fn eq(&self, other: &usize) -> bool {
+                other == &$ty
+            }
+        }
+
+        impl Typed for $node {
+            type Type = usize;
+
+            fn get_type(&self) -> usize {
+                $ty
+            }
+        }
+
+        impl<'b, S> Downcast<'b, usize, usize, &'b $node, S> for $container
+        where
+            S: SchemaExt<usize, usize>,
+        {
+            fn downcast<'a: 'b>(&'a self) -> SchemaResult<&'b $node, usize, usize, S> {
+                match self {
+                    $container::$variant(n) => Ok(n),
+                    n => Err(TypedError::DownCastFailed(
+                        stringify!($node).to_string(),
+                        n.get_type().to_string(),
+                    )),
+                }
+            }
+        }
+
+        impl<'b, S> DowncastMut<'b, usize, usize, &'b mut $node, S> for $container
+        where
+            S: SchemaExt<usize, usize>,
+        {
+            fn downcast_mut<'a: 'b>(&'a mut self) -> SchemaResult<&'b mut $node, usize, usize, S> {
+                match self {
+                    $container::$variant(n) => Ok(n),
+                    n => Err(TypedError::DownCastFailed(
+                        stringify!($node).to_string(),
+                        n.get_type().to_string(),
+                    )),
+                }
+            }
+        }
+    )*};
+}
+
+variant_impl! {
+    VariantNode:
+    Number(NumberNode) = 0;
+    Name(NameNode) = 1;
+}
+
+variant_impl! {
+    VariantEdge:
+    Weight(WeightEdge) = 0;
+    Label(LabelEdge) = 1;
+}
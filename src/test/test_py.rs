@@ -122,18 +122,8 @@ impl Dummy<Faker> for TestProject {
                     })
                     .collect();
                 if let Some((source, target, edge_type)) = possible_edge_types.iter().choose(rng) {
-                    let source_id = g
-                        .nodes()
-                        .filter(|n| &n.1 == source)
-                        .choose(rng)
-                        .map(|n| n.0)
-                        .unwrap();
-                    let target_id = g
-                        .nodes()
-                        .filter(|n| &n.1 == target)
-                        .choose(rng)
-                        .map(|n| n.0)
-                        .unwrap();
+                    let source_id = g.nodes_of_type(*source).choose(rng).unwrap();
+                    let target_id = g.nodes_of_type(*target).choose(rng).unwrap();
 
                     let add_edge = Action::AddEdge {
                         id: next_edge_id,
@@ -44,11 +44,13 @@ pub enum TypedError<NK, EK, NT, ET> {
     #[error("Failed to get edge ({0:?})")]
     MissingEdge(EK),
 
-    #[error("Invalid edge type {0} from {1} to {2} due to {3:?}")]
-    InvalidEdgeType(ET, NT, NT, DisAllowedEdge),
+    /// The last field is the name of the schema which rejected the edge
+    #[error("Invalid edge type {0} from {1} to {2} due to {3} in schema {4}")]
+    InvalidEdgeType(ET, NT, NT, DisAllowedEdge, String),
 
-    #[error("Invalid node type {0} due to {1:?}")]
-    InvalidNodeType(NT, DisAllowedNode),
+    /// The last field is the name of the schema which rejected the node
+    #[error("Invalid node type {0} due to {1:?} in schema {2}")]
+    InvalidNodeType(NT, DisAllowedNode, String),
 
     #[error("The graph has entered an invalid state")]
     InvalidInternalState,
@@ -71,14 +73,69 @@ pub enum TypedError<NK, EK, NT, ET> {
     #[error("Failed to move {0:?} to {1:?} since they do not have the same source")]
     InvalidEdgeMove(EK, EK),
 
+    #[error("Tried to get mutable access to node ({0:?}) more than once at the same time")]
+    AliasedNode(NK),
+
+    #[error("The new outgoing edge order of {0:?} does not match its current outgoing edges")]
+    InvalidEdgeOrder(NK),
+
+    #[error("Edge {0:?} can not be reversed due to {1}")]
+    ReversedEdgeNotAllowed(EK, DisAllowedEdge),
+
+    #[error("The walker exceeded the limit of {0} branches")]
+    BranchLimitExceeded(usize),
+
+    #[error("Found a negative cycle going through {0:?}")]
+    NegativeCycle(NK),
+
+    #[error("Node {0:?} already exists with type {1} which can not be changed to {2}")]
+    NodeTypeChanged(NK, NT, NT),
+
     #[error("Expected for atleast one edge of type {2:?} to be connected to {0:?}({1:?})")]
     InvalidLowerBound(NK, NT, String),
 
-    #[cfg(test)]
+    #[cfg(all(test, feature = "serde"))]
     #[error(transparent)]
     SerdeJsonError(#[from] serde_json::Error),
 }
 
+pub type GraphIoResult<T, NK, EK, S> = Result<
+    T,
+    GraphIoError<
+        NK,
+        EK,
+        <<S as SchemaExt<NK, EK>>::N as Typed>::Type,
+        <<S as SchemaExt<NK, EK>>::E as Typed>::Type,
+    >,
+>;
+
+/// Errors produced while reading or writing a graph
+///
+/// This allows helpers for different formats to share a single result type,
+/// while TypedError is kept free of any format specific errors
+#[derive(Error, Debug)]
+pub enum GraphIoError<NK, EK, NT, ET> {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// The data could not be encoded or decoded by the format
+    #[error(transparent)]
+    Format(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error(transparent)]
+    TypedError(#[from] TypedError<NK, EK, NT, ET>),
+}
+
+impl<NK, EK, NT, ET> GraphIoError<NK, EK, NT, ET> {
+    /// Wrap an error from a format such as serde_json or bincode
+    pub fn format<E>(e: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        GraphIoError::Format(Box::new(e))
+    }
+}
+
 impl<NK, EK, NT, ET> TypedError<NK, EK, NT, ET> {
     pub fn map<NK1, EK1, NT1, ET1, NKF, EKF, NTF, ETF>(
         self,
@@ -102,10 +159,10 @@ impl<NK, EK, NT, ET> TypedError<NK, EK, NT, ET> {
             TypedError::EdgeIdMissing(a) => TypedError::EdgeIdMissing(ek_map(a)),
             TypedError::MissingNode(a) => TypedError::MissingNode(nk_map(a)),
             TypedError::MissingEdge(a) => TypedError::MissingEdge(ek_map(a)),
-            TypedError::InvalidEdgeType(a, b, c, e) => {
-                TypedError::InvalidEdgeType(et_map(a), nt_map(b), nt_map(c), e)
+            TypedError::InvalidEdgeType(a, b, c, e, s) => {
+                TypedError::InvalidEdgeType(et_map(a), nt_map(b), nt_map(c), e, s)
             }
-            TypedError::InvalidNodeType(a, e) => TypedError::InvalidNodeType(nt_map(a), e),
+            TypedError::InvalidNodeType(a, e, s) => TypedError::InvalidNodeType(nt_map(a), e, s),
             TypedError::InvalidInternalState => TypedError::InvalidInternalState,
             TypedError::DownCastFailed(a, b) => TypedError::DownCastFailed(a, b),
             TypedError::InconsistentNodeIds(a, b) => {
@@ -115,11 +172,126 @@ impl<NK, EK, NT, ET> TypedError<NK, EK, NT, ET> {
                 TypedError::InconsistentEdgeIds(ek_map(a), ek_map(b))
             }
             TypedError::InvalidEdgeMove(a, b) => TypedError::InvalidEdgeMove(ek_map(a), ek_map(b)),
+            TypedError::AliasedNode(a) => TypedError::AliasedNode(nk_map(a)),
+            TypedError::InvalidEdgeOrder(a) => TypedError::InvalidEdgeOrder(nk_map(a)),
             TypedError::MissingNodeKey(a) => TypedError::MissingNodeKey(a),
             TypedError::MissingEdgeKey(a) => TypedError::MissingEdgeKey(a),
+            TypedError::ReversedEdgeNotAllowed(a, e) => {
+                TypedError::ReversedEdgeNotAllowed(ek_map(a), e)
+            }
+            TypedError::BranchLimitExceeded(a) => TypedError::BranchLimitExceeded(a),
+            TypedError::NegativeCycle(a) => TypedError::NegativeCycle(nk_map(a)),
+            TypedError::NodeTypeChanged(a, b, c) => {
+                TypedError::NodeTypeChanged(nk_map(a), nt_map(b), nt_map(c))
+            }
             TypedError::InvalidLowerBound(a, b, s) => TypedError::InvalidLowerBound(nk_map(a), nt_map(b), s),
-            #[cfg(test)]
+            #[cfg(all(test, feature = "serde"))]
             TypedError::SerdeJsonError(a) => TypedError::SerdeJsonError(a),
         }
     }
 }
+
+/// Position of the variant in the TypedError enum
+///
+/// The match has no wildcard, so adding a variant fails to compile until it is also added to typed_error_map_test
+#[cfg(test)]
+fn variant_index<NK, EK, NT, ET>(e: &TypedError<NK, EK, NT, ET>) -> usize {
+    match e {
+        TypedError::NodeKeyRemoved(_) => 0,
+        TypedError::EdgeKeyRemoved(_) => 1,
+        TypedError::NodeIdCollision(_) => 2,
+        TypedError::EdgeIdCollision(_) => 3,
+        TypedError::NodeIdMissing(_) => 4,
+        TypedError::EdgeIdMissing(_) => 5,
+        TypedError::MissingNode(_) => 6,
+        TypedError::MissingEdge(_) => 7,
+        TypedError::InvalidEdgeType(..) => 8,
+        TypedError::InvalidNodeType(..) => 9,
+        TypedError::InvalidInternalState => 10,
+        TypedError::DownCastFailed(..) => 11,
+        TypedError::InconsistentNodeIds(..) => 12,
+        TypedError::InconsistentEdgeIds(..) => 13,
+        TypedError::MissingNodeKey(_) => 14,
+        TypedError::MissingEdgeKey(_) => 15,
+        TypedError::InvalidEdgeMove(..) => 16,
+        TypedError::AliasedNode(_) => 17,
+        TypedError::InvalidEdgeOrder(_) => 18,
+        TypedError::ReversedEdgeNotAllowed(..) => 19,
+        TypedError::BranchLimitExceeded(_) => 20,
+        TypedError::NegativeCycle(_) => 21,
+        TypedError::NodeTypeChanged(..) => 22,
+        TypedError::InvalidLowerBound(..) => 23,
+        #[cfg(all(test, feature = "serde"))]
+        TypedError::SerdeJsonError(_) => 24,
+    }
+}
+
+#[test]
+fn typed_error_map_test() {
+    let errors: Vec<TypedError<usize, usize, usize, usize>> = vec![
+        TypedError::NodeKeyRemoved(1),
+        TypedError::EdgeKeyRemoved(2),
+        TypedError::NodeIdCollision(3),
+        TypedError::EdgeIdCollision(4),
+        TypedError::NodeIdMissing(5),
+        TypedError::EdgeIdMissing(6),
+        TypedError::MissingNode(7),
+        TypedError::MissingEdge(8),
+        TypedError::InvalidEdgeType(1, 2, 3, DisAllowedEdge::SelfLoop, "A".to_string()),
+        TypedError::InvalidNodeType(4, DisAllowedNode::InvalidType, "B".to_string()),
+        TypedError::InvalidInternalState,
+        TypedError::DownCastFailed("C".to_string(), "D".to_string()),
+        TypedError::InconsistentNodeIds(1, 2),
+        TypedError::InconsistentEdgeIds(3, 4),
+        TypedError::MissingNodeKey(NodeKey::default()),
+        TypedError::MissingEdgeKey(EdgeKey::default()),
+        TypedError::InvalidEdgeMove(5, 6),
+        TypedError::AliasedNode(7),
+        TypedError::InvalidEdgeOrder(8),
+        TypedError::ReversedEdgeNotAllowed(9, DisAllowedEdge::InvalidType),
+        TypedError::BranchLimitExceeded(10),
+        TypedError::NegativeCycle(11),
+        TypedError::NodeTypeChanged(12, 5, 6),
+        TypedError::InvalidLowerBound(1, 2, "E".to_string()),
+        #[cfg(all(test, feature = "serde"))]
+        TypedError::SerdeJsonError(serde_json::from_str::<usize>("").unwrap_err()),
+    ];
+
+    // Every variant is part of the test
+    let indices: Vec<usize> = errors.iter().map(variant_index).collect();
+    let expected: Vec<usize> = (0..errors.len()).collect();
+    assert_eq!(indices, expected);
+
+    for e in errors {
+        let index = variant_index(&e);
+        let before = format!("{:?}", e);
+
+        // Mapping with identity keeps every field
+        let e = e.map(|nk| nk, |ek| ek, |nt| nt, |et| et);
+        assert_eq!(format!("{:?}", e), before);
+
+        // Mapping the types keeps the variant and the schema name
+        let e = e.map(
+            |nk| nk,
+            |ek| ek,
+            |nt| format!("N{nt}"),
+            |et| format!("E{et}"),
+        );
+        assert_eq!(variant_index(&e), index);
+        match e {
+            TypedError::InvalidEdgeType(et, nt1, nt2, _, name) => {
+                assert_eq!(
+                    (et, nt1, nt2, name.as_str()),
+                    ("E1".into(), "N2".into(), "N3".into(), "A")
+                )
+            }
+            TypedError::InvalidNodeType(nt, _, name) => {
+                assert_eq!((nt, name.as_str()), ("N4".into(), "B"))
+            }
+            TypedError::NodeTypeChanged(_, old, new) => {
+                assert_eq!((old, new), ("N5".into(), "N6".into()))
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,414 @@
+//! Derive macros for the node and edge weights of a typed_graph TypedGraph
+//!
+//! The macros are reexported by typed_graph when the `derive` feature is enabled.
+//!
+//! Deriving on a struct implements `Id`, `Typed`, `PartialEq` with the type and `NodeExt`/`EdgeExt`.
+//! The struct must have a field containing the id and be given the variant of the type enum it belongs to
+//! ```ignore
+//! #[derive(Debug, Clone, GraphNode)]
+//! #[graph(id = id, type = NodeType::A)]
+//! pub struct A<K: Key> {
+//!     id: K,
+//!     name: String,
+//! }
+//! ```
+//! The id field defaults to `id` and can be left out.
+//! A struct used both as a node and an edge derives one of them and implements the other marker trait by hand.
+//!
+//! Deriving on an enum where every variant wraps a single weight implements the same traits by forwarding to the variants.
+//! It also implements `From` for each of the wrapped weights together with `Downcast` and `DowncastMut` to get them back
+//! ```ignore
+//! #[derive(Debug, Clone, GraphNode)]
+//! #[graph(type = NodeType)]
+//! pub enum Node<K: Key> {
+//!     A(A<K>),
+//!     B(B<K>),
+//! }
+//! ```
+//! The key used for the ids is the type parameter of the enum,
+//! if the enum does not have exactly one type parameter it must be given using `#[graph(key = ...)]`
+//!
+//! The `schema!` macro generates a static schema from a list of allowed edges, see its documentation for details
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DataEnum, DataStruct, DeriveInput, Error, Fields, Ident,
+    Path, Result, Type,
+};
+
+mod schema;
+
+/// Generate a zero sized schema together with the node and edge enums it uses
+///
+/// The schema is given the weights of the nodes and edges along with the edges it allows
+/// ```ignore
+/// schema! {
+///     pub struct Schema {
+///         nodes: Node<K> {
+///             A(A<K>),
+///             B(B<K>),
+///         },
+///         edges: Edge<K> {
+///             AB(AB<K>),
+///         },
+///         rules: {
+///             A -> AB -> B,
+///         },
+///     }
+/// }
+/// ```
+/// This creates the `NodeType` and `EdgeType` enums with a variant for every node and edge,
+/// the `Node` and `Edge` enums deriving GraphNode and GraphEdge and the schema implementing `SchemaExt`.
+/// The weights must derive GraphNode or GraphEdge using the generated types, such as `#[graph(type = NodeType::A)]`.
+///
+/// Every node is allowed and an edge is only allowed between the types given by one of the rules
+#[proc_macro]
+pub fn schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as schema::SchemaInput);
+    schema::expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implement the traits needed by node weights
+#[proc_macro_derive(GraphNode, attributes(graph))]
+pub fn derive_graph_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, WeightKind::Node)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implement the traits needed by edge weights
+#[proc_macro_derive(GraphEdge, attributes(graph))]
+pub fn derive_graph_edge(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, WeightKind::Edge)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Clone, Copy)]
+enum WeightKind {
+    Node,
+    Edge,
+}
+
+impl WeightKind {
+    fn name(self) -> &'static str {
+        match self {
+            WeightKind::Node => "GraphNode",
+            WeightKind::Edge => "GraphEdge",
+        }
+    }
+
+    /// The marker trait implemented by the weight
+    fn ext_trait(self) -> TokenStream2 {
+        match self {
+            WeightKind::Node => quote!(::typed_graph::NodeExt),
+            WeightKind::Edge => quote!(::typed_graph::EdgeExt),
+        }
+    }
+}
+
+/// The content of the #[graph(...)] attributes
+#[derive(Default)]
+struct GraphAttr {
+    id: Option<Ident>,
+    key: Option<Type>,
+    ty: Option<Path>,
+}
+
+impl GraphAttr {
+    fn parse(input: &DeriveInput) -> Result<Self> {
+        let mut attr = GraphAttr::default();
+        for a in input.attrs.iter().filter(|a| a.path().is_ident("graph")) {
+            a.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    attr.id = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("key") {
+                    attr.key = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("type") {
+                    attr.ty = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `id`, `key` or `type`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(attr)
+    }
+}
+
+fn expand(input: DeriveInput, kind: WeightKind) -> Result<TokenStream2> {
+    let attr = GraphAttr::parse(&input)?;
+    let ty = attr.ty.clone().ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            format!("{} requires a #[graph(type = ...)] attribute", kind.name()),
+        )
+    })?;
+
+    match &input.data {
+        Data::Struct(data) => expand_struct(&input, data, attr.id, ty, kind),
+        Data::Enum(data) => expand_enum(&input, data, attr.key, ty, kind),
+        Data::Union(_) => Err(Error::new(
+            Span::call_site(),
+            format!("{} can not be derived for unions", kind.name()),
+        )),
+    }
+}
+
+/// Implement the traits for a single weight where the type is a variant of the type enum
+fn expand_struct(
+    input: &DeriveInput,
+    data: &DataStruct,
+    id: Option<Ident>,
+    variant: Path,
+    kind: WeightKind,
+) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let ext = kind.ext_trait();
+
+    let id = id.unwrap_or_else(|| format_ident!("id"));
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            name,
+            format!(
+                "{} can only be derived for structs with named fields",
+                kind.name()
+            ),
+        ));
+    };
+    let key = fields
+        .named
+        .iter()
+        .find(|field| field.ident.as_ref() == Some(&id))
+        .map(|field| &field.ty)
+        .ok_or_else(|| Error::new_spanned(&id, format!("no field named `{}`", id)))?;
+
+    // The type of the weight is the enum which the variant belongs to
+    if variant.segments.len() < 2 {
+        return Err(Error::new_spanned(
+            &variant,
+            "expected the variant of the type such as `NodeType::A`",
+        ));
+    }
+    let ty = Path {
+        leading_colon: variant.leading_colon,
+        segments: variant
+            .segments
+            .iter()
+            .take(variant.segments.len() - 1)
+            .cloned()
+            .collect(),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::typed_graph::Id<#key> for #name #ty_generics #where_clause {
+            fn get_id(&self) -> #key {
+                ::core::clone::Clone::clone(&self.#id)
+            }
+
+            fn set_id(&mut self, new_id: #key) {
+                self.#id = new_id;
+            }
+        }
+
+        impl #impl_generics ::typed_graph::Typed for #name #ty_generics #where_clause {
+            type Type = #ty;
+
+            fn get_type(&self) -> Self::Type {
+                #variant
+            }
+        }
+
+        impl #impl_generics ::core::cmp::PartialEq<#ty> for #name #ty_generics #where_clause {
+            fn eq(&self, other: &#ty) -> bool {
+                other == &#variant
+            }
+        }
+
+        impl #impl_generics #ext<#key> for #name #ty_generics #where_clause {}
+    })
+}
+
+/// Implement the traits for an enum by forwarding to the weights wrapped by its variants
+fn expand_enum(
+    input: &DeriveInput,
+    data: &DataEnum,
+    key: Option<Type>,
+    ty: Path,
+    kind: WeightKind,
+) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let ext = kind.ext_trait();
+
+    let key = match key {
+        Some(key) => key,
+        None => {
+            let mut params = input.generics.type_params();
+            match (params.next(), params.next()) {
+                (Some(param), None) => {
+                    let ident = &param.ident;
+                    parse_quote!(#ident)
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        name,
+                        "the key can not be inferred, use #[graph(key = ...)]",
+                    ))
+                }
+            }
+        }
+    };
+
+    if data.variants.is_empty() {
+        return Err(Error::new_spanned(
+            name,
+            format!(
+                "{} can not be derived for enums without variants",
+                kind.name()
+            ),
+        ));
+    }
+
+    let mut variants = Vec::new();
+    let mut weights = Vec::new();
+    for variant in &data.variants {
+        match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                variants.push(&variant.ident);
+                weights.push(&fields.unnamed[0].ty);
+            }
+            _ => {
+                return Err(Error::new_spanned(
+                    variant,
+                    "expected a variant containing a single weight",
+                ))
+            }
+        }
+    }
+    let variant_names = variants.iter().map(|variant| variant.to_string());
+
+    // Downcasting is implemented for any schema using the enum
+    // so the generics are extended with the schema and the key of the other kind of weight
+    let mut downcast_generics = input.generics.clone();
+    downcast_generics.params.insert(0, parse_quote!('__b));
+    downcast_generics.params.push(parse_quote!(__K));
+    downcast_generics.params.push(parse_quote!(__S));
+    let predicates = &mut downcast_generics.make_where_clause().predicates;
+    predicates.push(parse_quote!(__K: ::typed_graph::Key));
+    let (nk, ek) = match kind {
+        WeightKind::Node => {
+            predicates.push(parse_quote!(
+                __S: ::typed_graph::SchemaExt<#key, __K, N = #name #ty_generics>
+            ));
+            (quote!(#key), quote!(__K))
+        }
+        WeightKind::Edge => {
+            predicates.push(parse_quote!(
+                __S: ::typed_graph::SchemaExt<__K, #key, E = #name #ty_generics>
+            ));
+            (quote!(__K), quote!(#key))
+        }
+    };
+    let (downcast_impl_generics, _, downcast_where_clause) = downcast_generics.split_for_impl();
+
+    let downcasts = variants
+        .iter()
+        .zip(&weights)
+        .zip(variant_names)
+        .map(|((variant, weight), variant_name)| {
+            quote! {
+                impl #impl_generics ::core::convert::From<#weight> for #name #ty_generics #where_clause {
+                    fn from(weight: #weight) -> Self {
+                        #name::#variant(weight)
+                    }
+                }
+
+                impl #downcast_impl_generics ::typed_graph::Downcast<'__b, #nk, #ek, &'__b #weight, __S>
+                    for #name #ty_generics #downcast_where_clause
+                {
+                    #[allow(unreachable_patterns)]
+                    fn downcast<'__a: '__b>(
+                        &'__a self,
+                    ) -> ::typed_graph::SchemaResult<&'__b #weight, #nk, #ek, __S> {
+                        match self {
+                            #name::#variant(weight) => ::core::result::Result::Ok(weight),
+                            weight => ::core::result::Result::Err(
+                                ::typed_graph::TypedError::DownCastFailed(
+                                    ::std::string::ToString::to_string(#variant_name),
+                                    ::std::string::ToString::to_string(
+                                        &::typed_graph::Typed::get_type(weight),
+                                    ),
+                                ),
+                            ),
+                        }
+                    }
+                }
+
+                impl #downcast_impl_generics ::typed_graph::DowncastMut<'__b, #nk, #ek, &'__b mut #weight, __S>
+                    for #name #ty_generics #downcast_where_clause
+                {
+                    #[allow(unreachable_patterns)]
+                    fn downcast_mut<'__a: '__b>(
+                        &'__a mut self,
+                    ) -> ::typed_graph::SchemaResult<&'__b mut #weight, #nk, #ek, __S> {
+                        match self {
+                            #name::#variant(weight) => ::core::result::Result::Ok(weight),
+                            weight => ::core::result::Result::Err(
+                                ::typed_graph::TypedError::DownCastFailed(
+                                    ::std::string::ToString::to_string(#variant_name),
+                                    ::std::string::ToString::to_string(
+                                        &::typed_graph::Typed::get_type(weight),
+                                    ),
+                                ),
+                            ),
+                        }
+                    }
+                }
+            }
+        });
+
+    Ok(quote! {
+        impl #impl_generics ::typed_graph::Id<#key> for #name #ty_generics #where_clause {
+            fn get_id(&self) -> #key {
+                match self {
+                    #(#name::#variants(weight) => ::typed_graph::Id::<#key>::get_id(weight)),*
+                }
+            }
+
+            fn set_id(&mut self, new_id: #key) {
+                match self {
+                    #(#name::#variants(weight) => ::typed_graph::Id::<#key>::set_id(weight, new_id)),*
+                }
+            }
+        }
+
+        impl #impl_generics ::typed_graph::Typed for #name #ty_generics #where_clause {
+            type Type = #ty;
+
+            fn get_type(&self) -> Self::Type {
+                match self {
+                    #(#name::#variants(weight) => ::typed_graph::Typed::get_type(weight)),*
+                }
+            }
+        }
+
+        impl #impl_generics ::core::cmp::PartialEq<#ty> for #name #ty_generics #where_clause {
+            fn eq(&self, other: &#ty) -> bool {
+                match self {
+                    #(#name::#variants(weight) => ::core::cmp::PartialEq::<#ty>::eq(weight, other)),*
+                }
+            }
+        }
+
+        impl #impl_generics #ext<#key> for #name #ty_generics #where_clause {}
+
+        #(#downcasts)*
+    })
+}
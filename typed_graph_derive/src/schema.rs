@@ -0,0 +1,258 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    braced, parse_quote, Attribute, Error, Generics, Ident, Result, Token, Variant, Visibility,
+};
+
+/// The input of the schema macro
+pub(crate) struct SchemaInput {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    nodes: Container,
+    edges: Container,
+    rules: Vec<Rule>,
+}
+
+/// An enum containing the weights of all the nodes or edges
+struct Container {
+    name: Ident,
+    generics: Generics,
+    variants: Punctuated<Variant, Token![,]>,
+}
+
+/// An edge type which is allowed between two node types
+struct Rule {
+    source: Ident,
+    edge: Ident,
+    target: Ident,
+}
+
+impl Parse for SchemaInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let name: Ident = input.parse()?;
+
+        let content;
+        braced!(content in input);
+
+        let mut nodes = None;
+        let mut edges = None;
+        let mut rules = None;
+        while !content.is_empty() {
+            let label: Ident = content.parse()?;
+            content.parse::<Token![:]>()?;
+
+            match label.to_string().as_str() {
+                "nodes" if nodes.is_none() => nodes = Some(content.parse()?),
+                "edges" if edges.is_none() => edges = Some(content.parse()?),
+                "rules" if rules.is_none() => {
+                    let rule_content;
+                    braced!(rule_content in content);
+                    let parsed = rule_content.parse_terminated(Rule::parse, Token![,])?;
+                    rules = Some(parsed.into_iter().collect());
+                }
+                "nodes" | "edges" | "rules" => {
+                    return Err(Error::new_spanned(&label, format!("duplicate `{}`", label)))
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        &label,
+                        "expected `nodes`, `edges` or `rules`",
+                    ))
+                }
+            }
+
+            if content.is_empty() {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+
+        Ok(SchemaInput {
+            nodes: nodes.ok_or_else(|| Error::new_spanned(&name, "missing `nodes`"))?,
+            edges: edges.ok_or_else(|| Error::new_spanned(&name, "missing `edges`"))?,
+            rules: rules.unwrap_or_default(),
+            attrs,
+            vis,
+            name,
+        })
+    }
+}
+
+impl Parse for Container {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let generics = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let variants = content.parse_terminated(Variant::parse, Token![,])?;
+
+        Ok(Container {
+            name,
+            generics,
+            variants,
+        })
+    }
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let source = input.parse()?;
+        input.parse::<Token![->]>()?;
+        let edge = input.parse()?;
+        input.parse::<Token![->]>()?;
+        let target = input.parse()?;
+
+        Ok(Rule {
+            source,
+            edge,
+            target,
+        })
+    }
+}
+
+pub(crate) fn expand(input: SchemaInput) -> Result<TokenStream2> {
+    let SchemaInput {
+        attrs,
+        vis,
+        name,
+        nodes,
+        edges,
+        rules,
+    } = input;
+
+    let node_type = format_ident!("{}Type", nodes.name);
+    let edge_type = format_ident!("{}Type", edges.name);
+    let node_enum = expand_container(&vis, &nodes, &node_type, quote!(::typed_graph::GraphNode))?;
+    let edge_enum = expand_container(&vis, &edges, &edge_type, quote!(::typed_graph::GraphEdge))?;
+
+    // The rules may only refer to the declared variants
+    for rule in &rules {
+        for (ident, container) in [
+            (&rule.source, &nodes),
+            (&rule.edge, &edges),
+            (&rule.target, &nodes),
+        ] {
+            if !container.variants.iter().any(|v| &v.ident == ident) {
+                return Err(Error::new_spanned(
+                    ident,
+                    format!("`{}` is not a variant of `{}`", ident, container.name),
+                ));
+            }
+        }
+    }
+
+    let sources = rules.iter().map(|rule| &rule.source);
+    let targets = rules.iter().map(|rule| &rule.target);
+    let edge_types = rules.iter().map(|rule| &rule.edge);
+    let allowed = if rules.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            #((#node_type::#sources, #node_type::#targets, #edge_type::#edge_types))|* => {
+                ::core::result::Result::Ok(())
+            }
+        }
+    };
+
+    let name_str = name.to_string();
+    let node_name = &nodes.name;
+    let edge_name = &edges.name;
+
+    Ok(quote! {
+        #node_enum
+        #edge_enum
+
+        #(#attrs)*
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+        #vis struct #name;
+
+        impl<__NK, __EK> ::typed_graph::SchemaExt<__NK, __EK> for #name
+        where
+            __NK: ::typed_graph::Key,
+            __EK: ::typed_graph::Key,
+        {
+            type N = #node_name<__NK>;
+            type E = #edge_name<__EK>;
+
+            fn name(&self) -> ::std::string::String {
+                ::std::string::ToString::to_string(#name_str)
+            }
+
+            fn allow_node(
+                &self,
+                _node_ty: #node_type,
+            ) -> ::core::result::Result<(), ::typed_graph::DisAllowedNode> {
+                ::core::result::Result::Ok(())
+            }
+
+            #[allow(unreachable_patterns)]
+            fn allow_edge(
+                &self,
+                _outgoing_edge_count: usize,
+                _incoming_edge_count: usize,
+                edge_ty: #edge_type,
+                source: #node_type,
+                target: #node_type,
+            ) -> ::core::result::Result<(), ::typed_graph::DisAllowedEdge> {
+                match (source, target, edge_ty) {
+                    #allowed
+                    _ => ::core::result::Result::Err(::typed_graph::DisAllowedEdge::InvalidType),
+                }
+            }
+        }
+    })
+}
+
+/// Create the type enum and the enum containing the weights
+fn expand_container(
+    vis: &Visibility,
+    container: &Container,
+    type_name: &Ident,
+    derive: TokenStream2,
+) -> Result<TokenStream2> {
+    let name = &container.name;
+
+    // The type parameter is used as the key of the weights
+    let mut generics = container.generics.clone();
+    if generics.params.len() != 1 || generics.type_params().count() != 1 {
+        return Err(Error::new_spanned(
+            name,
+            format!(
+                "`{}` must have a single type parameter used as the key",
+                name
+            ),
+        ));
+    }
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::typed_graph::Key));
+    }
+
+    let variants = &container.variants;
+    let variant_names = variants.iter().map(|variant| &variant.ident);
+
+    Ok(quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #vis enum #type_name {
+            #(#variant_names),*
+        }
+
+        impl ::core::fmt::Display for #type_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::write!(f, "{:?}", self)
+            }
+        }
+
+        #[derive(Debug, Clone, #derive)]
+        #[graph(type = #type_name)]
+        #vis enum #name #generics {
+            #variants
+        }
+    })
+}
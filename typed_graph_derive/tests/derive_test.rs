@@ -0,0 +1,183 @@
+use std::fmt::Display;
+use typed_graph::*;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum WeightType {
+    Small,
+    Large,
+}
+
+impl Display for WeightType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, GraphNode)]
+#[graph(id = key, type = WeightType::Small)]
+struct Small {
+    key: u32,
+    value: u8,
+}
+
+#[derive(Debug, Clone, GraphNode)]
+#[graph(type = WeightType::Large)]
+struct Large<K: Key> {
+    id: K,
+    value: u64,
+}
+
+// The same weight is also used for edges
+impl<K: Key> EdgeExt<K> for Large<K> {}
+
+#[derive(Debug, Clone, GraphNode)]
+#[graph(key = u32, type = WeightType)]
+enum Node {
+    Small(Small),
+    Large(Large<u32>),
+}
+
+#[derive(Debug, Clone, GraphEdge)]
+#[graph(type = WeightType)]
+enum Edge<K: Key> {
+    Large(Large<K>),
+}
+
+#[derive(Default)]
+struct Schema;
+
+impl SchemaExt<u32, u64> for Schema {
+    type N = Node;
+    type E = Edge<u64>;
+
+    fn name(&self) -> String {
+        "Schema".to_string()
+    }
+
+    fn allow_node(&self, _node_ty: WeightType) -> Result<(), DisAllowedNode> {
+        Ok(())
+    }
+
+    fn allow_edge(
+        &self,
+        _outgoing_edge_count: usize,
+        _incoming_edge_count: usize,
+        _edge_ty: WeightType,
+        _source: WeightType,
+        _target: WeightType,
+    ) -> Result<(), DisAllowedEdge> {
+        Ok(())
+    }
+}
+
+#[test]
+fn derive_struct_test() {
+    let mut small = Small { key: 1, value: 2 };
+    assert_eq!(Id::<u32>::get_id(&small), 1);
+    small.set_id(3);
+    assert_eq!(small.key, 3);
+    assert_eq!(small.get_type(), WeightType::Small);
+    assert!(small == WeightType::Small);
+    assert!(small != WeightType::Large);
+
+    let large = Large { id: 5u64, value: 6 };
+    assert_eq!(large.get_id(), 5);
+    assert_eq!(large.get_type(), WeightType::Large);
+}
+
+#[test]
+fn derive_enum_test() -> SchemaResult<(), u32, u64, Schema> {
+    let mut g = TypedGraph::new(Schema);
+    g.add_node(Small { key: 0, value: 1 })?;
+    g.add_node(Large { id: 1, value: 2 })?;
+    g.add_edge(0, 1, Large { id: 7u64, value: 3 })?;
+
+    let node = g.get_node(1)?;
+    assert_eq!(node.get_id(), 1);
+    assert_eq!(node.get_type(), WeightType::Large);
+    assert!(*node == WeightType::Large);
+
+    let small: &Small = g.get_node_downcast(0)?;
+    assert_eq!(small.value, 1);
+    assert!(matches!(
+        g.get_node_downcast::<&Small>(1),
+        Err(TypedError::DownCastFailed(a, b)) if a == "Small" && b == "Large"
+    ));
+
+    let large: &mut Large<u32> = g.get_node_downcast_mut(1)?;
+    large.value = 4;
+    let large: &Large<u32> = g.get_node_downcast(1)?;
+    assert_eq!(large.value, 4);
+
+    let edge: &Large<u64> = g.get_edge_downcast(7)?;
+    assert_eq!(edge.value, 3);
+
+    Ok(())
+}
+
+mod static_schema {
+    use typed_graph::*;
+
+    schema! {
+        /// A schema where people own pets
+        pub struct PetSchema {
+            nodes: Animal<K> {
+                Person(Person<K>),
+                Pet(Pet<K>),
+            },
+            edges: Relation<K> {
+                Owns(Owns<K>),
+            },
+            rules: {
+                Person -> Owns -> Pet,
+            },
+        }
+    }
+
+    #[derive(Debug, Clone, GraphNode)]
+    #[graph(type = AnimalType::Person)]
+    pub struct Person<K: Key> {
+        id: K,
+    }
+
+    #[derive(Debug, Clone, GraphNode)]
+    #[graph(type = AnimalType::Pet)]
+    pub struct Pet<K: Key> {
+        id: K,
+    }
+
+    #[derive(Debug, Clone, GraphEdge)]
+    #[graph(type = RelationType::Owns)]
+    pub struct Owns<K: Key> {
+        id: K,
+    }
+
+    #[test]
+    fn schema_macro_test() -> SchemaResult<(), u32, u64, PetSchema> {
+        assert_eq!(std::mem::size_of::<PetSchema>(), 0);
+        assert_eq!(SchemaExt::<u32, u64>::name(&PetSchema), "PetSchema");
+        assert_eq!(AnimalType::Person.to_string(), "Person");
+
+        let mut g = TypedGraph::new(PetSchema);
+        g.add_node(Person { id: 0 })?;
+        g.add_node(Pet { id: 1 })?;
+        g.add_edge(0, 1, Owns { id: 0 })?;
+
+        // Only the edges given by the rules are allowed
+        assert!(matches!(
+            g.add_edge(1, 0, Owns { id: 1 }),
+            Err(TypedError::InvalidEdgeType(
+                RelationType::Owns,
+                AnimalType::Pet,
+                AnimalType::Person,
+                DisAllowedEdge::InvalidType,
+                _
+            ))
+        ));
+
+        let pet: &Pet<u32> = g.get_node_downcast(1)?;
+        assert_eq!(pet.get_id(), 1);
+
+        Ok(())
+    }
+}